@@ -0,0 +1,361 @@
+#![allow(dead_code)]
+
+use super::{Error, ErrorBuilder, Result};
+use proc_macro2::{Span, TokenStream};
+use syn::parse::Parser;
+
+/// The kind of value that a key of an `AttrSchema` takes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum AttrValueKind {
+    /// No value: `#[attr(skip)]`
+    Flag,
+    /// A string literal: `#[attr(rename = "foo")]`
+    Str,
+    /// An integer literal: `#[attr(max = 10)]`
+    Int,
+    /// `true` or `false`, or only the key for `true`: `#[attr(enabled)]`, `#[attr(enabled = false)]`
+    Bool,
+    /// A path, as-is or in a string: `#[attr(with = my::func)]`, `#[attr(with = "my::func")]`
+    Path,
+    /// Any expression: `#[attr(default = 1 + 2)]`
+    Expr,
+}
+
+/// A key of an `AttrSchema`
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AttrKey {
+    name: String,
+    aliases: Vec<String>,
+    kind: AttrValueKind,
+    required: bool,
+}
+
+impl AttrKey {
+    /// An optional key without aliases
+    pub fn new(name: &str, kind: AttrValueKind) -> Self {
+        Self {
+            name: name.to_string(),
+            aliases: vec![],
+            kind,
+            required: false,
+        }
+    }
+    /// Add another name for the key. Values are stored under the main name.
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+    /// Make the key required or optional
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// The main name of the key
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The other names of the key
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+    /// The main name followed by the aliases
+    pub fn spellings(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+    /// The kind of value of the key
+    pub fn kind(&self) -> AttrValueKind {
+        self.kind
+    }
+    /// Check if the key is required
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+}
+
+/// A value parsed by `AttrSchema`
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum AttrValue {
+    Flag,
+    Str(#[cfg_attr(feature = "serde", serde(with = "super::tokens::serde_syn"))] syn::LitStr),
+    Int(#[cfg_attr(feature = "serde", serde(with = "super::tokens::serde_syn"))] syn::LitInt),
+    Bool(bool),
+    Path(#[cfg_attr(feature = "serde", serde(with = "super::tokens::serde_syn"))] syn::Path),
+    Expr(#[cfg_attr(feature = "serde", serde(with = "super::tokens::serde_syn"))] syn::Expr),
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ParsedKey {
+    key: String,
+    written: String,
+    value: AttrValue,
+    #[cfg_attr(feature = "serde", serde(skip, default = "Span::call_site"))]
+    span: Span,
+}
+
+/// The values of an attribute, parsed by `AttrSchema::parse`. Values are looked up by the main
+/// name of their key, even if they were written with an alias.
+///
+/// With the `serde` feature, the values (and `AttrSchema`) can be serialized, e.g. to cache them
+/// under `OUT_DIR` or to pass them from a build script to the macro. Spans are not serialized,
+/// so errors about deserialized values point at the call site.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AttrValues {
+    values: Vec<ParsedKey>,
+}
+
+impl AttrValues {
+    /// Check if the key was given
+    pub fn has(&self, key: &str) -> bool {
+        self.find(key).is_some()
+    }
+    /// The value of the key
+    pub fn get(&self, key: &str) -> Option<&AttrValue> {
+        self.find(key).map(|parsed| &parsed.value)
+    }
+    /// The span of the key, e.g. for errors about its value
+    pub fn span(&self, key: &str) -> Option<Span> {
+        self.find(key).map(|parsed| parsed.span)
+    }
+    /// The value of a `Str` key
+    pub fn str(&self, key: &str) -> Option<String> {
+        match self.get(key)? {
+            AttrValue::Str(lit) => Some(lit.value()),
+            _ => None,
+        }
+    }
+    /// The value of a `Bool` or `Flag` key, `false` if it wasn't given
+    pub fn bool(&self, key: &str) -> bool {
+        matches!(self.get(key), Some(AttrValue::Flag | AttrValue::Bool(true)))
+    }
+    /// The value of an `Int` key, with an error at the literal if it doesn't fit into `N`
+    pub fn int<N>(&self, key: &str) -> Result<Option<N>>
+    where
+        N: std::str::FromStr,
+        N::Err: std::fmt::Display,
+    {
+        match self.get(key) {
+            Some(AttrValue::Int(lit)) => Ok(Some(lit.base10_parse()?)),
+            _ => Ok(None),
+        }
+    }
+    /// The value of a `Path` key
+    pub fn path(&self, key: &str) -> Option<&syn::Path> {
+        match self.get(key)? {
+            AttrValue::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+    /// The value of an `Expr` key
+    pub fn expr(&self, key: &str) -> Option<&syn::Expr> {
+        match self.get(key)? {
+            AttrValue::Expr(expr) => Some(expr),
+            _ => None,
+        }
+    }
+
+    /// The keys as they were written (including aliases), in order
+    pub fn written_keys(&self) -> Vec<&str> {
+        self.values
+            .iter()
+            .map(|parsed| parsed.written.as_str())
+            .collect()
+    }
+    /// Check if no keys were given
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn find(&self, key: &str) -> Option<&ParsedKey> {
+        self.values.iter().find(|parsed| parsed.key == key)
+    }
+}
+
+/// The keys that an attribute like `#[my_attr(rename = "foo", skip)]` accepts. Unknown keys,
+/// duplicate keys, missing required keys and invalid values are reported together.
+/// ```ignore
+/// let schema = AttrSchema::new("my_attr")
+///     .key(AttrKey::new("rename", AttrValueKind::Str).alias("name"))
+///     .key(AttrKey::new("skip", AttrValueKind::Flag))
+///     .key(AttrKey::new("max", AttrValueKind::Int).required(true));
+/// let values = schema.parse(&field.attrs)?;
+/// let max: u32 = values.int("max")?.unwrap();
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AttrSchema {
+    attr: String,
+    keys: Vec<AttrKey>,
+}
+
+impl AttrSchema {
+    /// A schema without keys for the attribute with the given name
+    pub fn new(attr: &str) -> Self {
+        Self {
+            attr: attr.to_string(),
+            keys: vec![],
+        }
+    }
+    /// Add a key
+    pub fn key(mut self, key: AttrKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// The name of the attribute
+    pub fn attr_name(&self) -> &str {
+        &self.attr
+    }
+    /// The keys, in the order they were added
+    pub fn keys(&self) -> &[AttrKey] {
+        &self.keys
+    }
+    /// The key with the given name or alias
+    pub fn find(&self, name: &str) -> Option<&AttrKey> {
+        self.keys
+            .iter()
+            .find(|key| key.spellings().any(|spelling| spelling == name))
+    }
+
+    /// Parse all attributes with the name of the schema. Attributes with other names are ignored.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "AttrSchema::parse",
+            skip_all,
+            fields(attr = %self.attr)
+        )
+    )]
+    pub fn parse(&self, attrs: &[syn::Attribute]) -> Result<AttrValues> {
+        let mut values = AttrValues::default();
+        let mut errors = Error::builder();
+        let mut span = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident(&self.attr)) {
+            span.get_or_insert_with(|| syn::spanned::Spanned::span(attr));
+            let result =
+                attr.parse_nested_meta(|meta| self.parse_meta(meta, &mut values, &mut errors));
+            if let Err(e) = result {
+                errors.with_error(e);
+            }
+        }
+        self.check_required(&values, span.unwrap_or_else(Span::call_site), &mut errors);
+        errors.ok_or_build()?;
+        Ok(values)
+    }
+
+    /// Parse the arguments of an attribute macro: `#[my_attr(...)]` passes the `...` as `args`
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "AttrSchema::parse_args",
+            skip_all,
+            fields(attr = %self.attr)
+        )
+    )]
+    pub fn parse_args(&self, args: TokenStream) -> Result<AttrValues> {
+        let mut values = AttrValues::default();
+        let mut errors = Error::builder();
+        let parser = syn::meta::parser(|meta| self.parse_meta(meta, &mut values, &mut errors));
+        if let Err(e) = parser.parse2(args) {
+            errors.with_error(e);
+        }
+        self.check_required(&values, Span::call_site(), &mut errors);
+        errors.ok_or_build()?;
+        Ok(values)
+    }
+
+    fn parse_meta(
+        &self,
+        meta: syn::meta::ParseNestedMeta,
+        values: &mut AttrValues,
+        errors: &mut ErrorBuilder,
+    ) -> syn::Result<()> {
+        let written = match meta.path.get_ident() {
+            Some(ident) => ident.to_string(),
+            None => return Err(meta.error("expected a key")),
+        };
+        let span = syn::spanned::Spanned::span(&meta.path);
+        let Some(key) = self.find(&written) else {
+            let expected: Vec<String> = self
+                .keys
+                .iter()
+                .map(|key| format!("`{}`", key.name))
+                .collect();
+            errors.with(
+                span,
+                format!(
+                    "unknown key `{}` in `#[{}]`, expected one of: {}",
+                    written,
+                    self.attr,
+                    expected.join(", ")
+                ),
+            );
+            return skip_value(&meta);
+        };
+        if let Some(previous) = values.find(&key.name) {
+            errors
+                .with(span, format!("duplicate key `{}`", key.name))
+                .with(previous.span, "first given here");
+            return skip_value(&meta);
+        }
+        let value = match key.kind {
+            AttrValueKind::Flag => {
+                if !meta.input.is_empty() && !meta.input.peek(syn::Token![,]) {
+                    errors.with(span, format!("`{}` doesn't take a value", written));
+                    return skip_value(&meta);
+                }
+                AttrValue::Flag
+            }
+            AttrValueKind::Str => AttrValue::Str(meta.value()?.parse()?),
+            AttrValueKind::Int => AttrValue::Int(meta.value()?.parse()?),
+            AttrValueKind::Bool if meta.input.peek(syn::Token![=]) => {
+                AttrValue::Bool(meta.value()?.parse::<syn::LitBool>()?.value)
+            }
+            AttrValueKind::Bool => AttrValue::Bool(true),
+            AttrValueKind::Path => {
+                let input = meta.value()?;
+                if input.peek(syn::LitStr) {
+                    AttrValue::Path(input.parse::<syn::LitStr>()?.parse()?)
+                } else {
+                    AttrValue::Path(input.parse()?)
+                }
+            }
+            AttrValueKind::Expr => AttrValue::Expr(meta.value()?.parse()?),
+        };
+        values.values.push(ParsedKey {
+            key: key.name.clone(),
+            written,
+            value,
+            span,
+        });
+        Ok(())
+    }
+
+    fn check_required(&self, values: &AttrValues, span: Span, errors: &mut ErrorBuilder) {
+        for key in self.keys.iter().filter(|key| key.required) {
+            if !values.has(&key.name) {
+                errors.with(
+                    span,
+                    format!("missing required key `{}` in `#[{}]`", key.name, self.attr),
+                );
+            }
+        }
+    }
+}
+
+/// Consume the value of a key that is reported as an error, so parsing can continue
+fn skip_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.input.parse::<proc_macro2::Group>()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+#![allow(dead_code)]
+
+use super::Result;
+use proc_macro2::Ident;
+
+/// The naming conventions that names can be converted between. The names used in attributes
+/// are the same ones that serde uses for `rename_all`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Case {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `kebab-case`
+    Kebab,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab,
+    /// `camelCase`
+    Camel,
+    /// `PascalCase`
+    Pascal,
+}
+
+impl Case {
+    /// All cases, for error messages and iteration
+    pub const ALL: [Case; 8] = [
+        Case::Lower,
+        Case::Upper,
+        Case::Snake,
+        Case::ScreamingSnake,
+        Case::Kebab,
+        Case::ScreamingKebab,
+        Case::Camel,
+        Case::Pascal,
+    ];
+
+    /// The name of the case as used in attributes, e.g. `"snake_case"`
+    pub fn name(self) -> &'static str {
+        match self {
+            Case::Lower => "lowercase",
+            Case::Upper => "UPPERCASE",
+            Case::Snake => "snake_case",
+            Case::ScreamingSnake => "SCREAMING_SNAKE_CASE",
+            Case::Kebab => "kebab-case",
+            Case::ScreamingKebab => "SCREAMING-KEBAB-CASE",
+            Case::Camel => "camelCase",
+            Case::Pascal => "PascalCase",
+        }
+    }
+    /// Look up a case by its name. See `Case::name`.
+    pub fn from_name(name: &str) -> Option<Case> {
+        Case::ALL.into_iter().find(|case| case.name() == name)
+    }
+    /// Parse a case from a string literal, e.g. from `#[my_attr(rename_all = "snake_case")]`
+    #[cfg(not(feature = "no-syn"))]
+    pub fn from_lit(lit: &syn::LitStr) -> Result<Case> {
+        Case::from_name(&lit.value()).ok_or_else(|| {
+            let names = Case::ALL.map(|case| format!("\"{}\"", case.name()));
+            super::Error::new_spanned(
+                lit,
+                format!("unknown case. Expected one of {}", names.join(", ")),
+            )
+        })
+    }
+
+    /// Convert a name to this case. Acronyms like `HTTPServer` are recognized as a single word.
+    pub fn convert(self, name: &str) -> String {
+        self.convert_with(name, &[])
+    }
+    /// Convert a name to this case, with a list of acronyms that should be treated as single
+    /// words when splitting and keep their spelling in `camelCase` and `PascalCase` output.
+    ///
+    /// ```ignore
+    /// assert_eq!(Case::Snake.convert("HTTPServerURL"), "http_server_url");
+    /// assert_eq!(Case::Snake.convert_with("OAuthToken", &["OAuth"]), "oauth_token");
+    /// assert_eq!(Case::Pascal.convert_with("http_server", &["HTTP"]), "HTTPServer");
+    /// ```
+    pub fn convert_with(self, name: &str, acronyms: &[&str]) -> String {
+        let words = split_words(name, acronyms);
+        match self {
+            Case::Lower => join_mapped(&words, "", str::to_lowercase),
+            Case::Upper => join_mapped(&words, "", str::to_uppercase),
+            Case::Snake => join_mapped(&words, "_", str::to_lowercase),
+            Case::ScreamingSnake => join_mapped(&words, "_", str::to_uppercase),
+            Case::Kebab => join_mapped(&words, "-", str::to_lowercase),
+            Case::ScreamingKebab => join_mapped(&words, "-", str::to_uppercase),
+            Case::Pascal => join_mapped(&words, "", |word| capitalize(word, acronyms)),
+            Case::Camel => {
+                let mut out = words.first().map_or_else(String::new, |w| w.to_lowercase());
+                for word in words.iter().skip(1) {
+                    out.push_str(&capitalize(word, acronyms));
+                }
+                out
+            }
+        }
+    }
+    /// Convert an identifier to this case, keeping its span. Returns an error for cases that
+    /// don't produce valid identifiers (e.g. `kebab-case`).
+    pub fn convert_ident(self, ident: &Ident, acronyms: &[&str]) -> Result<Ident> {
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        super::ident::parse_ident(&self.convert_with(name, acronyms), ident.span())
+    }
+}
+
+/// Split a name into its words. Words are separated by non-alphanumeric characters and by case
+/// changes, where a run of uppercase letters is kept together as an acronym: `HTTPServerURL`
+/// becomes `["HTTP", "Server", "URL"]`.
+///
+/// Any of the given `acronyms` found at the start of a word is taken as a word on its own, which
+/// handles acronyms that are not all uppercase (`OAuth`) or that are directly followed by
+/// another uppercase word.
+pub(crate) fn split_words<'a>(name: &'a str, acronyms: &[&str]) -> Vec<&'a str> {
+    let chars: Vec<(usize, char)> = name.char_indices().collect();
+    let mut words = vec![];
+    let mut start = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        if !c.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                words.push(&name[start..pos]);
+            }
+            i += 1;
+            continue;
+        }
+        if start.is_none() || is_word_start(&chars, i) {
+            if let Some(start) = start.take() {
+                words.push(&name[start..pos]);
+            }
+            let acronym = acronyms
+                .iter()
+                .filter(|acronym| name[pos..].starts_with(**acronym))
+                .map(|acronym| pos + acronym.len())
+                .filter(|&end| !name[end..].starts_with(char::is_lowercase))
+                .max();
+            if let Some(end) = acronym.filter(|&end| end > pos) {
+                words.push(&name[pos..end]);
+                while i < chars.len() && chars[i].0 < end {
+                    i += 1;
+                }
+                continue;
+            }
+            start = Some(pos);
+        }
+        i += 1;
+    }
+    if let Some(start) = start {
+        words.push(&name[start..]);
+    }
+    words
+}
+
+/// Check if a new word starts at `chars[i]`, assuming `chars[i - 1]` is part of a word
+fn is_word_start(chars: &[(usize, char)], i: usize) -> bool {
+    let prev = chars[i - 1].1;
+    let current = chars[i].1;
+    if !current.is_uppercase() {
+        return false;
+    }
+    if !prev.is_uppercase() {
+        // fooBar, foo2Bar
+        return true;
+    }
+    // HTTPServer: the last uppercase letter before a lowercase one starts a new word
+    chars
+        .get(i + 1)
+        .is_some_and(|(_, next)| next.is_lowercase())
+}
+
+fn join_mapped(words: &[&str], separator: &str, map: impl Fn(&str) -> String) -> String {
+    words
+        .iter()
+        .map(|word| map(word))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Uppercase the first letter and lowercase the rest, unless the word is one of the acronyms
+fn capitalize(word: &str, acronyms: &[&str]) -> String {
+    if let Some(acronym) = acronyms.iter().find(|a| a.eq_ignore_ascii_case(word)) {
+        return acronym.to_string();
+    }
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
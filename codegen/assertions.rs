@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use super::super::{Edition, RustFeature};
+use super::{core_path, LintPolicy};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote_spanned, ToTokens};
+
+/// `const _: () = assert!(condition, "message");`, which fails to compile if the condition is
+/// false. `condition` has to be a constant expression. The error points at `span`, which should
+/// be the user code that imposed the requirement (e.g. the attribute argument).
+///
+/// If the `TargetRustVersion` doesn't support `RustFeature::ConstPanic`, this uses an array
+/// length that underflows instead, which fails at the same place but without the message.
+/// ```ignore
+/// // #[bitfield(bits = 12)] struct Flags(u8);
+/// output.extend(codegen::const_assert(
+///     quote!(#bits <= 8 * ::core::mem::size_of::<#ty>()),
+///     "the field type is too small for the number of bits",
+///     bits.span(),
+/// ));
+/// ```
+pub(crate) fn const_assert(condition: impl ToTokens, message: &str, span: Span) -> TokenStream {
+    let condition = condition.into_token_stream();
+    let lints = LintPolicy::default();
+    if !RustFeature::ConstPanic.is_supported() {
+        return quote_spanned! {span=>
+            #lints
+            const _: [(); 0] = [(); 0 - !(#condition) as usize];
+        };
+    }
+    let assert = core_path("assert", span, Edition::current());
+    quote_spanned! {span=>
+        #lints
+        const _: () = #assert!(#condition, #message);
+    }
+}
+
+/// Assert that `size_of::<ty>() == size`
+pub(crate) fn assert_size(ty: impl ToTokens, size: usize, span: Span) -> TokenStream {
+    let ty = ty.into_token_stream();
+    let message = format!("`{}` must have a size of {} bytes", ty, size);
+    let size_of = core_path("mem::size_of", span, Edition::current());
+    const_assert(
+        quote_spanned!(span=> #size_of::<#ty>() == #size),
+        &message,
+        span,
+    )
+}
+
+/// Assert that `align_of::<ty>() == align`
+pub(crate) fn assert_align(ty: impl ToTokens, align: usize, span: Span) -> TokenStream {
+    let ty = ty.into_token_stream();
+    let message = format!("`{}` must have an alignment of {} bytes", ty, align);
+    let align_of = core_path("mem::align_of", span, Edition::current());
+    const_assert(
+        quote_spanned!(span=> #align_of::<#ty>() == #align),
+        &message,
+        span,
+    )
+}
+
+/// Assert that `ty` implements `bound` (a trait, or multiple joined by `+`). The error is the
+/// usual "the trait bound is not satisfied" at `span`. Since the assertion is a separate item,
+/// `ty` can't use generic parameters of the input.
+/// ```ignore
+/// // #[derive(MyDerive)] requires all fields to implement `Serialize`
+/// for field in &fields {
+///     let ty = &field.ty;
+///     output.extend(codegen::assert_impl(ty, quote!(::serde::Serialize), ty.span()));
+/// }
+/// ```
+pub(crate) fn assert_impl(ty: impl ToTokens, bound: impl ToTokens, span: Span) -> TokenStream {
+    let ty = ty.into_token_stream();
+    let bound = bound.into_token_stream();
+    let lints = LintPolicy::default();
+    let sized = core_path("marker::Sized", span, Edition::current());
+    quote_spanned! {span=>
+        #lints
+        const _: () = {
+            fn __assert_impl<T: ?#sized + #bound>() {}
+            fn __check() {
+                __assert_impl::<#ty>();
+            }
+        };
+    }
+}
@@ -0,0 +1,280 @@
+#![allow(dead_code)]
+
+use super::super::{tokens, Error, Result};
+use super::LintPolicy;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+
+/// A builder for blanket impls: `impl<T: Bound> Trait for T`.
+/// ```ignore
+/// // impl<T: ::serde::Serialize + ?Sized> MyTrait for T { ... }
+/// let blanket = BlanketImpl::new(quote!(MyTrait), format_ident!("T"))
+///     .bound(quote!(::serde::Serialize))
+///     .bound(quote!(?Sized))
+///     .item(quote!(fn describe(&self) -> String { ... }));
+/// tracker.add(&blanket.to_item()?, attr.span())?;
+/// ```
+#[derive(Clone)]
+pub(crate) struct BlanketImpl {
+    trait_path: TokenStream,
+    param: Ident,
+    bounds: Vec<TokenStream>,
+    generics: Vec<TokenStream>,
+    self_ty: Option<TokenStream>,
+    where_predicates: Vec<TokenStream>,
+    unsafety: bool,
+    items: Vec<TokenStream>,
+    lints: LintPolicy,
+}
+
+impl BlanketImpl {
+    /// Start an impl of `trait_path` for all types `param`, without bounds
+    pub fn new(trait_path: impl ToTokens, param: Ident) -> Self {
+        Self {
+            trait_path: trait_path.into_token_stream(),
+            param,
+            bounds: vec![],
+            generics: vec![],
+            self_ty: None,
+            where_predicates: vec![],
+            unsafety: false,
+            items: vec![],
+            lints: LintPolicy::default(),
+        }
+    }
+
+    /// Add a bound of the parameter: `.bound(quote!(Clone))`
+    pub fn bound(mut self, bound: impl ToTokens) -> Self {
+        self.bounds.push(bound.into_token_stream());
+        self
+    }
+    /// Add another generic parameter: `.generic(quote!('a))`
+    pub fn generic(mut self, param: impl ToTokens) -> Self {
+        self.generics.push(param.into_token_stream());
+        self
+    }
+    /// Implement the trait for a type built from the parameter instead of the parameter itself:
+    /// `.self_ty(quote!(&#param))`
+    pub fn self_ty(mut self, ty: impl ToTokens) -> Self {
+        self.self_ty = Some(ty.into_token_stream());
+        self
+    }
+    /// Add a predicate to the where clause
+    pub fn where_predicate(mut self, predicate: impl ToTokens) -> Self {
+        self.where_predicates.push(predicate.into_token_stream());
+        self
+    }
+    /// Make the impl `unsafe` or not
+    pub fn unsafety(mut self, unsafety: bool) -> Self {
+        self.unsafety = unsafety;
+        self
+    }
+    /// Add an item to the impl, e.g. a method or an associated type
+    pub fn item(mut self, item: impl ToTokens) -> Self {
+        self.items.push(item.into_token_stream());
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// Render the impl
+    pub fn build(&self) -> TokenStream {
+        let Self {
+            trait_path,
+            param,
+            bounds,
+            generics,
+            self_ty,
+            where_predicates,
+            unsafety,
+            items,
+            lints,
+        } = self;
+        let bounds = (!bounds.is_empty()).then(|| quote!(: #(#bounds)+*));
+        let self_ty = match self_ty {
+            Some(ty) => ty.clone(),
+            None => param.to_token_stream(),
+        };
+        let unsafety = unsafety.then(|| quote!(unsafe));
+        let where_clause =
+            (!where_predicates.is_empty()).then(|| quote!(where #(#where_predicates,)*));
+        quote! {
+            #lints
+            #unsafety impl<#(#generics,)* #param #bounds> #trait_path for #self_ty #where_clause {
+                #(#items)*
+            }
+        }
+    }
+
+    /// Parse the rendered impl, e.g. for `ImplTracker::add`
+    pub fn to_item(&self) -> Result<syn::ItemImpl> {
+        Ok(syn::parse2(self.build())?)
+    }
+}
+
+impl ToTokens for BlanketImpl {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
+
+#[derive(Clone)]
+struct TrackedImpl {
+    item: syn::ItemImpl,
+    span: Span,
+}
+
+/// Collects the trait impls that a macro generates and reports the ones that probably overlap as
+/// spanned errors, instead of letting rustc point E0119 at the macro invocation.
+///
+/// The check is a heuristic: two impls overlap if their traits and self types match, where the
+/// generic parameters of an impl match any type. Bounds are ignored, so an impl for a type that
+/// doesn't implement the bound of a blanket impl is still reported. Paths are compared by their
+/// last segment, so `std::vec::Vec<T>` and `Vec<T>` are the same type.
+/// ```ignore
+/// let mut tracker = ImplTracker::new();
+/// for (variant, ty) in &conversions {
+///     let item: syn::ItemImpl = syn::parse_quote!(impl From<#ty> for #name { ... });
+///     tracker.add(&item, ty.span())?;
+///     output.extend(item.into_token_stream());
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct ImplTracker {
+    impls: Vec<TrackedImpl>,
+}
+
+impl ImplTracker {
+    /// Start without any impls
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an impl. Returns an error at `span` (and at the span of the other impl) if it overlaps
+    /// with an impl that was added before, in which case it is not added. Inherent impls are
+    /// ignored.
+    pub fn add(&mut self, item: &syn::ItemImpl, span: Span) -> Result<()> {
+        if item.trait_.is_none() {
+            return Ok(());
+        }
+        if let Some(other) = self.overlapping(item) {
+            let (_, trait_path, _) = item.trait_.as_ref().expect("checked above");
+            return Error::builder()
+                .with(
+                    span,
+                    format!(
+                        "conflicting implementations of `{}` for `{}`",
+                        tokens::format_tokens(trait_path.to_token_stream()),
+                        tokens::format_tokens(item.self_ty.to_token_stream()),
+                    ),
+                )
+                .with(other.span, "the conflicting implementation comes from here")
+                .build_err();
+        }
+        self.impls.push(TrackedImpl {
+            item: item.clone(),
+            span,
+        });
+        Ok(())
+    }
+
+    /// Check if the impl overlaps with one that was added, without adding it
+    pub fn overlaps(&self, item: &syn::ItemImpl) -> bool {
+        self.overlapping(item).is_some()
+    }
+
+    /// The number of tracked impls
+    pub fn len(&self) -> usize {
+        self.impls.len()
+    }
+    /// Check if no impls were added
+    pub fn is_empty(&self) -> bool {
+        self.impls.is_empty()
+    }
+
+    fn overlapping(&self, item: &syn::ItemImpl) -> Option<&TrackedImpl> {
+        let (_, trait_path, _) = item.trait_.as_ref()?;
+        self.impls.iter().find(|other| {
+            let Some((_, other_trait, _)) = &other.item.trait_ else {
+                return false;
+            };
+            let vars = Vars(&item.generics, &other.item.generics);
+            // negative impls conflict with positive ones as well, so they aren't checked
+            unify_paths(trait_path, other_trait, &vars)
+                && unify_types(&item.self_ty, &other.item.self_ty, &vars)
+        })
+    }
+}
+
+/// The generics of the two impls that are compared
+struct Vars<'a>(&'a syn::Generics, &'a syn::Generics);
+
+impl Vars<'_> {
+    fn is_var(generics: &syn::Generics, path: &syn::Path) -> bool {
+        let Some(ident) = path.get_ident() else {
+            return false;
+        };
+        generics.type_params().any(|param| param.ident == *ident)
+    }
+}
+
+fn unify_types(a: &syn::Type, b: &syn::Type, vars: &Vars) -> bool {
+    use syn::Type;
+    match (a, b) {
+        (Type::Path(a), _) if a.qself.is_none() && Vars::is_var(vars.0, &a.path) => true,
+        (_, Type::Path(b)) if b.qself.is_none() && Vars::is_var(vars.1, &b.path) => true,
+        (Type::Group(a), _) => unify_types(&a.elem, b, vars),
+        (_, Type::Group(b)) => unify_types(a, &b.elem, vars),
+        (Type::Paren(a), _) => unify_types(&a.elem, b, vars),
+        (_, Type::Paren(b)) => unify_types(a, &b.elem, vars),
+        (Type::Path(a), Type::Path(b)) if a.qself.is_none() && b.qself.is_none() => {
+            unify_paths(&a.path, &b.path, vars)
+        }
+        (Type::Reference(a), Type::Reference(b)) => {
+            a.mutability.is_some() == b.mutability.is_some() && unify_types(&a.elem, &b.elem, vars)
+        }
+        (Type::Ptr(a), Type::Ptr(b)) => {
+            a.mutability.is_some() == b.mutability.is_some() && unify_types(&a.elem, &b.elem, vars)
+        }
+        (Type::Slice(a), Type::Slice(b)) => unify_types(&a.elem, &b.elem, vars),
+        (Type::Array(a), Type::Array(b)) => unify_types(&a.elem, &b.elem, vars),
+        (Type::Tuple(a), Type::Tuple(b)) => {
+            a.elems.len() == b.elems.len()
+                && a.elems
+                    .iter()
+                    .zip(&b.elems)
+                    .all(|(a, b)| unify_types(a, b, vars))
+        }
+        // projections, trait objects, `impl Trait`, fn pointers and macros
+        _ => tokens::eq(&a.to_token_stream(), &b.to_token_stream()),
+    }
+}
+
+fn unify_paths(a: &syn::Path, b: &syn::Path, vars: &Vars) -> bool {
+    let (Some(a), Some(b)) = (a.segments.last(), b.segments.last()) else {
+        return false;
+    };
+    if a.ident != b.ident {
+        return false;
+    }
+    match (&a.arguments, &b.arguments) {
+        (syn::PathArguments::AngleBracketed(a), syn::PathArguments::AngleBracketed(b)) => {
+            let types = |args: &syn::AngleBracketedGenericArguments| -> Vec<syn::Type> {
+                args.args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            };
+            let (a, b) = (types(a), types(b));
+            a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| unify_types(a, b, vars))
+        }
+        (syn::PathArguments::None, syn::PathArguments::None) => true,
+        (a, b) => tokens::eq(&a.to_token_stream(), &b.to_token_stream()),
+    }
+}
@@ -0,0 +1,195 @@
+#![allow(dead_code)]
+
+use super::super::tokens::{self, TokenRope};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+
+/// A `#[cfg(...)]` predicate
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum CfgExpr {
+    /// A name without a value: `unix`, `test`, `debug_assertions`
+    Flag(String),
+    /// `key = "value"`: `feature = "std"`, `target_os = "linux"`
+    KeyValue(String, String),
+    /// `any(...)`, true if any of the predicates is true. `any()` is always false.
+    Any(Vec<CfgExpr>),
+    /// `all(...)`, true if all of the predicates are true. `all()` is always true.
+    All(Vec<CfgExpr>),
+    /// `not(...)`
+    Not(Box<CfgExpr>),
+}
+
+impl CfgExpr {
+    /// `name`. Panics if `name` is not a valid identifier.
+    pub fn flag(name: &str) -> Self {
+        check_name(name);
+        Self::Flag(name.to_string())
+    }
+    /// `key = "value"`. Panics if `key` is not a valid identifier.
+    pub fn key_value(key: &str, value: &str) -> Self {
+        check_name(key);
+        Self::KeyValue(key.to_string(), value.to_string())
+    }
+    /// `feature = "name"`
+    pub fn feature(name: &str) -> Self {
+        Self::key_value("feature", name)
+    }
+    /// `target_<key> = "value"`: `CfgExpr::target("os", "linux")`
+    pub fn target(key: &str, value: &str) -> Self {
+        Self::key_value(&format!("target_{}", key), value)
+    }
+    /// `any(...)`
+    pub fn any(exprs: impl IntoIterator<Item = CfgExpr>) -> Self {
+        Self::Any(exprs.into_iter().collect())
+    }
+    /// `all(...)`
+    pub fn all(exprs: impl IntoIterator<Item = CfgExpr>) -> Self {
+        Self::All(exprs.into_iter().collect())
+    }
+    /// `not(...)`. Negating a `not` returns its inner predicate instead of nesting.
+    pub fn negate(self) -> Self {
+        match self {
+            Self::Not(inner) => *inner,
+            expr => Self::Not(Box::new(expr)),
+        }
+    }
+
+    /// The `#[cfg(...)]` attribute for this predicate
+    pub fn attr(&self) -> TokenStream {
+        quote!(#[cfg(#self)])
+    }
+}
+
+impl ToTokens for CfgExpr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ident = |name: &str| Ident::new(name, Span::call_site());
+        tokens.append_all(match self {
+            Self::Flag(name) => ident(name).into_token_stream(),
+            Self::KeyValue(key, value) => {
+                let key = ident(key);
+                let value = Literal::string(value);
+                quote!(#key = #value)
+            }
+            Self::Any(exprs) => quote!(any(#(#exprs),*)),
+            Self::All(exprs) => quote!(all(#(#exprs),*)),
+            Self::Not(expr) => quote!(not(#expr)),
+        });
+    }
+}
+
+fn check_name(name: &str) {
+    if !super::super::ident::is_valid_ident(name) {
+        panic!("CfgExpr: `{}` is not a valid cfg name", name);
+    }
+}
+
+/// A builder for conditionally compiled output, like an `if`/`else if`/`else` chain of `#[cfg]`s.
+///
+/// Every branch gets the negation of all previous conditions, so exactly one branch is compiled
+/// (or none, without `otherwise`). The attribute is added to every item in the branch, see
+/// `tokens::split_items`.
+/// ```ignore
+/// let output = CfgBuilder::new()
+///     .when(CfgExpr::feature("std"), quote! { impl std::error::Error for #name {} })
+///     .when(CfgExpr::feature("alloc"), quote! { use alloc::string::String; })
+///     .otherwise(quote! { compile_error!("either `std` or `alloc` is required"); })
+///     .build();
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct CfgBuilder {
+    branches: Vec<(CfgExpr, TokenStream)>,
+    otherwise: Option<TokenStream>,
+}
+
+impl CfgBuilder {
+    /// A builder without branches
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add items that are only compiled if `condition` is true and no previous condition is
+    pub fn when(mut self, condition: CfgExpr, items: impl ToTokens) -> Self {
+        self.branches.push((condition, items.into_token_stream()));
+        self
+    }
+    /// Add items that are compiled if none of the conditions are true. Replaces previous calls.
+    pub fn otherwise(mut self, items: impl ToTokens) -> Self {
+        self.otherwise = Some(items.into_token_stream());
+        self
+    }
+
+    /// The condition under which the branch at `index` is compiled. `index == len` is the
+    /// `otherwise` branch.
+    fn condition(&self, index: usize) -> CfgExpr {
+        let previous = self.branches[..index]
+            .iter()
+            .map(|(condition, _)| condition.clone());
+        let mut parts: Vec<CfgExpr> = previous.map(CfgExpr::negate).collect();
+        if let Some((condition, _)) = self.branches.get(index) {
+            parts.push(condition.clone());
+        }
+        match parts.len() {
+            1 => parts.pop().unwrap(),
+            _ => CfgExpr::All(parts),
+        }
+    }
+
+    /// Render all branches
+    pub fn build(&self) -> TokenStream {
+        let branches = self.branches.iter().map(|(_, items)| items);
+        let mut output = TokenRope::new();
+        for (index, items) in branches.chain(&self.otherwise).enumerate() {
+            let attr = self.condition(index).attr();
+            for item in tokens::split_items(items.clone()) {
+                let item = item.tokens;
+                output.push(quote!(#attr #item));
+            }
+        }
+        output.finish()
+    }
+}
+
+impl ToTokens for CfgBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_item_gets_the_attribute() {
+        let items = quote! {
+            impl<T> Foo for T where T: Iterator<Item = u8> { fn a() {} }
+            fn b() {}
+            struct S<T = u8> { x: T }
+            struct R;
+        };
+        let output = CfgBuilder::new()
+            .when(CfgExpr::feature("std"), items.clone())
+            .otherwise(items)
+            .build();
+        let file: syn::File = syn::parse2(output).unwrap();
+        assert_eq!(file.items.len(), 8);
+        let conditions: Vec<String> = file
+            .items
+            .iter()
+            .map(|item| {
+                let attrs = match item {
+                    syn::Item::Impl(item) => &item.attrs,
+                    syn::Item::Fn(item) => &item.attrs,
+                    syn::Item::Struct(item) => &item.attrs,
+                    _ => panic!("unexpected item"),
+                };
+                assert_eq!(attrs.len(), 1);
+                attrs[0].meta.to_token_stream().to_string()
+            })
+            .collect();
+        let (first, second) = conditions.split_at(4);
+        assert!(first.iter().all(|c| c == &first[0]));
+        assert!(second.iter().all(|c| c == &second[0]));
+        assert_eq!(first[0], quote!(cfg(feature = "std")).to_string());
+        assert_eq!(second[0], quote!(cfg(not(feature = "std"))).to_string());
+    }
+}
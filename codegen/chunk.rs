@@ -0,0 +1,264 @@
+#![allow(dead_code)]
+
+use super::super::tokens::{self, TokenRope, TokenVisitor};
+use super::LintPolicy;
+use proc_macro2::{Ident, Punct, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+
+/// Splits huge generated functions into `#[inline(always)]` helper functions that are called in
+/// sequence, since rustc gets slow for functions with a lot of code (e.g. the registration of
+/// thousands of table entries).
+///
+/// Only the top-level statements of the body are moved, so they can't share local variables.
+/// State is passed through `param`s instead, which every helper gets as an argument. To keep the
+/// behavior the same, statements stay in the original function if they:
+/// - declare items, since later statements might use them
+/// - contain `return`, `break`, `continue` or `?`, which would affect the helper instead
+/// - contain `await` or `yield`, since helpers are never `async`
+/// - use `self` or `Self`, which helpers can't refer to
+///
+/// Helpers of a `const fn` are `const` as well, and helpers of an `unsafe fn` are `unsafe`, so
+/// the moved statements keep their context.
+///
+/// The first `let` statement ends the splitting, it and all statements after it stay in place.
+/// ```ignore
+/// let chunker = FnChunker::new().param(format_ident!("registry"), quote!(&mut Registry));
+/// let item: syn::ItemFn = syn::parse2(quote! {
+///     fn register_all(registry: &mut Registry) {
+///         #(registry.add(#entries);)*
+///     }
+/// })?;
+/// output.extend(chunker.chunk(item));
+/// ```
+#[derive(Clone)]
+pub(crate) struct FnChunker {
+    max_statements: usize,
+    max_tokens: usize,
+    params: Vec<(Ident, TokenStream)>,
+    lints: LintPolicy,
+}
+
+impl FnChunker {
+    /// Split into helpers of up to 500 statements or 20000 tokens each
+    pub fn new() -> Self {
+        Self {
+            max_statements: 500,
+            max_tokens: 20_000,
+            params: vec![],
+            lints: LintPolicy::default(),
+        }
+    }
+    /// Set the maximum number of statements per helper
+    pub fn max_statements(mut self, max: usize) -> Self {
+        self.max_statements = max.max(1);
+        self
+    }
+    /// Set the maximum number of tokens per helper. A single statement that is larger than this
+    /// gets its own helper.
+    pub fn max_tokens(mut self, max: usize) -> Self {
+        self.max_tokens = max;
+        self
+    }
+    /// Pass a variable of the function to every helper with the same name. `ty` should be a
+    /// reference or `Copy`, since the variable is passed to each helper in turn.
+    pub fn param(mut self, name: Ident, ty: impl ToTokens) -> Self {
+        self.params.push((name, ty.into_token_stream()));
+        self
+    }
+    /// Replace the lints that are allowed on the helpers, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// Check if the function is above the limits and would be split
+    pub fn needs_chunking(&self, item: &syn::ItemFn) -> bool {
+        item.block.stmts.len() > self.max_statements
+            || tokens::stats(&item.block.to_token_stream()).tokens > self.max_tokens
+    }
+
+    /// Split the body of the function if it is above the limits, otherwise return it unchanged.
+    /// The helpers are declared inside of the function, so they don't need unique names.
+    pub fn chunk(&self, mut item: syn::ItemFn) -> TokenStream {
+        if !self.needs_chunking(&item) {
+            return item.into_token_stream();
+        }
+        let stmts = std::mem::take(&mut item.block.stmts);
+        let split_end = stmts
+            .iter()
+            .position(|stmt| matches!(stmt, syn::Stmt::Local(_)))
+            .unwrap_or(stmts.len());
+
+        let (type_params, turbofish) = self.generics(&item.sig.generics);
+        let where_clause = &item.sig.generics.where_clause;
+        let param_decls = self.params.iter().map(|(name, ty)| quote!(#name: #ty));
+        let param_decls = quote!(#(#param_decls),*);
+        let param_names = self.params.iter().map(|(name, _)| name);
+        let param_names = quote!(#(#param_names),*);
+        let lints = &self.lints;
+        let (constness, unsafety) = (&item.sig.constness, &item.sig.unsafety);
+
+        let mut helpers = TokenRope::new();
+        let mut body = TokenRope::new();
+        let mut chunk = TokenRope::new();
+        let mut chunk_len = 0;
+        let mut chunk_tokens = 0;
+        let mut helper_count = 0usize;
+        let mut flush = |chunk: &mut TokenRope, body: &mut TokenRope| {
+            if chunk.is_empty() {
+                return;
+            }
+            let name = format_ident!("__chunk_{}", helper_count);
+            helper_count += 1;
+            let stmts = std::mem::take(chunk).finish();
+            helpers.push(quote! {
+                #lints
+                #[inline(always)]
+                #constness #unsafety fn #name #type_params(#param_decls) #where_clause {
+                    #stmts
+                }
+            });
+            body.push(match unsafety {
+                Some(_) => {
+                    quote!(#[allow(unused_unsafe)] unsafe { #name #turbofish(#param_names); })
+                }
+                None => quote!(#name #turbofish(#param_names);),
+            });
+        };
+        let last = stmts.len().saturating_sub(1);
+        let mut iter = stmts.into_iter().enumerate();
+        for (i, stmt) in iter.by_ref().take(split_end) {
+            let movable = i != last && is_movable(&stmt);
+            let stmt = stmt.into_token_stream();
+            if !movable {
+                flush(&mut chunk, &mut body);
+                (chunk_len, chunk_tokens) = (0, 0);
+                body.push(stmt);
+                continue;
+            }
+            let stmt_tokens = tokens::stats(&stmt).tokens;
+            if chunk_len >= self.max_statements
+                || (chunk_len > 0 && chunk_tokens + stmt_tokens > self.max_tokens)
+            {
+                flush(&mut chunk, &mut body);
+                (chunk_len, chunk_tokens) = (0, 0);
+            }
+            chunk.push(stmt);
+            chunk_len += 1;
+            chunk_tokens += stmt_tokens;
+        }
+        flush(&mut chunk, &mut body);
+        body.extend(iter.map(|(_, stmt)| stmt.into_token_stream()));
+        let (helpers, body) = (helpers.finish(), body.finish());
+
+        let syn::ItemFn {
+            attrs, vis, sig, ..
+        } = &item;
+        quote! {
+            #(#attrs)*
+            #vis #sig {
+                #helpers
+                #body
+            }
+        }
+    }
+
+    /// The generic parameters of the helpers and the turbofish to call them. Lifetimes are left
+    /// to inference, since late-bound lifetimes can't be specified explicitly.
+    fn generics(&self, generics: &syn::Generics) -> (TokenStream, TokenStream) {
+        if generics.params.is_empty() {
+            return (TokenStream::new(), TokenStream::new());
+        }
+        let params = &generics.params;
+        let args: Vec<TokenStream> = params
+            .iter()
+            .filter_map(|param| match param {
+                syn::GenericParam::Type(param) => Some(param.ident.to_token_stream()),
+                syn::GenericParam::Const(param) => Some(param.ident.to_token_stream()),
+                syn::GenericParam::Lifetime(_) => None,
+            })
+            .collect();
+        let turbofish = if args.is_empty() {
+            TokenStream::new()
+        } else {
+            quote!(::<#(#args),*>)
+        };
+        (quote!(<#params>), turbofish)
+    }
+}
+
+impl Default for FnChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check if a statement that isn't the last one can be moved into a helper without changing its
+/// behavior
+fn is_movable(stmt: &syn::Stmt) -> bool {
+    if matches!(stmt, syn::Stmt::Item(_) | syn::Stmt::Local(_)) {
+        return false;
+    }
+    let mut finder = ControlFlowFinder(false);
+    tokens::walk(&stmt.to_token_stream(), &mut finder);
+    !finder.0
+}
+
+/// Finds tokens that would behave differently in a helper function
+struct ControlFlowFinder(bool);
+
+impl TokenVisitor for ControlFlowFinder {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if [
+            "return", "break", "continue", "await", "yield", "self", "Self",
+        ]
+        .iter()
+        .any(|keyword| ident == keyword)
+        {
+            self.0 = true;
+        }
+    }
+    fn visit_punct(&mut self, punct: &Punct) {
+        if punct.as_char() == '?' {
+            self.0 = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(item: TokenStream) -> String {
+        let item: syn::ItemFn = syn::parse2(item).unwrap();
+        let output = FnChunker::new()
+            .max_statements(1)
+            .lints(LintPolicy::none())
+            .chunk(item);
+        output.to_string()
+    }
+
+    #[test]
+    fn await_stays_in_place() {
+        let output = chunk(quote! {
+            async fn f() { a(); b().await; c(); d() }
+        });
+        assert!(output.contains(&quote!(__chunk_0(); b().await; __chunk_1();).to_string()));
+        assert!(!output.contains("async fn __chunk"));
+    }
+
+    #[test]
+    fn helpers_keep_const_and_unsafe() {
+        let output = chunk(quote! {
+            const unsafe fn f() { a(); b(); c() }
+        });
+        assert!(output.contains(&quote!(const unsafe fn __chunk_0()).to_string()));
+        let call = quote!(
+            #[allow(unused_unsafe)]
+            unsafe {
+                __chunk_0();
+            }
+        );
+        assert!(output.contains(&call.to_string()));
+    }
+}
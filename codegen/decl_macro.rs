@@ -0,0 +1,216 @@
+#![allow(dead_code)]
+
+use super::super::tokens::TokenRope;
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{ToTokens, TokenStreamExt};
+
+/// The fragment specifier of a `macro_rules!` metavariable: the `expr` in `$x:expr`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Fragment {
+    Block,
+    Expr,
+    Ident,
+    Item,
+    Lifetime,
+    Literal,
+    Meta,
+    Pat,
+    PatParam,
+    Path,
+    Stmt,
+    Tt,
+    Ty,
+    Vis,
+}
+
+impl Fragment {
+    /// All fragment specifiers
+    pub const ALL: [Fragment; 14] = [
+        Fragment::Block,
+        Fragment::Expr,
+        Fragment::Ident,
+        Fragment::Item,
+        Fragment::Lifetime,
+        Fragment::Literal,
+        Fragment::Meta,
+        Fragment::Pat,
+        Fragment::PatParam,
+        Fragment::Path,
+        Fragment::Stmt,
+        Fragment::Tt,
+        Fragment::Ty,
+        Fragment::Vis,
+    ];
+
+    /// The specifier as written: `"expr"`, `"pat_param"`, ...
+    pub fn name(self) -> &'static str {
+        match self {
+            Fragment::Block => "block",
+            Fragment::Expr => "expr",
+            Fragment::Ident => "ident",
+            Fragment::Item => "item",
+            Fragment::Lifetime => "lifetime",
+            Fragment::Literal => "literal",
+            Fragment::Meta => "meta",
+            Fragment::Pat => "pat",
+            Fragment::PatParam => "pat_param",
+            Fragment::Path => "path",
+            Fragment::Stmt => "stmt",
+            Fragment::Tt => "tt",
+            Fragment::Ty => "ty",
+            Fragment::Vis => "vis",
+        }
+    }
+    /// Look up a specifier by its name. See `Fragment::name`.
+    pub fn from_name(name: &str) -> Option<Fragment> {
+        Fragment::ALL
+            .into_iter()
+            .find(|fragment| fragment.name() == name)
+    }
+}
+
+/// A `$` token
+pub(crate) fn dollar(span: Span) -> TokenTree {
+    let mut dollar = Punct::new('$', Spacing::Alone);
+    dollar.set_span(span);
+    TokenTree::Punct(dollar)
+}
+
+/// A metavariable in a matcher: `$name:fragment`
+pub(crate) fn metavar(name: &Ident, fragment: Fragment) -> TokenStream {
+    let mut tokens = var(name);
+    let mut colon = Punct::new(':', Spacing::Alone);
+    colon.set_span(name.span());
+    tokens.append(colon);
+    tokens.append(Ident::new(fragment.name(), name.span()));
+    tokens
+}
+
+/// A metavariable in a transcriber: `$name`
+pub(crate) fn var(name: &Ident) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    tokens.append(dollar(name.span()));
+    tokens.append(name.clone());
+    tokens
+}
+
+/// `$crate`, which refers to the crate that defines the `macro_rules!` macro wherever the macro
+/// is used
+pub(crate) fn dollar_crate(span: Span) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    tokens.append(dollar(span));
+    tokens.append(Ident::new("crate", span));
+    tokens
+}
+
+/// Replace `crate` at the start of paths with `$crate`, e.g. for paths from the input of a
+/// proc macro that are copied into a transcriber, where `crate::` would refer to the crate that
+/// calls the declarative macro. Only a `crate` that is followed by `::` is replaced, so
+/// `pub(crate)` is kept.
+pub(crate) fn dollar_crate_paths(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut out = TokenRope::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(ident)
+                if ident == "crate"
+                    && super::super::tokens::is_punct_seq(&tokens[i + 1..], "::")
+                    && !(i > 0 && is_dollar(&tokens[i - 1])) =>
+            {
+                out.push_tree(dollar(ident.span()));
+                out.push_tree(ident.clone().into());
+            }
+            TokenTree::Group(group) => {
+                let mut new = Group::new(group.delimiter(), dollar_crate_paths(group.stream()));
+                new.set_span(group.span());
+                out.push_tree(new.into());
+            }
+            _ => {
+                out.push_tree(token.clone());
+            }
+        }
+    }
+    out.finish()
+}
+
+fn is_dollar(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == '$')
+}
+
+/// How often the contents of a `Repetition` can occur
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RepeatKind {
+    /// `$(...)*`
+    ZeroOrMore,
+    /// `$(...)+`
+    OneOrMore,
+    /// `$(...)?`, which can't have a separator
+    AtMostOnce,
+}
+
+/// A repetition in a matcher or transcriber: `$( contents ) separator kind`
+/// ```ignore
+/// let field = format_ident!("field");
+/// // ($($field:ident),* $(,)?) => { $(fn $field() {})* };
+/// let fields = Repetition::new(metavar(&field, Fragment::Ident)).separator(',');
+/// let trailing = Repetition::new(quote!(,)).kind(RepeatKind::AtMostOnce);
+/// let field = var(&field);
+/// let transcriber = Repetition::new(quote!(fn #field() {}));
+/// let shim = shim.rule(quote!(#fields #trailing), transcriber);
+/// ```
+#[derive(Clone)]
+pub(crate) struct Repetition {
+    contents: TokenStream,
+    separator: Option<char>,
+    kind: RepeatKind,
+}
+
+impl Repetition {
+    /// A `ZeroOrMore` repetition without a separator
+    pub fn new(contents: impl ToTokens) -> Self {
+        Self {
+            contents: contents.into_token_stream(),
+            separator: None,
+            kind: RepeatKind::ZeroOrMore,
+        }
+    }
+    /// Set the separator, usually `,` or `;`
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+    /// Set how often the contents can occur
+    pub fn kind(mut self, kind: RepeatKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Render the repetition. Panics for an `AtMostOnce` repetition with a separator, which
+    /// `macro_rules!` doesn't accept.
+    pub fn build(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        tokens.append(dollar(Span::call_site()));
+        tokens.append(Group::new(Delimiter::Parenthesis, self.contents.clone()));
+        if let Some(separator) = self.separator {
+            if self.kind == RepeatKind::AtMostOnce {
+                panic!("Repetition: `$(...)?` can't have a separator");
+            }
+            tokens.append(Punct::new(separator, Spacing::Alone));
+        }
+        tokens.append(Punct::new(
+            match self.kind {
+                RepeatKind::ZeroOrMore => '*',
+                RepeatKind::OneOrMore => '+',
+                RepeatKind::AtMostOnce => '?',
+            },
+            Spacing::Alone,
+        ));
+        tokens
+    }
+}
+
+impl ToTokens for Repetition {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
@@ -0,0 +1,211 @@
+#![allow(dead_code)]
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// A value for a placeholder in `doc`
+#[derive(Clone)]
+pub(crate) struct DocValue {
+    text: String,
+    escape: bool,
+}
+
+impl DocValue {
+    /// Text that is inserted as-is, so it can contain markdown, links or code blocks
+    pub fn raw(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            escape: false,
+        }
+    }
+    /// The doc comments of the input, e.g. to repeat the docs of a field on its getter
+    pub fn original(attrs: &[syn::Attribute]) -> Self {
+        Self::raw(original_docs(attrs))
+    }
+}
+
+impl From<&str> for DocValue {
+    fn from(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            escape: true,
+        }
+    }
+}
+
+impl From<String> for DocValue {
+    fn from(text: String) -> Self {
+        Self { text, escape: true }
+    }
+}
+
+/// Generate `#[doc = "..."]` attributes from a multi-line template with `{name}` placeholders.
+///
+/// The template is dedented and leading and trailing blank lines are removed, so it can be
+/// written as an indented string literal. Values are escaped with `escape_doc` unless they are
+/// `DocValue::raw` or `DocValue::original`, so names like `Vec<T>` or `[u8; 4]` don't turn into
+/// broken links, html tags or code spans. Inside of a code span of the template (`` `{ty}` ``),
+/// where markdown shows backslashes literally, values are inserted unescaped. Use `{{` and `}}`
+/// for literal braces.
+///
+/// Panics if the template uses a placeholder without a value.
+/// ```ignore
+/// let docs = codegen::doc(
+///     "
+///     Returns the `{field}` field of [`{ty}`].
+///
+///     {docs}
+///     ",
+///     &[
+///         ("field", name.to_string().into()),
+///         ("ty", type_name.to_string().into()),
+///         ("docs", DocValue::original(&field.attrs)),
+///     ],
+/// );
+/// quote! { #docs pub fn #name(&self) -> &#ty { &self.#name } }
+/// ```
+pub(crate) fn doc(template: &str, args: &[(&str, DocValue)]) -> TokenStream {
+    let text = fill(&dedent(template), args);
+    let lines = text.lines().map(|line| {
+        let line = if line.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", line)
+        };
+        quote!(#[doc = #line])
+    });
+    lines.collect()
+}
+
+/// Escape text for use in doc comments: `[`, `]`, `<`, `>`, `*`, `_` and backticks are escaped
+/// with a backslash, so the text is shown literally instead of as links, html, emphasis or inline
+/// code.
+pub(crate) fn escape_doc(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '<' | '>' | '*' | '_' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The text of all `#[doc = "..."]` attributes (i.e. doc comments), one per line, without the
+/// space that `///` puts at the start of each line
+pub(crate) fn original_docs(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) => Some(lit.value()),
+            _ => None,
+        })
+        .flat_map(|value| {
+            // `/** ... */` comments produce a single attribute with multiple lines
+            value
+                .lines()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Remove the common indentation and the leading and trailing blank lines
+fn dedent(template: &str) -> String {
+    let lines: Vec<&str> = template.lines().collect();
+    let start = lines.iter().position(|line| !line.trim().is_empty());
+    let end = lines.iter().rposition(|line| !line.trim().is_empty());
+    let (Some(start), Some(end)) = (start, end) else {
+        return String::new();
+    };
+    let lines = &lines[start..=end];
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect();
+    lines.join("\n")
+}
+
+fn fill(template: &str, args: &[(&str, DocValue)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    // whether the template is inside of a `code span` (or a code block)
+    let mut in_code = false;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        in_code ^= rest[..pos].matches('`').count() % 2 == 1;
+        let brace = &rest[pos..pos + 1];
+        rest = &rest[pos + 1..];
+        if let Some(after) = rest.strip_prefix(brace) {
+            out.push_str(brace);
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            panic!("doc template: unmatched `}}` in {:?}", template);
+        }
+        let Some(end) = rest.find('}') else {
+            panic!("doc template: unclosed `{{` in {:?}", template);
+        };
+        let name = rest[..end].trim();
+        let Some((_, value)) = args.iter().find(|(n, _)| *n == name) else {
+            panic!("doc template: no value for `{{{}}}`", name);
+        };
+        if value.escape && !in_code {
+            out.push_str(&escape_doc(&value.text));
+        } else {
+            out.push_str(&value.text);
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(template: &str, args: &[(&str, DocValue)]) -> String {
+        fill(&dedent(template), args)
+    }
+
+    #[test]
+    fn values_in_code_spans_are_not_escaped() {
+        let args = [("ty", DocValue::from("Vec<T>"))];
+        assert_eq!(text("Returns [`{ty}`].", &args), "Returns [`Vec<T>`].");
+        assert_eq!(
+            text("A {ty}, `code` {ty}", &args),
+            r"A Vec\<T\>, `code` Vec\<T\>"
+        );
+        assert_eq!(
+            text("```\nlet x: {ty};\n```\n{ty}", &args),
+            "```\nlet x: Vec<T>;\n```\nVec\\<T\\>"
+        );
+    }
+
+    #[test]
+    fn underscores_are_escaped() {
+        assert_eq!(escape_doc("foo_bar_baz"), r"foo\_bar\_baz");
+        let args = [("name", DocValue::from("foo_bar_baz"))];
+        assert_eq!(
+            text("`{name}` and {name}", &args),
+            r"`foo_bar_baz` and foo\_bar\_baz"
+        );
+    }
+}
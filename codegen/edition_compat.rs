@@ -0,0 +1,63 @@
+#![allow(dead_code)]
+
+use super::super::Edition;
+use super::StdPolicy;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+
+/// A trait object type for `bounds` (e.g. `Fn() + Send`): `dyn Fn() + Send`, or the bare
+/// `Fn() + Send` for 2015, where `dyn` is only a contextual keyword and bare trait objects are
+/// the norm. Bare trait objects are a hard error from 2021 on. Like `dyn`, it needs parentheses
+/// after `&` if there are multiple bounds.
+/// ```ignore
+/// let callback = codegen::trait_object(quote!(Fn(&#ty) + Send), Edition::current());
+/// quote!(Box<#callback>)
+/// ```
+pub(crate) fn trait_object(bounds: impl ToTokens, edition: Edition) -> TokenStream {
+    let bounds = bounds.into_token_stream();
+    if edition == Edition::E2015 {
+        bounds
+    } else {
+        quote!(dyn #bounds)
+    }
+}
+
+/// The absolute path of an item of `core`: `"option::Option"` -> `::core::option::Option`.
+///
+/// In 2015, `::name` is relative to the crate root instead of an extern crate, so `::core` only
+/// works in `no_std` crates (which have `core` injected at the root). Other 2015 crates have
+/// `std` injected instead, which re-exports everything in `core`. `StdPolicy::current()` tells
+/// the two apart. Panics if `path` is not a valid path.
+pub(crate) fn core_path(path: &str, span: Span, edition: Edition) -> TokenStream {
+    let krate = if edition == Edition::E2015 && StdPolicy::current() == StdPolicy::Std {
+        "std"
+    } else {
+        "core"
+    };
+    let mut tokens = TokenStream::new();
+    for segment in std::iter::once(krate).chain(path.split("::")) {
+        let segment = segment.trim();
+        assert!(
+            super::super::ident::is_valid_ident(segment),
+            "core_path: `{}` is not a valid path",
+            path
+        );
+        tokens.append_all(quote::quote_spanned!(span=> ::));
+        tokens.append(Ident::new(segment, span));
+    }
+    tokens
+}
+
+/// A name for a generated item (not one taken from the input), with a `_` appended if it is a
+/// keyword in any edition. This doesn't depend on the edition of the user's crate, so the output
+/// stays valid when the crate moves to a newer edition, e.g. `gen` (reserved in 2024) becomes
+/// `gen_` everywhere. Use `ident::escaped` for names from the input instead, which makes them
+/// raw where needed.
+pub(crate) fn generated_name(name: &str, span: Span) -> Ident {
+    let name = if Edition::LATEST.is_keyword(name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    };
+    Ident::new(&name, span)
+}
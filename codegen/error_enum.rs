@@ -0,0 +1,268 @@
+#![allow(dead_code)]
+
+use super::super::tokens::TokenRope;
+use super::super::{Error, Result};
+use super::{FormatTemplate, LintPolicy, MatchBuilder, StdPolicy};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+
+/// A variant of an `ErrorEnum`, with the parts of a `thiserror`-like attribute that the macro
+/// parsed from the input
+#[derive(Clone)]
+pub(crate) struct ErrorVariant {
+    name: Ident,
+    fields: syn::Fields,
+    display: Option<syn::LitStr>,
+    source: Option<syn::Member>,
+    impl_from: bool,
+}
+
+impl ErrorVariant {
+    /// Take the name and fields of a variant. A field named `source` is the source by default.
+    pub fn new(variant: &syn::Variant) -> Self {
+        let source = variant
+            .fields
+            .iter()
+            .filter_map(|field| field.ident.as_ref())
+            .find(|ident| *ident == "source")
+            .map(|ident| syn::Member::Named(ident.clone()));
+        Self {
+            name: variant.ident.clone(),
+            fields: variant.fields.clone(),
+            display: None,
+            source,
+            impl_from: false,
+        }
+    }
+    /// Set the format string for `Display`, e.g. from `#[error("...")]`. Fields are referred to
+    /// by name (`{path}`) or index (`{0}`), with the usual format specs (`{path:?}`), see
+    /// `FormatTemplate`.
+    pub fn display(mut self, format: syn::LitStr) -> Self {
+        self.display = Some(format);
+        self
+    }
+    /// Set the field that `Error::source` returns
+    pub fn source(mut self, field: syn::Member) -> Self {
+        self.source = Some(field);
+        self
+    }
+    /// Generate `From<FieldType>` for the enum, e.g. for `#[from]`. The variant must have exactly
+    /// one field, which also becomes the source.
+    pub fn impl_from(mut self, impl_from: bool) -> Self {
+        self.impl_from = impl_from;
+        self
+    }
+
+    /// The name of the variant
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    /// The field for `from`
+    fn field_for_from(&self) -> Result<(syn::Member, &syn::Type)> {
+        let mut fields = self.fields.iter();
+        match (fields.next(), fields.next()) {
+            (Some(field), None) => {
+                let member = match &field.ident {
+                    Some(ident) => syn::Member::Named(ident.clone()),
+                    None => syn::Member::Unnamed(0.into()),
+                };
+                Ok((member, &field.ty))
+            }
+            _ => Error::err(
+                self.name.span(),
+                "a variant with `From` must have exactly one field",
+            ),
+        }
+    }
+
+    /// The source field, if any
+    fn source_field(&self) -> Result<Option<syn::Member>> {
+        if self.impl_from {
+            return Ok(Some(self.field_for_from()?.0));
+        }
+        Ok(self.source.clone())
+    }
+
+    /// `Self::Name { a, b }`, `Self::Name(_0, _1)` or `Self::Name`, binding all fields
+    fn pattern(&self) -> TokenStream {
+        let name = &self.name;
+        let bindings = self.bindings().into_iter().map(|(_, binding)| binding);
+        match &self.fields {
+            syn::Fields::Named(_) => quote!(Self::#name { #(#bindings),* }),
+            syn::Fields::Unnamed(_) => quote!(Self::#name(#(#bindings),*)),
+            syn::Fields::Unit => quote!(Self::#name),
+        }
+    }
+    /// The names of the fields for `FormatTemplate`, with the bindings of `pattern`
+    fn bindings(&self) -> Vec<(String, TokenStream)> {
+        let binding = |(i, field): (usize, &syn::Field)| match &field.ident {
+            Some(ident) => (ident.unraw().to_string(), ident.to_token_stream()),
+            None => (i.to_string(), format_ident!("_{}", i).into_token_stream()),
+        };
+        self.fields.iter().enumerate().map(binding).collect()
+    }
+}
+
+/// The building blocks of a `thiserror`-like derive: `Display`, `Error` (with `source`) and
+/// `From` impls for an error enum. Parsing the attributes is left to the macro, so it can use
+/// its own attribute names and options.
+/// ```ignore
+/// let mut error = ErrorEnum::new(&input.ident, &input.generics);
+/// for variant in &data.variants {
+///     let attr = parse_error_attr(&variant.attrs)?; // #[error("..."), from]
+///     error = error.variant(ErrorVariant::new(variant).display(attr.format).impl_from(attr.from));
+/// }
+/// Ok(error.build()?)
+/// ```
+#[derive(Clone)]
+pub(crate) struct ErrorEnum {
+    name: Ident,
+    generics: syn::Generics,
+    variants: Vec<ErrorVariant>,
+}
+
+impl ErrorEnum {
+    /// Start an enum without variants
+    pub fn new(name: &Ident, generics: &syn::Generics) -> Self {
+        Self {
+            name: name.clone(),
+            generics: generics.clone(),
+            variants: vec![],
+        }
+    }
+    /// Add a variant
+    pub fn variant(mut self, variant: ErrorVariant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    /// `impl Display`. Returns an error at every variant without a format string and every
+    /// placeholder that doesn't refer to a field.
+    pub fn display_impl(&self) -> Result<TokenStream> {
+        let mut error = Error::builder();
+        let scrutinee = if self.variants.is_empty() {
+            quote!(*self)
+        } else {
+            quote!(self)
+        };
+        let mut arms = MatchBuilder::new(scrutinee).exhaustive(true);
+        for variant in &self.variants {
+            let Some(format) = &variant.display else {
+                error.with(
+                    variant.name.span(),
+                    format!("missing the display format for `{}`", variant.name),
+                );
+                continue;
+            };
+            let write = FormatTemplate::parse(format)
+                .and_then(|template| template.write(quote!(__formatter), &variant.bindings()));
+            match write {
+                Ok(write) => arms = arms.arm(variant.pattern(), write),
+                Err(e) => {
+                    error.with_error(e);
+                }
+            }
+        }
+        error.ok_or_build()?;
+        let body = quote! {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #arms
+            }
+        };
+        Ok(self.impl_for(quote!(::core::fmt::Display), body))
+    }
+
+    /// `impl Error` with `source`. Uses `StdPolicy::current()` for the path of `Error`.
+    pub fn error_impl(&self) -> Result<TokenStream> {
+        let error_trait = StdPolicy::current().path("error::Error")?;
+        let mut errors = Error::builder();
+        let mut arms = MatchBuilder::new(quote!(self));
+        for variant in &self.variants {
+            let source = match variant.source_field() {
+                Ok(Some(source)) => source,
+                Ok(None) => continue,
+                Err(e) => {
+                    errors.with_error(e);
+                    continue;
+                }
+            };
+            let name = &variant.name;
+            arms = arms.arm(
+                quote!(Self::#name { #source: __source, .. }),
+                quote!(::core::option::Option::Some(__source as &(dyn #error_trait + 'static))),
+            );
+        }
+        errors.ok_or_build()?;
+        let arms = arms.fallback(quote!(::core::option::Option::None));
+        let body = quote! {
+            fn source(&self) -> ::core::option::Option<&(dyn #error_trait + 'static)> {
+                #arms
+            }
+        };
+        Ok(self.impl_for(error_trait, body))
+    }
+
+    /// `impl From<FieldType>` for every variant with `impl_from`
+    pub fn conversion_impls(&self) -> Result<TokenStream> {
+        let mut errors = Error::builder();
+        let mut output = TokenRope::new();
+        for variant in self.variants.iter().filter(|variant| variant.impl_from) {
+            let (member, ty) = match variant.field_for_from() {
+                Ok(field) => field,
+                Err(e) => {
+                    errors.with_error(e);
+                    continue;
+                }
+            };
+            let name = &variant.name;
+            let body = quote! {
+                fn from(source: #ty) -> Self {
+                    Self::#name { #member: source }
+                }
+            };
+            output.push(self.impl_for(quote!(::core::convert::From<#ty>), body));
+        }
+        errors.ok_or_build()?;
+        Ok(output.finish())
+    }
+
+    /// All of `display_impl`, `error_impl` and `conversion_impls`, with the errors of all of them
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "ErrorEnum::build", skip_all)
+    )]
+    pub fn build(&self) -> Result<TokenStream> {
+        let mut errors = Error::builder();
+        let mut output = TokenRope::new();
+        for part in [
+            self.display_impl(),
+            self.error_impl(),
+            self.conversion_impls(),
+        ] {
+            match part {
+                Ok(tokens) => {
+                    output.push(tokens);
+                }
+                Err(e) => {
+                    errors.with_error(e);
+                }
+            }
+        }
+        errors.ok_or_build()?;
+        Ok(output.finish())
+    }
+
+    fn impl_for(&self, trait_path: impl ToTokens, body: TokenStream) -> TokenStream {
+        let name = &self.name;
+        let lints = LintPolicy::default();
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        quote! {
+            #lints
+            impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+                #body
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+#![allow(dead_code)]
+
+use super::super::tokens::TokenRope;
+use super::super::Edition;
+use proc_macro2::{Delimiter, Ident, Literal, Span, TokenStream, TokenTree};
+use quote::{quote_spanned, ToTokens};
+
+/// Helpers for common expression shapes, instead of small `quote!` templates.
+///
+/// The generated identifiers and punctuation get the span of the first token of the receiver
+/// (or path), so errors like "no method named `iter`" point at the user's code that the
+/// expression was generated from. Receivers that are not a simple path, field access or call
+/// (e.g. `a + b` or `&x`) are wrapped in parentheses.
+///
+/// All names are expected to come from the macro itself, so the helpers panic if they are not
+/// valid identifiers.
+/// ```ignore
+/// // self.items.iter().map(|item| item.len())
+/// let items = Expr::field_path(quote!(self), &["items"]);
+/// let expr = Expr::method_chain(items, &[("iter", &[]), ("map", &[quote!(|item| item.len())])]);
+/// ```
+pub(crate) struct Expr;
+
+impl Expr {
+    /// `receiver.name(args...)`
+    pub fn method_call(
+        receiver: impl ToTokens,
+        name: &str,
+        args: impl IntoIterator<Item = impl ToTokens>,
+    ) -> TokenStream {
+        let args: Vec<TokenStream> = args
+            .into_iter()
+            .map(|arg| arg.into_token_stream())
+            .collect();
+        Self::method_chain(receiver, &[(name, &args)])
+    }
+
+    /// `receiver.a(args...).b(args...)...`
+    pub fn method_chain(receiver: impl ToTokens, calls: &[(&str, &[TokenStream])]) -> TokenStream {
+        let receiver = receiver.into_token_stream();
+        let span = first_span(&receiver);
+        let mut output = TokenRope::new();
+        output.push(receiver_tokens(receiver));
+        for (name, args) in calls {
+            let name = Ident::new(name, span);
+            output.push(quote_spanned!(span=> .#name(#(#args),*)));
+        }
+        output.finish()
+    }
+
+    /// `receiver.a.b.0`. Numeric names are tuple fields.
+    pub fn field_path(receiver: impl ToTokens, fields: &[&str]) -> TokenStream {
+        let receiver = receiver.into_token_stream();
+        let span = first_span(&receiver);
+        let mut output = TokenRope::new();
+        output.push(receiver_tokens(receiver));
+        for field in fields {
+            let field = match field.parse::<usize>() {
+                Ok(index) => {
+                    let mut index = Literal::usize_unsuffixed(index);
+                    index.set_span(span);
+                    index.into_token_stream()
+                }
+                Err(_) => Ident::new(field, span).into_token_stream(),
+            };
+            output.push(quote_spanned!(span=> .#field));
+        }
+        output.finish()
+    }
+
+    /// `path(args...)`, e.g. `Expr::call(quote!(::core::convert::From::from), [value])`
+    pub fn call(path: impl ToTokens, args: impl IntoIterator<Item = impl ToTokens>) -> TokenStream {
+        let path = path.into_token_stream();
+        let span = first_span(&path);
+        let args = args.into_iter();
+        quote_spanned!(span=> #path(#(#args),*))
+    }
+
+    /// `|params...| body`
+    pub fn closure(
+        params: impl IntoIterator<Item = impl ToTokens>,
+        body: impl ToTokens,
+    ) -> TokenStream {
+        let body = body.into_token_stream();
+        let span = first_span(&body);
+        let params = params.into_iter();
+        quote_spanned!(span=> |#(#params),*| #body)
+    }
+
+    /// `&expr` or `&mut expr`
+    pub fn reference(expr: impl ToTokens, mutable: bool) -> TokenStream {
+        let expr = expr.into_token_stream();
+        let span = first_span(&expr);
+        let expr = receiver_tokens(expr);
+        if mutable {
+            quote_spanned!(span=> &mut #expr)
+        } else {
+            quote_spanned!(span=> &#expr)
+        }
+    }
+}
+
+fn first_span(tokens: &TokenStream) -> Span {
+    tokens
+        .clone()
+        .into_iter()
+        .next()
+        .map_or_else(Span::call_site, |token| token.span())
+}
+
+/// The receiver, in parentheses unless it is made of paths, field accesses, calls, indexing,
+/// literals and `?`, which all bind tighter than a method call
+fn receiver_tokens(receiver: TokenStream) -> TokenStream {
+    let simple = receiver.clone().into_iter().all(|token| match token {
+        TokenTree::Ident(ident) => {
+            let name = ident.to_string();
+            matches!(name.as_str(), "self" | "Self" | "crate" | "super")
+                || !Edition::LATEST.is_keyword(&name)
+        }
+        TokenTree::Punct(punct) => matches!(punct.as_char(), ':' | '.' | '?'),
+        TokenTree::Literal(_) => true,
+        TokenTree::Group(group) => group.delimiter() != Delimiter::None,
+    });
+    if simple && !receiver.is_empty() {
+        receiver
+    } else {
+        let span = first_span(&receiver);
+        quote_spanned!(span=> (#receiver))
+    }
+}
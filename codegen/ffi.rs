@@ -0,0 +1,314 @@
+#![allow(dead_code)]
+
+use super::super::ident::GeneratedNames;
+use super::super::tokens::{self, Template, TokenRope};
+use super::super::{Edition, Error, Result};
+use super::{FnBuilder, LintPolicy, StdPolicy};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+
+/// How values of a Rust type are passed through an `ExternWrapper`.
+///
+/// The conversions are code with a `__value__` placeholder (see `tokens::Template`). Both
+/// default to `::core::convert::Into::into(__value__)`.
+/// ```ignore
+/// // `&str` arguments are passed as C strings
+/// let c_str = FfiType::new(quote!(&str), quote!(*const ::core::ffi::c_char)).incoming(quote!(
+///     unsafe { ::core::ffi::CStr::from_ptr(__value__) }.to_str().unwrap_or_default()
+/// ));
+/// let bool_result = FfiType::new(quote!(bool), quote!(u8));
+/// ```
+#[derive(Clone)]
+pub(crate) struct FfiType {
+    rust: TokenStream,
+    ffi: TokenStream,
+    incoming: Template,
+    outgoing: Template,
+}
+
+impl FfiType {
+    /// Pass the Rust type `rust` as `ffi`
+    pub fn new(rust: impl ToTokens, ffi: impl ToTokens) -> Self {
+        let into = Template::new(quote!(::core::convert::Into::into(__value__)));
+        Self {
+            rust: rust.into_token_stream(),
+            ffi: ffi.into_token_stream(),
+            incoming: into.clone(),
+            outgoing: into,
+        }
+    }
+    /// Set the conversion of an argument from the FFI type to the Rust type
+    pub fn incoming(mut self, conversion: impl ToTokens) -> Self {
+        self.incoming = Template::new(conversion.into_token_stream());
+        self
+    }
+    /// Set the conversion of the return value from the Rust type to the FFI type
+    pub fn outgoing(mut self, conversion: impl ToTokens) -> Self {
+        self.outgoing = Template::new(conversion.into_token_stream());
+        self
+    }
+
+    /// The Rust type
+    pub fn rust(&self) -> &TokenStream {
+        &self.rust
+    }
+    /// The type used in the signature of the wrapper
+    pub fn ffi(&self) -> &TokenStream {
+        &self.ffi
+    }
+}
+
+/// The symbol name of an `ExternWrapper`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum FfiSymbol {
+    /// `#[no_mangle]`, the symbol is the name of the wrapper
+    NoMangle,
+    /// `#[export_name = "..."]`
+    Export(String),
+    /// No attribute, the symbol is mangled. For wrappers that are only passed to C as callbacks.
+    Mangled,
+}
+
+/// Generates an `extern "C"` wrapper around a Rust function, e.g. for a macro that exports
+/// functions to C.
+///
+/// Arguments and the return value can be converted with `FfiType`s, types without one are passed
+/// as they are. By default, the wrapper is named `__name_ffi` and exported as `name`, so that it
+/// doesn't collide with the original function.
+///
+/// Unwinding out of an `extern "C"` function aborts the process. With `catch_panics`, panics are
+/// caught and a fallback value is returned instead.
+/// ```ignore
+/// // #[export_c] fn parse(input: &str) -> bool { ... }
+/// let wrapper = ExternWrapper::new(&item.sig)
+///     .map_type(c_str)
+///     .map_type(bool_result)
+///     .catch_panics(quote!(0));
+/// // #[unsafe(export_name = "parse")]
+/// // pub extern "C" fn __parse_ffi(input: *const c_char) -> u8 { ... }
+/// output.extend(wrapper.build()?);
+/// ```
+#[derive(Clone)]
+pub(crate) struct ExternWrapper {
+    sig: syn::Signature,
+    name: Ident,
+    target: TokenStream,
+    symbol: FfiSymbol,
+    abi: String,
+    vis: TokenStream,
+    attrs: Vec<TokenStream>,
+    types: Vec<FfiType>,
+    on_panic: Option<TokenStream>,
+    lints: LintPolicy,
+}
+
+impl ExternWrapper {
+    /// Start a wrapper for the function with the given signature
+    pub fn new(sig: &syn::Signature) -> Self {
+        let name = GeneratedNames::new().fn_of(&sig.ident, "ffi");
+        Self {
+            sig: sig.clone(),
+            name,
+            target: sig.ident.to_token_stream(),
+            symbol: FfiSymbol::Export(sig.ident.to_string()),
+            abi: "C".to_string(),
+            vis: quote!(pub),
+            attrs: vec![],
+            types: vec![],
+            on_panic: None,
+            lints: LintPolicy::default(),
+        }
+    }
+
+    /// Set the name of the wrapper
+    pub fn name(mut self, name: Ident) -> Self {
+        self.name = name;
+        self
+    }
+    /// Set the path of the function that is called, e.g. if it is in a different module. The
+    /// default is the name from the signature.
+    pub fn target(mut self, path: impl ToTokens) -> Self {
+        self.target = path.into_token_stream();
+        self
+    }
+    /// Set the symbol name
+    pub fn symbol(mut self, symbol: FfiSymbol) -> Self {
+        self.symbol = symbol;
+        self
+    }
+    /// Set the ABI. The default is `"C"`.
+    pub fn abi(mut self, abi: &str) -> Self {
+        self.abi = abi.to_string();
+        self
+    }
+    /// Set the visibility. The default is `pub`.
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Add an attribute, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(attr.into_token_stream());
+        self
+    }
+    /// Convert all arguments and return values of the type. Later mappings of the same type
+    /// replace earlier ones.
+    pub fn map_type(mut self, mapping: FfiType) -> Self {
+        self.types
+            .retain(|existing| !tokens::eq(&existing.rust, &mapping.rust));
+        self.types.push(mapping);
+        self
+    }
+    /// Catch panics and return `value` instead, which has to be of the FFI return type. Uses
+    /// `catch_unwind`, so this needs `std`.
+    pub fn catch_panics(mut self, value: impl ToTokens) -> Self {
+        self.on_panic = Some(value.into_token_stream());
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// The name of the wrapper
+    pub fn wrapper_name(&self) -> &Ident {
+        &self.name
+    }
+
+    /// Render the wrapper. Returns an error for signatures that can't be exported: methods,
+    /// `async` and variadic functions and functions with type or const parameters.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "ExternWrapper::build", skip_all)
+    )]
+    pub fn build(&self) -> Result<TokenStream> {
+        let sig = &self.sig;
+        let mut error = Error::builder();
+        if let Some(asyncness) = &sig.asyncness {
+            error.with(asyncness.span, "`async` functions can't be called from C");
+        }
+        if let Some(variadic) = &sig.variadic {
+            error.with(
+                variadic.dots.spans[0],
+                "variadic functions can't be wrapped",
+            );
+        }
+        for param in &sig.generics.params {
+            if !matches!(param, syn::GenericParam::Lifetime(_)) {
+                error.with_error(Error::new_spanned(
+                    param,
+                    "generic functions can't be exported to C",
+                ));
+            }
+        }
+
+        let mut builder = FnBuilder::new(self.name.clone())
+            .vis(&self.vis)
+            .abi(Some(&self.abi))
+            .unsafety(sig.unsafety.is_some())
+            .lints(self.lints.clone());
+        if let Some(symbol) = self.symbol_attr() {
+            builder = builder.attr(symbol);
+        }
+        for attr in &self.attrs {
+            builder = builder.attr(attr);
+        }
+        for param in &sig.generics.params {
+            if let syn::GenericParam::Lifetime(lifetime) = param {
+                builder = builder.generic(lifetime);
+            }
+        }
+
+        let mut conversions = TokenRope::new();
+        let mut args = vec![];
+        for (i, input) in sig.inputs.iter().enumerate() {
+            let pat_type = match input {
+                syn::FnArg::Typed(pat_type) => pat_type,
+                syn::FnArg::Receiver(receiver) => {
+                    error.with_error(Error::new_spanned(
+                        receiver,
+                        "methods can't be exported to C, wrap them in a free function",
+                    ));
+                    continue;
+                }
+            };
+            let arg = match &*pat_type.pat {
+                syn::Pat::Ident(pat) if pat.subpat.is_none() => pat.ident.clone(),
+                _ => format_ident!("__arg{}", i),
+            };
+            let ty = &pat_type.ty;
+            match self.mapping(ty) {
+                Some(mapping) => {
+                    builder = builder.arg(&arg, &mapping.ffi);
+                    match mapping.incoming.fill(&[("value", arg.to_token_stream())]) {
+                        Ok(value) => {
+                            conversions.push(quote!(let #arg: #ty = #value;));
+                        }
+                        Err(e) => {
+                            error.with_error(e);
+                        }
+                    }
+                }
+                None => builder = builder.arg(&arg, ty),
+            }
+            args.push(arg);
+        }
+        error.ok_or_build()?;
+        let conversions = conversions.finish();
+
+        let target = &self.target;
+        let mut call = quote!(#target(#(#args),*));
+        if sig.unsafety.is_some() {
+            call = quote!(unsafe { #call });
+        }
+        let mut result = quote! {
+            #conversions
+            #call
+        };
+        if let syn::ReturnType::Type(_, ty) = &sig.output {
+            match self.mapping(ty) {
+                Some(mapping) => {
+                    let value = mapping.outgoing.fill(&[("value", quote!(__result))])?;
+                    result = quote! {
+                        let __result: #ty = { #result };
+                        #value
+                    };
+                    builder = builder.ret(&mapping.ffi);
+                }
+                None => builder = builder.ret(ty),
+            }
+        }
+        if let Some(on_panic) = &self.on_panic {
+            let catch_unwind = StdPolicy::current().path("panic::catch_unwind")?;
+            result = quote! {
+                match #catch_unwind(::core::panic::AssertUnwindSafe(move || { #result })) {
+                    ::core::result::Result::Ok(__value) => __value,
+                    ::core::result::Result::Err(_) => #on_panic,
+                }
+            };
+        }
+        Ok(builder.body(result).build())
+    }
+
+    fn mapping(&self, ty: &syn::Type) -> Option<&FfiType> {
+        let ty = ty.to_token_stream();
+        self.types
+            .iter()
+            .find(|mapping| tokens::eq(&mapping.rust, &ty))
+    }
+
+    /// `no_mangle` or `export_name = "..."`, wrapped in `unsafe(...)` since edition 2024
+    fn symbol_attr(&self) -> Option<TokenStream> {
+        let attr = match &self.symbol {
+            FfiSymbol::NoMangle => quote!(no_mangle),
+            FfiSymbol::Export(name) => quote!(export_name = #name),
+            FfiSymbol::Mangled => return None,
+        };
+        if Edition::current() >= Edition::E2024 {
+            Some(quote!(unsafe(#attr)))
+        } else {
+            Some(attr)
+        }
+    }
+}
@@ -0,0 +1,276 @@
+#![allow(dead_code)]
+
+use super::super::{IdePolicy, RustFeature};
+use super::{LintPolicy, Provenance};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
+
+/// A builder for a function or method, for when parts of the signature are only known at runtime.
+/// Optional qualifiers like `async` or `unsafe` would otherwise need a separate `quote!` for
+/// every combination.
+/// ```ignore
+/// let getter = FnBuilder::new(format_ident!("get_{}", field))
+///     .vis(quote!(pub))
+///     .constness(is_copy)
+///     .receiver(quote!(&self))
+///     .ret(quote!(&#ty))
+///     .body(quote!(&self.#field));
+/// ```
+#[derive(Clone)]
+pub(crate) struct FnBuilder {
+    name: Ident,
+    attrs: Vec<TokenStream>,
+    vis: TokenStream,
+    constness: bool,
+    asyncness: bool,
+    unsafety: bool,
+    abi: Option<String>,
+    generics: Vec<TokenStream>,
+    receiver: Option<TokenStream>,
+    args: Vec<(TokenStream, TokenStream)>,
+    ret: Option<TokenStream>,
+    where_predicates: Vec<TokenStream>,
+    body: Option<TokenStream>,
+    lints: LintPolicy,
+    provenance: Option<Provenance>,
+}
+
+impl FnBuilder {
+    /// Start a function with the given name, private and with an empty body
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            attrs: vec![],
+            vis: TokenStream::new(),
+            constness: false,
+            asyncness: false,
+            unsafety: false,
+            abi: None,
+            generics: vec![],
+            receiver: None,
+            args: vec![],
+            ret: None,
+            where_predicates: vec![],
+            body: Some(TokenStream::new()),
+            lints: LintPolicy::default(),
+            provenance: Provenance::current(),
+        }
+    }
+
+    /// Add an attribute, given without `#[...]`: `.attr(quote!(inline))`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(quote!(#[#attr]));
+        self
+    }
+    /// Copy existing attributes, e.g. doc comments and `#[cfg]`s from the input
+    pub fn attrs<'a>(mut self, attrs: impl IntoIterator<Item = &'a syn::Attribute>) -> Self {
+        self.attrs
+            .extend(attrs.into_iter().map(ToTokens::into_token_stream));
+        self
+    }
+    /// Set the visibility: `.vis(quote!(pub(crate)))` or `.vis(&input.vis)`
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Make the function `const` or not
+    pub fn constness(mut self, constness: bool) -> Self {
+        self.constness = constness;
+        self
+    }
+    /// Make the function `async` or not
+    pub fn asyncness(mut self, asyncness: bool) -> Self {
+        self.asyncness = asyncness;
+        self
+    }
+    /// Make the function `unsafe` or not
+    pub fn unsafety(mut self, unsafety: bool) -> Self {
+        self.unsafety = unsafety;
+        self
+    }
+    /// Set the ABI: `.abi(Some("C"))` for `extern "C" fn`
+    pub fn abi(mut self, abi: Option<&str>) -> Self {
+        self.abi = abi.map(str::to_string);
+        self
+    }
+
+    /// Add a generic parameter: `.generic(quote!(T: Clone))`
+    pub fn generic(mut self, param: impl ToTokens) -> Self {
+        self.generics.push(param.into_token_stream());
+        self
+    }
+    /// Copy the parameters and where clause of existing generics
+    pub fn generics(mut self, generics: &syn::Generics) -> Self {
+        self.generics
+            .extend(generics.params.iter().map(ToTokens::into_token_stream));
+        if let Some(where_clause) = &generics.where_clause {
+            self.where_predicates.extend(
+                where_clause
+                    .predicates
+                    .iter()
+                    .map(ToTokens::into_token_stream),
+            );
+        }
+        self
+    }
+    /// Set the receiver: `.receiver(quote!(&mut self))`
+    pub fn receiver(mut self, receiver: impl ToTokens) -> Self {
+        self.receiver = Some(receiver.into_token_stream());
+        self
+    }
+    /// Add an argument with a pattern (usually just a name) and a type
+    pub fn arg(mut self, pat: impl ToTokens, ty: impl ToTokens) -> Self {
+        self.args
+            .push((pat.into_token_stream(), ty.into_token_stream()));
+        self
+    }
+    /// Set the return type. The default is `()`.
+    pub fn ret(mut self, ty: impl ToTokens) -> Self {
+        self.ret = Some(ty.into_token_stream());
+        self
+    }
+    /// Add a predicate to the where clause: `.where_predicate(quote!(T: Default))`
+    pub fn where_predicate(mut self, predicate: impl ToTokens) -> Self {
+        self.where_predicates.push(predicate.into_token_stream());
+        self
+    }
+
+    /// Set the statements of the body, without the surrounding braces
+    pub fn body(mut self, body: impl ToTokens) -> Self {
+        self.body = Some(body.into_token_stream());
+        self
+    }
+    /// Remove the body, ending the signature with `;` as in a trait or `extern` block
+    pub fn no_body(mut self) -> Self {
+        self.body = None;
+        self
+    }
+
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+    /// Replace the provenance note on the output, or remove it with `None`, see `Provenance`
+    pub fn provenance(mut self, provenance: Option<Provenance>) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// The name of the function
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+    /// The span of the function's name
+    pub fn span(&self) -> Span {
+        self.name.span()
+    }
+
+    /// Render the function. A `const fn` with trait bounds is rendered with a compile error if
+    /// the `TargetRustVersion` doesn't support `RustFeature::ConstFnTraitBounds`.
+    pub fn build(&self) -> TokenStream {
+        let Self {
+            name,
+            attrs,
+            vis,
+            constness,
+            asyncness,
+            unsafety,
+            abi,
+            generics,
+            receiver,
+            args,
+            ret,
+            where_predicates,
+            body,
+            lints,
+            provenance,
+        } = self;
+        let span = IdePolicy::current().item_span(name);
+        let bounds_error = if *constness && has_trait_bounds(generics, where_predicates) {
+            let required = RustFeature::ConstFnTraitBounds.require(name.span());
+            required.err().map(TokenStream::from)
+        } else {
+            None
+        };
+        let constness = constness.then(|| quote_spanned!(span=> const));
+        let asyncness = asyncness.then(|| quote_spanned!(span=> async));
+        let unsafety = unsafety.then(|| quote_spanned!(span=> unsafe));
+        let abi = abi.as_ref().map(|abi| {
+            let abi = proc_macro2::Literal::string(abi);
+            quote_spanned!(span=> extern #abi)
+        });
+        let generics = (!generics.is_empty()).then(|| quote_spanned!(span=> <#(#generics),*>));
+        let receiver = receiver.iter();
+        let args = args
+            .iter()
+            .map(|(pat, ty)| quote_spanned!(span=> #pat: #ty));
+        let ret = ret.as_ref().map(|ty| quote_spanned!(span=> -> #ty));
+        let where_clause = (!where_predicates.is_empty())
+            .then(|| quote_spanned!(span=> where #(#where_predicates,)*));
+        let body = match body {
+            Some(body) => quote_spanned!(span=> { #body }),
+            None => quote_spanned!(span=> ;),
+        };
+        quote_spanned! {span=>
+            #lints
+            #(#attrs)*
+            #provenance
+            #vis #constness #asyncness #unsafety #abi fn #name #generics(#(#receiver,)* #(#args),*) #ret
+            #where_clause
+            #body
+            #bounds_error
+        }
+    }
+}
+
+/// Whether the generic parameters or where predicates bound a type by a trait, as opposed to
+/// only lifetimes (`T: 'a`, `'a: 'b`) and const parameters
+fn has_trait_bounds(generics: &[TokenStream], where_predicates: &[TokenStream]) -> bool {
+    let is_trait = |bound: &syn::TypeParamBound| matches!(bound, syn::TypeParamBound::Trait(_));
+    generics.iter().any(
+        |param| match syn::parse2::<syn::GenericParam>(param.clone()) {
+            Ok(syn::GenericParam::Type(param)) => param.bounds.iter().any(is_trait),
+            _ => false,
+        },
+    ) || where_predicates.iter().any(|predicate| {
+        match syn::parse2::<syn::WherePredicate>(predicate.clone()) {
+            Ok(syn::WherePredicate::Type(predicate)) => predicate.bounds.iter().any(is_trait),
+            _ => false,
+        }
+    })
+}
+
+impl ToTokens for FnBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::TargetRustVersion;
+    use super::*;
+
+    fn has_bounds_error(builder: FnBuilder) -> bool {
+        let output = TargetRustVersion::new(60).scoped(|| builder.constness(true).build());
+        output.to_string().contains("compile_error")
+    }
+
+    #[test]
+    fn lifetime_bounds_are_not_trait_bounds() {
+        let f = || FnBuilder::new(quote::format_ident!("f")).provenance(None);
+        assert!(!has_bounds_error(
+            f().generic(quote!('a)).generic(quote!(T: 'a))
+        ));
+        assert!(!has_bounds_error(
+            f().generic(quote!(T)).where_predicate(quote!(T: 'static))
+        ));
+        assert!(!has_bounds_error(f().where_predicate(quote!('a: 'b))));
+        assert!(!has_bounds_error(f().generic(quote!(const N: usize))));
+        assert!(has_bounds_error(f().generic(quote!(T: 'a + Clone))));
+        assert!(has_bounds_error(
+            f().generic(quote!(T)).where_predicate(quote!(T: Clone))
+        ));
+    }
+}
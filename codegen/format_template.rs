@@ -0,0 +1,252 @@
+#![allow(dead_code)]
+
+use super::super::{lit, Error, Result};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use std::ops::Range;
+
+/// A part of a `FormatTemplate`
+#[derive(Clone)]
+enum Part {
+    /// Text, with `{{` and `}}` still escaped
+    Text(String),
+    /// A placeholder `{name:spec}`
+    Placeholder {
+        name: String,
+        range: Range<usize>,
+        spec: Option<Spec>,
+    },
+}
+
+/// A format spec, split at `name$` references: `>width$` has the pieces `[">", ""]` and the
+/// reference `width`
+#[derive(Clone)]
+struct Spec {
+    pieces: Vec<String>,
+    refs: Vec<(String, Range<usize>)>,
+}
+
+/// A format string with `{field}` placeholders from the user, e.g. from a doc comment or an
+/// attribute like `#[error("invalid value {value:?} at {0}")]`.
+///
+/// Unlike passing the string to `write!` directly, the placeholders are checked against the
+/// fields that are actually available, with an error at the placeholder for unknown names.
+/// Placeholders are names or tuple indices, with the usual format specs. Width and precision
+/// can refer to fields as well: `{value:>width$}`. Positional `{}` is not allowed, since there
+/// are no arguments to refer to.
+/// ```ignore
+/// // #[display("{name} is {age} years old")]
+/// let template = FormatTemplate::parse(&lit)?;
+/// let fields: Vec<_> = named_fields.iter().map(|f| (f.to_string(), quote!(self.#f))).collect();
+/// // ::core::write!(f, "{0} is {1} years old", self.name, self.age)
+/// let body = template.write(quote!(f), &fields)?;
+/// ```
+#[derive(Clone)]
+pub(crate) struct FormatTemplate {
+    lit: syn::LitStr,
+    parts: Vec<Part>,
+}
+
+impl FormatTemplate {
+    /// Parse the placeholders of a format string. Returns an error for unmatched braces and
+    /// invalid placeholders.
+    pub fn parse(lit: &syn::LitStr) -> Result<Self> {
+        let value = lit.value();
+        let error_at =
+            |range: Range<usize>, message: &str| Error::new(lit::value_span(lit, range), message);
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut i = 0;
+        while let Some(offset) = value[i..].find(['{', '}']) {
+            let start = i + offset;
+            text.push_str(&value[i..start]);
+            let brace = &value[start..start + 1];
+            if value[start + 1..].starts_with(brace) {
+                text.push_str(&value[start..start + 2]);
+                i = start + 2;
+                continue;
+            }
+            if brace == "}" {
+                return Err(error_at(
+                    start..start + 1,
+                    "unmatched `}` in format string, use `}}` for a literal `}`",
+                ));
+            }
+            let Some(len) = value[start..].find('}') else {
+                return Err(error_at(
+                    start..start + 1,
+                    "unclosed `{` in format string, use `{{` for a literal `{`",
+                ));
+            };
+            let end = start + len + 1;
+            let inner = &value[start + 1..end - 1];
+            let (name, spec) = match inner.split_once(':') {
+                Some((name, spec)) => (name.trim(), Some(spec)),
+                None => (inner.trim(), None),
+            };
+            if !is_field_name(name) {
+                let message = if name.is_empty() {
+                    "positional `{}` is not supported, use a field name like `{name}` or `{0}`"
+                } else {
+                    "expected a field name or index"
+                };
+                return Err(error_at(start..end, message));
+            }
+            let spec = spec.map(|spec| {
+                let spec_start = end - 1 - spec.len();
+                split_spec(spec, spec_start)
+            });
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(Part::Placeholder {
+                name: name.strip_prefix("r#").unwrap_or(name).to_string(),
+                range: start..end,
+                spec,
+            });
+            i = end;
+        }
+        text.push_str(&value[i..]);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self {
+            lit: lit.clone(),
+            parts,
+        })
+    }
+
+    /// The names of all fields used by the template, in order of their first occurrence
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec![];
+        for (name, _) in self.references() {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+
+    /// The format string with positional placeholders, and the expressions for them. `fields`
+    /// are the names that can be used, with the expressions they refer to. Returns an error at
+    /// every placeholder that refers to an unknown field.
+    pub fn args<N: AsRef<str>>(
+        &self,
+        fields: &[(N, TokenStream)],
+    ) -> Result<(syn::LitStr, Vec<TokenStream>)> {
+        let mut error = Error::builder();
+        let mut used: Vec<String> = vec![];
+        let mut index_of = |name: &str, range: &Range<usize>| -> usize {
+            if !fields.iter().any(|(n, _)| n.as_ref() == name) {
+                let available: Vec<&str> = fields.iter().map(|(n, _)| n.as_ref()).collect();
+                let message = if available.is_empty() {
+                    format!("unknown field `{}`, there are no fields", name)
+                } else {
+                    format!(
+                        "unknown field `{}`, available are: {}",
+                        name,
+                        available.join(", ")
+                    )
+                };
+                error.with(lit::value_span(&self.lit, range.clone()), message);
+            }
+            match used.iter().position(|n| n == name) {
+                Some(index) => index,
+                None => {
+                    used.push(name.to_string());
+                    used.len() - 1
+                }
+            }
+        };
+        let mut format = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => format.push_str(text),
+                Part::Placeholder { name, range, spec } => {
+                    format += &format!("{{{}", index_of(name, range));
+                    if let Some(Spec { pieces, refs }) = spec {
+                        format.push(':');
+                        for (i, piece) in pieces.iter().enumerate() {
+                            format.push_str(piece);
+                            if let Some((name, range)) = refs.get(i) {
+                                format += &format!("{}$", index_of(name, range));
+                            }
+                        }
+                    }
+                    format.push('}');
+                }
+            }
+        }
+        error.ok_or_build()?;
+        let args = used
+            .iter()
+            .filter_map(|name| fields.iter().find(|(n, _)| n.as_ref() == name))
+            .map(|(_, expr)| expr.clone())
+            .collect();
+        Ok((syn::LitStr::new(&format, self.lit.span()), args))
+    }
+
+    /// `::core::write!(formatter, "...", args...)`, see `args`
+    pub fn write<N: AsRef<str>>(
+        &self,
+        formatter: impl ToTokens,
+        fields: &[(N, TokenStream)],
+    ) -> Result<TokenStream> {
+        let (format, args) = self.args(fields)?;
+        Ok(quote!(::core::write!(#formatter, #format #(, #args)*)))
+    }
+
+    /// `::core::format_args!("...", args...)`, e.g. for logging macros, see `args`
+    pub fn format_args<N: AsRef<str>>(&self, fields: &[(N, TokenStream)]) -> Result<TokenStream> {
+        let (format, args) = self.args(fields)?;
+        Ok(quote!(::core::format_args!(#format #(, #args)*)))
+    }
+
+    /// The span of the format string
+    pub fn span(&self) -> Span {
+        self.lit.span()
+    }
+
+    /// All field references, including those in format specs
+    fn references(&self) -> impl Iterator<Item = (&str, &Range<usize>)> {
+        self.parts.iter().flat_map(|part| match part {
+            Part::Text(_) => vec![],
+            Part::Placeholder { name, range, spec } => {
+                let mut refs = vec![(name.as_str(), range)];
+                if let Some(spec) = spec {
+                    refs.extend(spec.refs.iter().map(|(name, range)| (name.as_str(), range)));
+                }
+                refs
+            }
+        })
+    }
+}
+
+/// An identifier or a tuple index
+fn is_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && (name.bytes().all(|b| b.is_ascii_digit())
+            || super::super::ident::is_valid_ident(name.strip_prefix("r#").unwrap_or(name)))
+}
+
+/// Split a format spec at `name$` references. `offset` is the position of the spec in the value.
+fn split_spec(spec: &str, offset: usize) -> Spec {
+    let mut pieces = vec![];
+    let mut refs = vec![];
+    let mut piece_start = 0;
+    for (dollar, _) in spec.match_indices('$') {
+        let name_start = spec[..dollar]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let name = &spec[name_start..dollar];
+        if name_start < piece_start || !is_field_name(name) {
+            continue;
+        }
+        pieces.push(spec[piece_start..name_start].to_string());
+        let name = name.strip_prefix("r#").unwrap_or(name);
+        refs.push((name.to_string(), offset + name_start..offset + dollar + 1));
+        piece_start = dollar + 1;
+    }
+    pieces.push(spec[piece_start..].to_string());
+    Spec { pieces, refs }
+}
@@ -0,0 +1,255 @@
+#![allow(dead_code)]
+
+use super::super::tokens::{is_punct_seq, TokenRope};
+use super::super::{Error, Result};
+use super::LintPolicy;
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+
+/// Attributes of the trait methods that are copied to the inherent methods
+const COPIED_ATTRS: &[&str] = &["doc", "cfg", "must_use", "deprecated"];
+
+/// Generates inherent methods that forward to the methods of a trait impl, so that they can be
+/// called without the trait in scope (like the `inherent` crate).
+///
+/// The methods are taken from an `impl Trait for Type` block, including its generics and where
+/// clause. Docs (and `#[cfg]`, `#[must_use]` and `#[deprecated]`) are copied from the impl, or
+/// from the trait definition if it is available and the impl has none. `Self::Assoc` for the
+/// associated types of the impl is replaced with `<Self as Trait>::Assoc`, since inherent
+/// associated types don't exist.
+/// ```ignore
+/// // #[inherent] impl Display for Foo { fn fmt(&self, f: &mut Formatter) -> fmt::Result { ... } }
+/// let forwarder = InherentForwarder::new(&item)?.vis(quote!(pub)).build()?;
+/// Ok(quote! { #item #forwarder })
+/// ```
+#[derive(Clone)]
+pub(crate) struct InherentForwarder {
+    item: syn::ItemImpl,
+    trait_path: syn::Path,
+    trait_def: Option<syn::ItemTrait>,
+    only: Option<Vec<Ident>>,
+    vis: TokenStream,
+    lints: LintPolicy,
+}
+
+impl InherentForwarder {
+    /// Forward all methods of a trait impl. Returns an error if the impl is not a trait impl.
+    pub fn new(item: &syn::ItemImpl) -> Result<Self> {
+        let Some((negative, trait_path, _)) = &item.trait_ else {
+            return Error::err_spanned(
+                item.self_ty.as_ref(),
+                "expected a trait impl: `impl Trait for Type`",
+            );
+        };
+        if let Some(negative) = negative {
+            return Error::err_spanned(negative, "negative impls have no methods to forward");
+        }
+        Ok(Self {
+            item: item.clone(),
+            trait_path: trait_path.clone(),
+            trait_def: None,
+            only: None,
+            vis: quote!(pub),
+            lints: LintPolicy::default(),
+        })
+    }
+
+    /// Use the docs of the trait's methods for the methods that have none in the impl
+    pub fn trait_def(mut self, def: &syn::ItemTrait) -> Self {
+        self.trait_def = Some(def.clone());
+        self
+    }
+    /// Only forward the methods with these names
+    pub fn only(mut self, names: impl IntoIterator<Item = Ident>) -> Self {
+        self.only = Some(names.into_iter().collect());
+        self
+    }
+    /// Set the visibility of the methods. The default is `pub`.
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// Render the inherent impl. Returns an error at every name passed to `only` that is not a
+    /// method of the impl.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "InherentForwarder::build", skip_all)
+    )]
+    pub fn build(&self) -> Result<TokenStream> {
+        let methods: Vec<&syn::ImplItemFn> = self
+            .item
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::ImplItem::Fn(method) => Some(method),
+                _ => None,
+            })
+            .collect();
+        if let Some(only) = &self.only {
+            let mut error = Error::builder();
+            for name in only {
+                if !methods.iter().any(|method| method.sig.ident == *name) {
+                    error.with(
+                        name.span(),
+                        format!("`{}` is not a method of this impl", name),
+                    );
+                }
+            }
+            error.ok_or_build()?;
+        }
+
+        let forwarded = methods
+            .iter()
+            .filter(|method| match &self.only {
+                Some(only) => only.contains(&method.sig.ident),
+                None => true,
+            })
+            .map(|method| self.forward(method));
+        let syn::ItemImpl {
+            generics, self_ty, ..
+        } = &self.item;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let lints = &self.lints;
+        Ok(quote! {
+            #lints
+            impl #impl_generics #self_ty #where_clause {
+                #(#forwarded)*
+            }
+        })
+    }
+
+    fn forward(&self, method: &syn::ImplItemFn) -> TokenStream {
+        let mut sig = method.sig.clone();
+        let name = &sig.ident;
+        let mut args = vec![];
+        for (i, input) in sig.inputs.iter_mut().enumerate() {
+            match input {
+                syn::FnArg::Receiver(_) => args.push(quote!(self)),
+                syn::FnArg::Typed(pat_type) => {
+                    let arg = match &*pat_type.pat {
+                        syn::Pat::Ident(pat) if pat.subpat.is_none() => pat.ident.clone(),
+                        _ => format_ident!("__arg{}", i),
+                    };
+                    *pat_type.pat = syn::parse_quote!(#arg);
+                    pat_type.attrs.clear();
+                    args.push(arg.into_token_stream());
+                }
+            }
+        }
+        let turbofish: Vec<&Ident> = sig
+            .generics
+            .params
+            .iter()
+            .filter_map(|param| match param {
+                syn::GenericParam::Type(param) => Some(&param.ident),
+                syn::GenericParam::Const(param) => Some(&param.ident),
+                syn::GenericParam::Lifetime(_) => None,
+            })
+            .collect();
+        let turbofish = (!turbofish.is_empty()).then(|| quote!(::<#(#turbofish),*>));
+
+        let trait_path = &self.trait_path;
+        let mut call = quote!(<Self as #trait_path>::#name #turbofish(#(#args),*));
+        if sig.asyncness.is_some() {
+            call = quote!(#call.await);
+        }
+        if sig.unsafety.is_some() {
+            call = quote!(unsafe { #call });
+        }
+        let sig = self.qualify_assoc_types(sig.into_token_stream());
+        let attrs = self.copied_attrs(method);
+        let vis = &self.vis;
+        quote! {
+            #(#attrs)*
+            #[inline]
+            #vis #sig {
+                #call
+            }
+        }
+    }
+
+    /// The attributes of the impl method, or the docs of the trait method
+    fn copied_attrs(&self, method: &syn::ImplItemFn) -> Vec<syn::Attribute> {
+        let copied =
+            |attr: &&syn::Attribute| COPIED_ATTRS.iter().any(|name| attr.path().is_ident(name));
+        let mut attrs: Vec<syn::Attribute> = method.attrs.iter().filter(copied).cloned().collect();
+        if attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+            return attrs;
+        }
+        let trait_method = self
+            .trait_def
+            .iter()
+            .flat_map(|def| &def.items)
+            .find_map(|item| match item {
+                syn::TraitItem::Fn(trait_method) if trait_method.sig.ident == method.sig.ident => {
+                    Some(trait_method)
+                }
+                _ => None,
+            });
+        if let Some(trait_method) = trait_method {
+            let docs = trait_method
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"));
+            attrs.extend(docs.cloned());
+        }
+        attrs
+    }
+
+    /// Replace `Self::Assoc` with `<Self as Trait>::Assoc` for the associated types of the impl
+    fn qualify_assoc_types(&self, stream: TokenStream) -> TokenStream {
+        let assoc_types: Vec<&Ident> = self
+            .item
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::ImplItem::Type(ty) => Some(&ty.ident),
+                _ => None,
+            })
+            .collect();
+        if assoc_types.is_empty() {
+            return stream;
+        }
+        qualify_self_paths(stream, &assoc_types, &self.trait_path)
+    }
+}
+
+fn qualify_self_paths(
+    stream: TokenStream,
+    names: &[&Ident],
+    trait_path: &syn::Path,
+) -> TokenStream {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut output = TokenRope::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let is_assoc_path = matches!(&tokens[i], TokenTree::Ident(ident) if ident == "Self")
+            && is_punct_seq(&tokens[i + 1..], "::")
+            && matches!(tokens.get(i + 3), Some(TokenTree::Ident(name)) if names.contains(&name));
+        if is_assoc_path {
+            let self_token = &tokens[i];
+            output.push(quote!(<#self_token as #trait_path>));
+            i += 1;
+            continue;
+        }
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                let stream = qualify_self_paths(group.stream(), names, trait_path);
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                output.push_tree(TokenTree::Group(new_group));
+            }
+            token => {
+                output.push_tree(token.clone());
+            }
+        }
+        i += 1;
+    }
+    output.finish()
+}
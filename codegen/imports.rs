@@ -0,0 +1,249 @@
+#![allow(dead_code)]
+
+use super::super::tokens::{walk_mut, TokenRope, TokenVisitorMut};
+use super::super::{Error, Result};
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use std::collections::BTreeMap;
+
+/// How `UseBuilder::apply` makes the imported names available
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ImportStyle {
+    /// Emit (merged) `use` statements before the code
+    Use,
+    /// Replace every use of an imported name in the code with its full path, so the code doesn't
+    /// depend on any imports (e.g. for code that is spliced into a user's function body)
+    Qualified,
+}
+
+#[derive(Clone)]
+struct Import {
+    path: syn::Path,
+    alias: Option<Ident>,
+}
+
+impl Import {
+    /// The name that the import makes available
+    fn name(&self) -> Option<&Ident> {
+        self.alias
+            .as_ref()
+            .or_else(|| self.path.segments.last().map(|segment| &segment.ident))
+    }
+    fn key(&self) -> String {
+        path_key(&self.path)
+    }
+}
+
+/// Collects the imports needed by generated code from several codegen stages, then either emits
+/// deduplicated and merged `use` statements or rewrites the code to use full paths.
+/// ```ignore
+/// let mut imports = UseBuilder::new();
+/// imports.add(parse_quote!(::core::fmt::Display));
+/// imports.add(parse_quote!(::core::fmt::Formatter));
+/// let code = quote!(impl Display for Foo { fn fmt(&self, f: &mut Formatter) -> ... });
+/// // use ::core::fmt::{Display, Formatter}; impl Display for Foo ...
+/// imports.apply(code, ImportStyle::Use)?
+/// // impl ::core::fmt::Display for Foo { fn fmt(&self, f: &mut ::core::fmt::Formatter) ...
+/// imports.apply(code, ImportStyle::Qualified)?
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct UseBuilder {
+    imports: Vec<Import>,
+}
+
+impl UseBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Import a path. Generic arguments in the path are ignored, and adding the same path twice
+    /// has no effect.
+    pub fn add(&mut self, path: syn::Path) -> &mut Self {
+        self.push(Import { path, alias: None })
+    }
+    /// Import a path under a different name: `use path as alias;`
+    pub fn add_as(&mut self, path: syn::Path, alias: Ident) -> &mut Self {
+        self.push(Import {
+            path,
+            alias: Some(alias),
+        })
+    }
+    /// Add all imports of another builder
+    pub fn merge(&mut self, other: &UseBuilder) -> &mut Self {
+        for import in &other.imports {
+            self.push(import.clone());
+        }
+        self
+    }
+
+    /// Check if nothing was imported
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty()
+    }
+
+    fn push(&mut self, mut import: Import) -> &mut Self {
+        for segment in &mut import.path.segments {
+            segment.arguments = syn::PathArguments::None;
+        }
+        let is_duplicate = self
+            .imports
+            .iter()
+            .any(|other| other.key() == import.key() && other.name() == import.name());
+        if !is_duplicate {
+            self.imports.push(import);
+        }
+        self
+    }
+
+    /// Returns an error at every import whose name is already used by a different path
+    fn check_conflicts(&self) -> Result<()> {
+        let mut error = Error::builder();
+        for (i, import) in self.imports.iter().enumerate() {
+            let conflict = self.imports[..i]
+                .iter()
+                .find(|other| other.name() == import.name() && other.key() != import.key());
+            if let (Some(other), Some(name)) = (conflict, import.name()) {
+                error.with_spanned(
+                    &import.path,
+                    format!(
+                        "conflicting imports for `{}`: `{}` and `{}`",
+                        name,
+                        other.key(),
+                        import.key()
+                    ),
+                );
+            }
+        }
+        error.ok_or_build()
+    }
+
+    /// Render the imports as `use` statements, merging imports with the same parent path:
+    /// `use crate::a::{B, C};`. Returns an error if two different paths are imported under the
+    /// same name.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "UseBuilder::build", skip_all)
+    )]
+    pub fn build(&self) -> Result<TokenStream> {
+        self.check_conflicts()?;
+        let mut groups: BTreeMap<String, (TokenStream, Vec<TokenStream>)> = BTreeMap::new();
+        for import in &self.imports {
+            let mut parent = import.path.clone();
+            let Some(last) = parent.segments.pop() else {
+                continue;
+            };
+            let last = last.into_value().ident;
+            let item = match &import.alias {
+                Some(alias) => quote!(#last as #alias),
+                None => last.into_token_stream(),
+            };
+            let key = path_key(&parent);
+            let entry = groups
+                .entry(key)
+                .or_insert_with(|| (parent.into_token_stream(), vec![]));
+            if !entry
+                .1
+                .iter()
+                .any(|other| other.to_string() == item.to_string())
+            {
+                entry.1.push(item);
+            }
+        }
+        let mut out = TokenRope::new();
+        for (_, (parent, mut items)) in groups {
+            items.sort_by_key(|item| item.to_string());
+            let statement = match &items[..] {
+                [single] if parent.is_empty() => quote!(use #single;),
+                [single] => quote!(use #parent #single;),
+                _ if parent.is_empty() => quote!(use {#(#items),*};),
+                _ => quote!(use #parent {#(#items),*};),
+            };
+            out.push(statement);
+        }
+        Ok(out.finish())
+    }
+
+    /// Replace every identifier in `code` that refers to an import with the import's full path.
+    /// Identifiers that are fields, methods or the later segments of a path (`x.Display`,
+    /// `fmt::Display`) are not replaced. Returns an error if two different paths are imported
+    /// under the same name.
+    pub fn qualify(&self, code: TokenStream) -> Result<TokenStream> {
+        self.check_conflicts()?;
+        Ok(walk_mut(
+            code,
+            &mut Qualify {
+                imports: &self.imports,
+                prev: None,
+            },
+        ))
+    }
+
+    /// Either `build` the imports and put them before `code`, or `qualify` the code
+    pub fn apply(&self, code: TokenStream, style: ImportStyle) -> Result<TokenStream> {
+        match style {
+            ImportStyle::Use => {
+                let mut out = self.build()?;
+                out.extend(code);
+                Ok(out)
+            }
+            ImportStyle::Qualified => self.qualify(code),
+        }
+    }
+}
+
+/// The path as written, e.g. `::core::fmt::`, used for comparing and grouping. Ends with `::`
+/// unless empty.
+fn path_key(path: &syn::Path) -> String {
+    let mut key = String::new();
+    if path.leading_colon.is_some() {
+        key += "::";
+    }
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    key += &segments.join("::");
+    if path.segments.trailing_punct() {
+        key += "::";
+    }
+    key
+}
+
+struct Qualify<'a> {
+    imports: &'a [Import],
+    /// The previous token at the current level, if it was a punct
+    prev: Option<char>,
+}
+
+impl TokenVisitorMut for Qualify<'_> {
+    fn replace_token(&mut self, token: &TokenTree) -> Option<TokenStream> {
+        let prev = std::mem::replace(
+            &mut self.prev,
+            match token {
+                TokenTree::Punct(punct) => Some(punct.as_char()),
+                _ => None,
+            },
+        );
+        let TokenTree::Ident(ident) = token else {
+            return None;
+        };
+        // `a.b`, `a::b`, `'a`
+        if matches!(prev, Some('.' | ':' | '\'')) {
+            return None;
+        }
+        let import = self
+            .imports
+            .iter()
+            .find(|import| import.name() == Some(ident))?;
+        let mut path = import.path.clone();
+        if let Some(last) = path.segments.last_mut() {
+            last.ident.set_span(ident.span());
+        }
+        Some(path.into_token_stream())
+    }
+    fn enter_group_mut(&mut self, _group: &Group) -> bool {
+        self.prev = None;
+        true
+    }
+    fn visit_group_mut(&mut self, _group: &mut Group) {
+        self.prev = None;
+    }
+}
@@ -0,0 +1,114 @@
+#![allow(dead_code)]
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens, TokenStreamExt};
+use std::cell::RefCell;
+
+thread_local! {
+    static CURRENT: RefCell<Option<LintPolicy>> = const { RefCell::new(None) };
+}
+
+/// The lints that are `allow`ed on generated items, so that users don't get warnings about code
+/// they didn't write.
+///
+/// The codegen builders (`FnBuilder`, `StructBuilder`, `EnumBuilder`, `TraitBuilder` and
+/// `AnonScope`) add `#[allow(...)]` with `LintPolicy::default()` to their output, which can be
+/// replaced per builder with their `lints` method, or for everything generated by a macro with
+/// `LintPolicy::scoped`:
+/// ```ignore
+/// #[proc_macro_derive(MyDerive)]
+/// pub fn my_derive(input: TokenStream) -> TokenStream {
+///     let policy = LintPolicy::recommended().allow("deprecated").remove("unused");
+///     policy.scoped(|| my_derive_impl(input.into()).into())
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct LintPolicy {
+    lints: Vec<String>,
+}
+
+impl LintPolicy {
+    /// The lints in `recommended`
+    pub const RECOMMENDED: &'static [&'static str] = &[
+        "unused",
+        "non_camel_case_types",
+        "non_snake_case",
+        "non_upper_case_globals",
+        "clippy::all",
+        "clippy::pedantic",
+        "clippy::nursery",
+    ];
+
+    /// A policy that doesn't allow anything, so the builders don't add any attributes
+    pub fn none() -> Self {
+        Self { lints: vec![] }
+    }
+    /// The default policy: unused code, naming conventions and all clippy lint groups except
+    /// `clippy::restriction`
+    pub fn recommended() -> Self {
+        Self {
+            lints: Self::RECOMMENDED.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Add a lint: `.allow("deprecated")` or `.allow("clippy::needless_lifetimes")`.
+    /// Panics if `lint` is not a valid lint path.
+    pub fn allow(mut self, lint: &str) -> Self {
+        if syn::parse_str::<syn::Path>(lint).is_err() {
+            panic!("LintPolicy::allow: `{}` is not a valid lint name", lint);
+        }
+        if !self.contains(lint) {
+            self.lints.push(lint.to_string());
+        }
+        self
+    }
+    /// Remove a lint, e.g. one of the `recommended` ones
+    pub fn remove(mut self, lint: &str) -> Self {
+        self.lints.retain(|l| l != lint);
+        self
+    }
+    /// Check if a lint is allowed by this policy. Only checks the exact name, not lint groups.
+    pub fn contains(&self, lint: &str) -> bool {
+        self.lints.iter().any(|l| l == lint)
+    }
+    /// The allowed lints
+    pub fn lints(&self) -> &[String] {
+        &self.lints
+    }
+    /// Check if the policy doesn't allow anything
+    pub fn is_empty(&self) -> bool {
+        self.lints.is_empty()
+    }
+
+    /// Use this policy as the `default` while running `f`, e.g. for the whole expansion of a macro
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::super::scope::scoped(&CURRENT, Some(self), f)
+    }
+
+    /// The `#[allow(...)]` attribute, or nothing if the policy is empty
+    pub fn attr(&self) -> TokenStream {
+        if self.is_empty() {
+            return TokenStream::new();
+        }
+        let lints = self
+            .lints
+            .iter()
+            .map(|lint| syn::parse_str::<syn::Path>(lint).expect("checked in allow"));
+        quote!(#[allow(#(#lints),*)])
+    }
+}
+
+impl Default for LintPolicy {
+    /// The policy set with `scoped`, or `recommended` outside of it
+    fn default() -> Self {
+        CURRENT
+            .with_borrow(Clone::clone)
+            .unwrap_or_else(Self::recommended)
+    }
+}
+
+impl ToTokens for LintPolicy {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.attr());
+    }
+}
@@ -0,0 +1,157 @@
+#![allow(dead_code)]
+
+use super::super::ident::GeneratedNames;
+use super::LintPolicy;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+
+/// A builder for a `macro_rules!` macro that gives users a declarative macro for generated items,
+/// e.g. a `routes!()` macro that expands to a generated static.
+///
+/// The generated items are put into a hidden `pub` module (`__name_impl`, see `GeneratedNames`),
+/// and the rules refer to them through `item_path`. Since `macro_rules!` resolves item paths at
+/// the call site, `item_path` is only usable from other modules with `module_path`, which makes
+/// it an absolute `$crate::...` path.
+/// ```ignore
+/// // #[register(module = "handlers")] fn a() {} -> handlers!() returns all registered handlers
+/// let shim = MacroShim::new(format_ident!("handlers"))
+///     .module_path("handlers")
+///     .export(true)
+///     .docs(&input.attrs)
+///     .items(quote! { pub static HANDLERS: &[fn()] = &[#(#handlers),*]; });
+/// let handlers = shim.item_path(&format_ident!("HANDLERS"));
+/// let shim = shim.rule(quote!(), quote!(#handlers));
+/// ```
+#[derive(Clone)]
+pub(crate) struct MacroShim {
+    name: Ident,
+    docs: Vec<TokenStream>,
+    export: bool,
+    module_path: Option<Vec<Ident>>,
+    items: TokenStream,
+    rules: Vec<(TokenStream, TokenStream)>,
+    lints: LintPolicy,
+}
+
+impl MacroShim {
+    /// Start a macro with the given name, without rules
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            docs: vec![],
+            export: false,
+            module_path: None,
+            items: TokenStream::new(),
+            rules: vec![],
+            lints: LintPolicy::default(),
+        }
+    }
+
+    /// Copy the doc comments from the input, so the macro is documented like the item that
+    /// created it. Other attributes are ignored.
+    pub fn docs<'a>(mut self, attrs: impl IntoIterator<Item = &'a syn::Attribute>) -> Self {
+        let docs = attrs.into_iter().filter(|attr| attr.path().is_ident("doc"));
+        self.docs.extend(docs.map(ToTokens::into_token_stream));
+        self
+    }
+    /// Add doc attributes, e.g. from `codegen::doc`
+    pub fn doc(mut self, doc: impl ToTokens) -> Self {
+        self.docs.push(doc.into_token_stream());
+        self
+    }
+    /// Add `#[macro_export]`, so the macro can be used from other crates and anywhere in the
+    /// current crate as `crate::name!`. Requires `module_path` if the rules use `item_path`.
+    pub fn export(mut self, export: bool) -> Self {
+        self.export = export;
+        self
+    }
+    /// The path of the module the shim is emitted in, relative to the crate root: `"a::b"` or
+    /// `"crate::a::b"`. Use `""` for the crate root. Panics if a segment is not an identifier.
+    pub fn module_path(mut self, path: &str) -> Self {
+        let path = if path == "crate" { "" } else { path };
+        let path = path.strip_prefix("crate::").unwrap_or(path);
+        let segments = path
+            .split("::")
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| Ident::new(segment.trim(), Span::call_site()));
+        self.module_path = Some(segments.collect());
+        self
+    }
+
+    /// Add items to the hidden module. They need to be `pub` to be usable from other crates.
+    pub fn items(mut self, items: impl ToTokens) -> Self {
+        items.to_tokens(&mut self.items);
+        self
+    }
+    /// Add a rule: `(matcher) => { transcriber };`. `$x` in `quote!` is passed through as is.
+    pub fn rule(mut self, matcher: impl ToTokens, transcriber: impl ToTokens) -> Self {
+        self.rules
+            .push((matcher.into_token_stream(), transcriber.into_token_stream()));
+        self
+    }
+    /// Replace the lints that are allowed on the hidden module, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// The name of the macro
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+    /// The name of the hidden module
+    pub fn module_name(&self) -> Ident {
+        GeneratedNames::new().module_of(&self.name)
+    }
+    /// The path to use for an item of the hidden module in the rules:
+    /// `$crate::a::b::__name_impl::item` with `module_path`, `__name_impl::item` otherwise
+    pub fn item_path(&self, item: &Ident) -> TokenStream {
+        let module = self.module_name();
+        match &self.module_path {
+            Some(segments) => quote!($crate #(:: #segments)* :: #module :: #item),
+            None => quote!(#module :: #item),
+        }
+    }
+
+    /// Render the hidden module (if there are items) and the macro
+    pub fn build(&self) -> TokenStream {
+        let Self {
+            name,
+            docs,
+            export,
+            items,
+            rules,
+            lints,
+            ..
+        } = self;
+        let module = (!items.is_empty()).then(|| {
+            let module = self.module_name();
+            quote! {
+                #lints
+                #[doc(hidden)]
+                pub mod #module {
+                    use super::*;
+                    #items
+                }
+            }
+        });
+        let export = export.then(|| quote!(#[macro_export]));
+        let rules = rules
+            .iter()
+            .map(|(matcher, transcriber)| quote!((#matcher) => { #transcriber };));
+        quote! {
+            #module
+            #(#docs)*
+            #export
+            macro_rules! #name {
+                #(#rules)*
+            }
+        }
+    }
+}
+
+impl ToTokens for MacroShim {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
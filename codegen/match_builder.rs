@@ -0,0 +1,185 @@
+#![allow(dead_code)]
+
+use super::super::Edition;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens, TokenStreamExt};
+
+/// One arm of a `MatchBuilder`
+#[derive(Clone)]
+pub(crate) struct MatchArm {
+    attrs: Vec<TokenStream>,
+    pat: TokenStream,
+    guard: Option<TokenStream>,
+    body: TokenStream,
+}
+
+impl MatchArm {
+    /// Create an arm `pat => { body }`
+    pub fn new(pat: impl ToTokens, body: impl ToTokens) -> Self {
+        Self {
+            attrs: vec![],
+            pat: pat.into_token_stream(),
+            guard: None,
+            body: body.into_token_stream(),
+        }
+    }
+    /// Add a guard: `pat if guard => ...`
+    pub fn guard(mut self, guard: impl ToTokens) -> Self {
+        self.guard = Some(guard.into_token_stream());
+        self
+    }
+    /// Add an attribute, given without `#[...]`, e.g. `.attr(quote!(cfg(feature = "x")))`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(quote!(#[#attr]));
+        self
+    }
+
+    /// Check if this arm matches everything: `_` or a binding without guard or attributes.
+    /// Keywords like `true` and `false` are not bindings.
+    fn is_catch_all(&self) -> bool {
+        if self.guard.is_some() || !self.attrs.is_empty() {
+            return false;
+        }
+        let tokens: Vec<TokenTree> = self.pat.clone().into_iter().collect();
+        matches!(&tokens[..], [TokenTree::Ident(ident)] if {
+            let name = ident.to_string();
+            name == "_"
+                || (name.starts_with(|c: char| c.is_lowercase())
+                    && !Edition::LATEST.is_keyword(&name))
+        })
+    }
+}
+
+impl ToTokens for MatchArm {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            attrs,
+            pat,
+            guard,
+            body,
+        } = self;
+        let guard = guard.as_ref().map(|guard| quote!(if #guard));
+        tokens.append_all(quote!(#(#attrs)* #pat #guard => { #body }));
+    }
+}
+
+/// A builder for `match` expressions with a varying number of arms.
+/// ```ignore
+/// let mut m = MatchBuilder::new(quote!(self));
+/// for variant in &variants {
+///     let name = &variant.ident;
+///     m = m.arm(quote!(Self::#name { .. }), quote!(stringify!(#name)));
+/// }
+/// // in case the enum has variants behind a #[cfg] that were skipped
+/// m.fallback(quote!("unknown"))
+/// ```
+#[derive(Clone)]
+pub(crate) struct MatchBuilder {
+    scrutinee: TokenStream,
+    arms: Vec<MatchArm>,
+    fallback: Option<TokenStream>,
+    exhaustive: bool,
+}
+
+impl MatchBuilder {
+    /// Start a `match` on the given expression
+    pub fn new(scrutinee: impl ToTokens) -> Self {
+        Self {
+            scrutinee: scrutinee.into_token_stream(),
+            arms: vec![],
+            fallback: None,
+            exhaustive: false,
+        }
+    }
+
+    /// Add an arm `pat => { body }`
+    pub fn arm(self, pat: impl ToTokens, body: impl ToTokens) -> Self {
+        self.arm_with(MatchArm::new(pat, body))
+    }
+    /// Add an arm `pat if guard => { body }`
+    pub fn arm_if(self, pat: impl ToTokens, guard: impl ToTokens, body: impl ToTokens) -> Self {
+        self.arm_with(MatchArm::new(pat, body).guard(guard))
+    }
+    /// Add an arm with attributes or a guard
+    pub fn arm_with(mut self, arm: MatchArm) -> Self {
+        self.arms.push(arm);
+        self
+    }
+
+    /// Add a `_ => { body }` arm at the end, unless the match is known to be exhaustive: either
+    /// because an arm already matches everything, or because `exhaustive(true)` was called.
+    ///
+    /// If it can't be known (e.g. arms for all variants of an enum, some of them behind
+    /// `#[cfg]`), the arm gets `#[allow(unreachable_patterns)]`, so it's always safe to add.
+    pub fn fallback(mut self, body: impl ToTokens) -> Self {
+        self.fallback = Some(body.into_token_stream());
+        self
+    }
+    /// Declare that the arms cover all possible values, so `fallback` is not needed
+    pub fn exhaustive(mut self, exhaustive: bool) -> Self {
+        self.exhaustive = exhaustive;
+        self
+    }
+
+    /// The number of arms, not counting the fallback
+    pub fn len(&self) -> usize {
+        self.arms.len()
+    }
+    /// Check if no arms were added yet
+    pub fn is_empty(&self) -> bool {
+        self.arms.is_empty()
+    }
+
+    /// Render the `match` expression
+    pub fn build(&self) -> TokenStream {
+        let scrutinee = &self.scrutinee;
+        let arms = &self.arms;
+        let fallback = self
+            .fallback
+            .as_ref()
+            .filter(|_| !self.exhaustive && !arms.iter().any(MatchArm::is_catch_all))
+            .map(|body| quote!(#[allow(unreachable_patterns)] _ => { #body }));
+        quote! {
+            match #scrutinee {
+                #(#arms)*
+                #fallback
+            }
+        }
+    }
+}
+
+impl ToTokens for MatchBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bool_arms_keep_the_fallback() {
+        let m = MatchBuilder::new(quote!(b))
+            .arm(quote!(true), quote!(1))
+            .fallback(quote!(0));
+        let expected = quote! {
+            match b {
+                true => { 1 }
+                #[allow(unreachable_patterns)] _ => { 0 }
+            }
+        };
+        assert_eq!(m.build().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn bindings_are_catch_alls() {
+        for pat in [quote!(_), quote!(other)] {
+            let m = MatchBuilder::new(quote!(b))
+                .arm(pat.clone(), quote!(1))
+                .fallback(quote!(0));
+            let expected = quote!(match b { #pat => { 1 } });
+            assert_eq!(m.build().to_string(), expected.to_string());
+        }
+    }
+}
@@ -0,0 +1,60 @@
+#![allow(dead_code)]
+
+mod assertions;
+mod blanket;
+mod cfg;
+mod chunk;
+mod decl_macro;
+mod doc;
+mod edition_compat;
+mod error_enum;
+mod expr;
+mod ffi;
+mod fn_builder;
+mod format_template;
+mod forward;
+mod imports;
+mod lints;
+mod macro_shim;
+mod match_builder;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod provenance;
+mod scope;
+mod serde_fields;
+mod state_machine;
+mod static_table;
+mod std_policy;
+mod test_builder;
+mod trait_builder;
+mod type_builder;
+mod version_compat;
+pub(crate) use assertions::*;
+pub(crate) use blanket::*;
+pub(crate) use cfg::*;
+pub(crate) use chunk::*;
+pub(crate) use decl_macro::*;
+pub(crate) use doc::*;
+pub(crate) use edition_compat::*;
+pub(crate) use error_enum::*;
+pub(crate) use expr::*;
+pub(crate) use ffi::*;
+pub(crate) use fn_builder::*;
+pub(crate) use format_template::*;
+pub(crate) use forward::*;
+pub(crate) use imports::*;
+pub(crate) use lints::*;
+pub(crate) use macro_shim::*;
+pub(crate) use match_builder::*;
+#[cfg(feature = "rayon")]
+pub(crate) use parallel::*;
+pub(crate) use provenance::*;
+pub(crate) use scope::*;
+pub(crate) use serde_fields::*;
+pub(crate) use state_machine::*;
+pub(crate) use static_table::*;
+pub(crate) use std_policy::*;
+pub(crate) use test_builder::*;
+pub(crate) use trait_builder::*;
+pub(crate) use type_builder::*;
+pub(crate) use version_compat::*;
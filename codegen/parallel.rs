@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+
+use super::super::tokens::{self, TokenRope};
+use super::super::{Error, Result};
+use proc_macro2::{Span, TokenStream};
+use rayon::prelude::*;
+
+/// Run `generate` for every field on the rayon thread pool and concatenate the results in
+/// the order of `fields`, for derives on structs or enums that are big enough for the code
+/// generation to dominate the build. The output is the same as running the generators one by
+/// one.
+///
+/// Tokens and spans can only be used on the thread that runs the macro: `proc_macro2` types
+/// aren't `Send`, and the compiler's token API panics on other threads. So the generators get
+/// plain data (names and types as strings, extracted from the input beforehand) and return
+/// source code. The results are then lexed on the calling thread with the span `span`, so
+/// generated code can't point errors at individual fields. Lexer errors are reported at `span`.
+/// ```ignore
+/// let fields: Vec<(String, String)> = input
+///     .fields
+///     .iter()
+///     .map(|field| (field.ident.as_ref().unwrap().to_string(), field.ty.to_token_stream().to_string()))
+///     .collect();
+/// let getters = codegen::par_map_fields(&fields, Span::call_site(), |(name, ty)| {
+///     format!("pub fn {name}(&self) -> &{ty} {{ &self.{name} }}")
+/// })?;
+/// quote!(impl #name { #getters })
+/// ```
+/// Only worth it if the generators do real work, since lexing the results isn't free.
+pub(crate) fn par_map_fields<T, F>(fields: &[T], span: Span, generate: F) -> Result<TokenStream>
+where
+    T: Sync,
+    F: Fn(&T) -> String + Send + Sync,
+{
+    let sources: Vec<String> = fields.par_iter().map(generate).collect();
+    let mut out = TokenRope::new();
+    let mut errors = Error::builder();
+    for source in sources {
+        match tokens::lex_at(&source, span) {
+            Ok(tokens) => {
+                out.push(tokens);
+            }
+            Err(e) => {
+                errors.with_error(e);
+            }
+        }
+    }
+    errors.ok_or_build()?;
+    Ok(out.finish())
+}
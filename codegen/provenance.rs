@@ -0,0 +1,88 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+use std::cell::RefCell;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Provenance>> = const { RefCell::new(None) };
+}
+
+/// A note on generated items about where they came from: `generated by my_macro v1.2 from
+/// `Foo``, so the output of `cargo expand` and snapshot diffs explain themselves.
+///
+/// The note is a doc comment paragraph after the other attributes of the item, which formatters
+/// and `cargo expand` print as `///`. The codegen builders for top-level items (`FnBuilder`,
+/// `StructBuilder`, `EnumBuilder`, `TraitBuilder` and `StaticTable`) add the provenance set with
+/// `scoped`, which can be replaced per builder with their `provenance` method:
+/// ```ignore
+/// #[proc_macro_derive(Builder)]
+/// pub fn builder(input: TokenStream) -> TokenStream {
+///     let input = syn::parse_macro_input!(input as syn::DeriveInput);
+///     let provenance = Provenance::new("Builder")
+///         .version(env!("CARGO_PKG_VERSION"))
+///         .source(&input.ident);
+///     provenance.scoped(|| derive_builder(input).into())
+/// }
+/// ```
+/// Methods added to a `TraitBuilder` don't get a note, since the trait has one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Provenance {
+    macro_name: String,
+    version: Option<String>,
+    source: Option<String>,
+}
+
+impl Provenance {
+    /// A note with only the name of the macro
+    pub fn new(macro_name: &str) -> Self {
+        Self {
+            macro_name: macro_name.to_string(),
+            version: None,
+            source: None,
+        }
+    }
+    /// Set the version of the macro, usually `env!("CARGO_PKG_VERSION")`
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+    /// Set what the items were generated from, e.g. the name of the input type
+    pub fn source(mut self, source: impl std::fmt::Display) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// The provenance set with `scoped`, if any
+    pub fn current() -> Option<Provenance> {
+        CURRENT.with_borrow(Clone::clone)
+    }
+    /// Add this provenance to the items built while running `f`
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::super::scope::scoped(&CURRENT, Some(self), f)
+    }
+
+    /// The text of the note: `generated by my_macro v1.2 from `Foo``
+    pub fn message(&self) -> String {
+        let mut message = format!("generated by {}", self.macro_name);
+        if let Some(version) = &self.version {
+            message.push_str(" v");
+            message.push_str(version.trim_start_matches('v'));
+        }
+        if let Some(source) = &self.source {
+            message.push_str(&format!(" from `{}`", source));
+        }
+        message
+    }
+    /// The doc attributes, with an empty line first to separate the note from existing docs
+    pub fn attr(&self) -> TokenStream {
+        let message = Literal::string(&format!(" {}", self.message()));
+        quote!(#[doc = ""] #[doc = #message])
+    }
+}
+
+impl ToTokens for Provenance {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.attr());
+    }
+}
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use super::super::ident::GeneratedNames;
+use super::LintPolicy;
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+
+/// Wrap generated items in `const _: () = { ... };`, so that helper items (imports, structs,
+/// functions) don't leak into the user's namespace. `impl` blocks inside still apply normally.
+/// See `AnonScope` for imports and the `mod` variant.
+pub(crate) fn anon_scope(items: impl ToTokens) -> TokenStream {
+    AnonScope::new().wrap(items)
+}
+
+/// A configurable version of `anon_scope`.
+/// ```ignore
+/// let scope = AnonScope::new()
+///     .extern_crate("alloc", "__alloc")
+///     .import(quote!(::core::fmt as __fmt));
+/// output.extend(scope.wrap(impls));
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct AnonScope {
+    imports: Vec<TokenStream>,
+    module: Option<Ident>,
+    lints: LintPolicy,
+}
+
+impl AnonScope {
+    /// A `const _` scope without imports
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `extern crate name as alias;`, e.g. to refer to `alloc` in `no_std` crates.
+    /// Panics if `name` or `alias` are not valid identifiers.
+    pub fn extern_crate(mut self, name: &str, alias: &str) -> Self {
+        let name = Ident::new(name, proc_macro2::Span::call_site());
+        let alias = Ident::new(alias, proc_macro2::Span::call_site());
+        self.imports.push(quote!(extern crate #name as #alias;));
+        self
+    }
+    /// Add `use path;`, given without `use` and `;`: `.import(quote!(::core::fmt as __fmt))`
+    pub fn import(mut self, path: impl ToTokens) -> Self {
+        let path = path.into_token_stream();
+        self.imports.push(quote!(use #path;));
+        self
+    }
+
+    /// Use a hidden module instead of `const _`, for items that have to be nameable from outside
+    /// (e.g. by a `macro_rules!` shim). The module gets `use super::*;` so the items can refer to
+    /// the user's code.
+    pub fn module(mut self, name: Ident) -> Self {
+        self.module = Some(name);
+        self
+    }
+    /// Same as `module`, using `GeneratedNames` for the name of the module: `__foo_impl`
+    pub fn module_for(self, ty: &Ident) -> Self {
+        self.module(GeneratedNames::new().module_of(ty))
+    }
+
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// Wrap the items in the scope
+    pub fn wrap(&self, items: impl ToTokens) -> TokenStream {
+        let imports = &self.imports;
+        let lints = &self.lints;
+        match &self.module {
+            Some(name) => quote! {
+                #lints
+                #[doc(hidden)]
+                mod #name {
+                    use super::*;
+                    #(#imports)*
+                    #items
+                }
+            },
+            None => quote! {
+                #lints
+                const _: () = {
+                    #(#imports)*
+                    #items
+                };
+            },
+        }
+    }
+}
@@ -0,0 +1,281 @@
+#![allow(dead_code)]
+
+use super::LintPolicy;
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+
+#[derive(Clone)]
+struct SerdeField {
+    member: Ident,
+    name: String,
+    ty: TokenStream,
+}
+
+/// The fields of a struct, for macros that generate their own `serde` impls. Provides the tedious
+/// parts of a `Deserialize` impl: the `FIELDS` constant, the field identifier enum, the visitor
+/// struct and the `visit_map` loop with duplicate and missing field errors. The matching
+/// `Serialize` body uses the same names.
+///
+/// The generated code refers to `::serde`, which can be changed with `serde_path` (like
+/// `#[serde(crate = "...")]`).
+/// ```ignore
+/// let fields = input_fields.iter().fold(SerdeFields::new(&input.ident), |fields, field| {
+///     let name = field.ident.clone().unwrap();
+///     fields.field(name.clone(), my_rename(&name), &field.ty)
+/// });
+/// let field_enum = format_ident!("__Field");
+/// let names = fields.names_const(&format_ident!("FIELDS"));
+/// let ident = fields.field_enum(&field_enum);
+/// let body = fields.visit_map_body(&field_enum, &format_ident!("__map"));
+/// let members = fields.members();
+/// let visitor = fields.visitor(&format_ident!("__Visitor"), &input.generics, quote! {
+///     fn visit_map<__A: ::serde::de::MapAccess<'de>>(self, mut __map: __A) -> Result<Self::Value, __A::Error> {
+///         #body
+///         Ok(#name { #(#members),* })
+///     }
+/// });
+/// ```
+#[derive(Clone)]
+pub(crate) struct SerdeFields {
+    type_name: Ident,
+    fields: Vec<SerdeField>,
+    serde: TokenStream,
+    lints: LintPolicy,
+}
+
+impl SerdeFields {
+    /// Start without fields for the given type
+    pub fn new(type_name: &Ident) -> Self {
+        Self {
+            type_name: type_name.clone(),
+            fields: vec![],
+            serde: quote!(::serde),
+            lints: LintPolicy::default(),
+        }
+    }
+    /// Add a field with the name it has in the serialized form
+    pub fn field(mut self, member: Ident, name: impl Into<String>, ty: impl ToTokens) -> Self {
+        self.fields.push(SerdeField {
+            member,
+            name: name.into(),
+            ty: ty.into_token_stream(),
+        });
+        self
+    }
+    /// Set the path of the `serde` crate. The default is `::serde`.
+    pub fn serde_path(mut self, path: impl ToTokens) -> Self {
+        self.serde = path.into_token_stream();
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// The names of the fields in the struct, in order
+    pub fn members(&self) -> Vec<&Ident> {
+        self.fields.iter().map(|field| &field.member).collect()
+    }
+    /// The serialized names of the fields, in order
+    pub fn names(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect()
+    }
+
+    /// `const NAME: &[&str] = &["a", "b"];`, for `deserialize_struct` and `unknown_field` errors
+    pub fn names_const(&self, name: &Ident) -> TokenStream {
+        let names = self.names();
+        let lints = &self.lints;
+        quote! {
+            #lints
+            const #name: &[&str] = &[#(#names),*];
+        }
+    }
+
+    /// An enum that identifies the fields, with a variant for every field and `__ignore` for
+    /// unknown ones. Deserializes from the name (as `str` or bytes) or the index of a field.
+    pub fn field_enum(&self, name: &Ident) -> TokenStream {
+        let Self { serde, lints, .. } = self;
+        let variants: Vec<Ident> = (0..self.fields.len())
+            .map(|i| format_ident!("__field{}", i))
+            .collect();
+        let indices = (0..self.fields.len()).map(|i| Literal::u64_unsuffixed(i as u64));
+        let names = self.names();
+        let byte_names = names
+            .iter()
+            .map(|name| Literal::byte_string(name.as_bytes()));
+        quote! {
+            #lints
+            enum #name {
+                #(#variants,)*
+                __ignore,
+            }
+            #lints
+            impl<'de> #serde::Deserialize<'de> for #name {
+                fn deserialize<__D>(__deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+                where
+                    __D: #serde::Deserializer<'de>,
+                {
+                    struct __FieldVisitor;
+                    impl<'de> #serde::de::Visitor<'de> for __FieldVisitor {
+                        type Value = #name;
+                        fn expecting(
+                            &self,
+                            __formatter: &mut ::core::fmt::Formatter<'_>,
+                        ) -> ::core::fmt::Result {
+                            __formatter.write_str("field identifier")
+                        }
+                        fn visit_u64<__E: #serde::de::Error>(
+                            self,
+                            __value: u64,
+                        ) -> ::core::result::Result<Self::Value, __E> {
+                            match __value {
+                                #(#indices => ::core::result::Result::Ok(#name::#variants),)*
+                                _ => ::core::result::Result::Ok(#name::__ignore),
+                            }
+                        }
+                        fn visit_str<__E: #serde::de::Error>(
+                            self,
+                            __value: &str,
+                        ) -> ::core::result::Result<Self::Value, __E> {
+                            match __value {
+                                #(#names => ::core::result::Result::Ok(#name::#variants),)*
+                                _ => ::core::result::Result::Ok(#name::__ignore),
+                            }
+                        }
+                        fn visit_bytes<__E: #serde::de::Error>(
+                            self,
+                            __value: &[u8],
+                        ) -> ::core::result::Result<Self::Value, __E> {
+                            match __value {
+                                #(#byte_names => ::core::result::Result::Ok(#name::#variants),)*
+                                _ => ::core::result::Result::Ok(#name::__ignore),
+                            }
+                        }
+                    }
+                    #serde::Deserializer::deserialize_identifier(__deserializer, __FieldVisitor)
+                }
+            }
+        }
+    }
+
+    /// The body of `Serialize::serialize` for a struct, serializing the fields of `self` with
+    /// `serialize_struct`
+    pub fn serialize_body(&self, serializer: &Ident) -> TokenStream {
+        let serde = &self.serde;
+        let type_name = self.type_name.to_string();
+        let len = self.fields.len();
+        let members = self.members();
+        let names = self.names();
+        quote! {
+            let mut __state = #serde::Serializer::serialize_struct(#serializer, #type_name, #len)?;
+            #(#serde::ser::SerializeStruct::serialize_field(&mut __state, #names, &self.#members)?;)*
+            #serde::ser::SerializeStruct::end(__state)
+        }
+    }
+
+    /// A visitor struct for the type with the given generics, with `Value` set to the type and
+    /// `expecting` set to "struct Name". `methods` are the `visit_*` methods. The visitor has
+    /// the lifetime `'de` as its first parameter, so the generics can have bounds like
+    /// `T: Deserialize<'de>`.
+    pub fn visitor(
+        &self,
+        name: &Ident,
+        generics: &syn::Generics,
+        methods: impl ToTokens,
+    ) -> TokenStream {
+        let Self {
+            type_name,
+            serde,
+            lints,
+            ..
+        } = self;
+        let (_, ty_generics, _) = generics.split_for_impl();
+        let mut visitor_generics = generics.clone();
+        visitor_generics.params.insert(0, syn::parse_quote!('de));
+        let (impl_generics, visitor_ty_generics, where_clause) = visitor_generics.split_for_impl();
+        let expecting = format!("struct {}", type_name);
+        quote! {
+            #lints
+            struct #name #impl_generics #where_clause {
+                marker: ::core::marker::PhantomData<#type_name #ty_generics>,
+                lifetime: ::core::marker::PhantomData<&'de ()>,
+            }
+            #lints
+            impl #impl_generics #serde::de::Visitor<'de> for #name #visitor_ty_generics #where_clause {
+                type Value = #type_name #ty_generics;
+                fn expecting(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    __formatter.write_str(#expecting)
+                }
+                #methods
+            }
+        }
+    }
+
+    /// An instance of the visitor from `visitor`, e.g. for `deserialize_struct`
+    pub fn visitor_value(&self, name: &Ident) -> TokenStream {
+        quote! {
+            #name {
+                marker: ::core::marker::PhantomData,
+                lifetime: ::core::marker::PhantomData,
+            }
+        }
+    }
+
+    /// The body of `visit_map`, which reads all entries of `map` and ends with a variable for
+    /// every field (named like the field), so that the value can be constructed with `members`.
+    /// Duplicate fields and missing fields are errors, unknown fields are skipped.
+    pub fn visit_map_body(&self, field_enum: &Ident, map: &Ident) -> TokenStream {
+        let serde = &self.serde;
+        let members = self.members();
+        let types = self.fields.iter().map(|field| &field.ty);
+        let variants = (0..self.fields.len()).map(|i| format_ident!("__field{}", i));
+        let names = self.names();
+        let missing = self
+            .fields
+            .iter()
+            .map(|field| self.missing_field(&field.member, &field.name));
+        quote! {
+            #(let mut #members: ::core::option::Option<#types> = ::core::option::Option::None;)*
+            while let ::core::option::Option::Some(__key) =
+                #serde::de::MapAccess::next_key::<#field_enum>(&mut #map)?
+            {
+                match __key {
+                    #(#field_enum::#variants => {
+                        if ::core::option::Option::is_some(&#members) {
+                            return ::core::result::Result::Err(
+                                <_ as #serde::de::Error>::duplicate_field(#names),
+                            );
+                        }
+                        #members = ::core::option::Option::Some(
+                            #serde::de::MapAccess::next_value(&mut #map)?,
+                        );
+                    })*
+                    _ => {
+                        let _ = #serde::de::MapAccess::next_value::<#serde::de::IgnoredAny>(&mut #map)?;
+                    }
+                }
+            }
+            #(#missing)*
+        }
+    }
+
+    /// Unwrap the `Option` in the variable `var` or return a `missing_field` error. The error
+    /// type is inferred from the return type of the surrounding `visit_*` method.
+    pub fn missing_field(&self, var: &Ident, name: &str) -> TokenStream {
+        let serde = &self.serde;
+        quote! {
+            let #var = match #var {
+                ::core::option::Option::Some(__value) => __value,
+                ::core::option::Option::None => {
+                    return ::core::result::Result::Err(
+                        <_ as #serde::de::Error>::missing_field(#name),
+                    );
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,312 @@
+#![allow(dead_code)]
+
+use super::super::{Error, ErrorBuilder, Result};
+use super::{doc, DocValue, EnumBuilder, LintPolicy, MatchBuilder, VariantDef};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+
+/// A state of a `StateMachine`
+#[derive(Clone)]
+pub(crate) struct StateDef {
+    name: Ident,
+    attrs: Vec<TokenStream>,
+    on_enter: TokenStream,
+    on_exit: TokenStream,
+}
+
+impl StateDef {
+    /// A state without hooks
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            attrs: vec![],
+            on_enter: TokenStream::new(),
+            on_exit: TokenStream::new(),
+        }
+    }
+    /// Add an attribute to the variant, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(attr.into_token_stream());
+        self
+    }
+    /// Add statements that run on every transition into this state
+    pub fn on_enter(mut self, code: impl ToTokens) -> Self {
+        code.to_tokens(&mut self.on_enter);
+        self
+    }
+    /// Add statements that run on every transition out of this state
+    pub fn on_exit(mut self, code: impl ToTokens) -> Self {
+        code.to_tokens(&mut self.on_exit);
+        self
+    }
+
+    /// The name of the state
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+}
+
+#[derive(Clone)]
+struct Transition {
+    from: Ident,
+    event: Ident,
+    to: Ident,
+}
+
+/// A generator for state machines, with the states and transitions parsed by the macro.
+///
+/// Renders an enum of the states, an enum of the events (`NameEvent` by default) and a
+/// `transition` method that returns the next state, or `None` if the event isn't valid in the
+/// current state. The exit hooks of the old state run before the enter hooks of the new one,
+/// including transitions from a state to itself.
+/// ```ignore
+/// // state_machine! { Light: Red --Timer--> Green, Green --Timer--> Red }
+/// let machine = StateMachine::new(format_ident!("Light"))
+///     .vis(quote!(pub))
+///     .state(StateDef::new(red.clone()).on_enter(quote!(println!("stop");)))
+///     .state(StateDef::new(green.clone()))
+///     .transition(red.clone(), timer.clone(), green.clone())
+///     .transition(green, timer, red.clone())
+///     .initial(red)
+///     .graphviz(true);
+/// // Light::Red.transition(LightEvent::Timer) == Some(Light::Green)
+/// output.extend(machine.build()?);
+/// ```
+#[derive(Clone)]
+pub(crate) struct StateMachine {
+    name: Ident,
+    event_name: Ident,
+    vis: TokenStream,
+    states: Vec<StateDef>,
+    transitions: Vec<Transition>,
+    initial: Option<Ident>,
+    graphviz: bool,
+    lints: LintPolicy,
+}
+
+impl StateMachine {
+    /// Start a private state machine without states
+    pub fn new(name: Ident) -> Self {
+        let event_name = format_ident!("{}Event", name, span = name.span());
+        Self {
+            name,
+            event_name,
+            vis: TokenStream::new(),
+            states: vec![],
+            transitions: vec![],
+            initial: None,
+            graphviz: false,
+            lints: LintPolicy::default(),
+        }
+    }
+
+    /// Set the visibility of the enums and the `transition` method
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Set the name of the event enum
+    pub fn event_name(mut self, name: Ident) -> Self {
+        self.event_name = name;
+        self
+    }
+    /// Add a state
+    pub fn state(mut self, state: StateDef) -> Self {
+        self.states.push(state);
+        self
+    }
+    /// Add a transition from one state to another on an event. Events are added to the event
+    /// enum in the order of their first use.
+    pub fn transition(mut self, from: Ident, event: Ident, to: Ident) -> Self {
+        self.transitions.push(Transition { from, event, to });
+        self
+    }
+    /// Set the initial state, which becomes the `Default`
+    pub fn initial(mut self, state: Ident) -> Self {
+        self.initial = Some(state);
+        self
+    }
+    /// Add a Graphviz `dot` graph of the transitions to the docs of the state enum
+    pub fn graphviz(mut self, graphviz: bool) -> Self {
+        self.graphviz = graphviz;
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// The name of the state enum
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+    /// The events in the order of their first use
+    pub fn events(&self) -> Vec<&Ident> {
+        let mut events: Vec<&Ident> = vec![];
+        for transition in &self.transitions {
+            if !events.contains(&&transition.event) {
+                events.push(&transition.event);
+            }
+        }
+        events
+    }
+
+    /// Render the state machine. Returns an error for duplicate states, transitions between
+    /// unknown states and multiple transitions from the same state on the same event.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "StateMachine::build", skip_all)
+    )]
+    pub fn build(&self) -> Result<TokenStream> {
+        self.validate()?;
+        let Self {
+            name,
+            event_name,
+            vis,
+            lints,
+            ..
+        } = self;
+        let derives = [
+            quote!(::core::clone::Clone),
+            quote!(::core::marker::Copy),
+            quote!(::core::cmp::PartialEq),
+            quote!(::core::cmp::Eq),
+            quote!(::core::hash::Hash),
+            quote!(::core::fmt::Debug),
+        ];
+        let with_derives = |builder: EnumBuilder| {
+            let builder = builder.vis(vis).lints(lints.clone());
+            derives.iter().fold(builder, EnumBuilder::derive)
+        };
+
+        let mut states = with_derives(EnumBuilder::new(name.clone()));
+        for state in &self.states {
+            let mut variant = VariantDef::new(state.name.clone());
+            for attr in &state.attrs {
+                variant = variant.attr(attr);
+            }
+            states = states.variant(variant);
+        }
+        let mut events = with_derives(EnumBuilder::new(event_name.clone()));
+        for event in self.events() {
+            events = events.variant(VariantDef::new(event.clone()));
+        }
+        let graph = self.graphviz.then(|| self.graph_docs());
+
+        let mut arms = MatchBuilder::new(quote!((self, event)));
+        for transition in &self.transitions {
+            let Transition { from, event, to } = transition;
+            let on_exit = &self.state_def(from).on_exit;
+            let on_enter = &self.state_def(to).on_enter;
+            arms = arms.arm(
+                quote!((Self::#from, #event_name::#event)),
+                quote!({
+                    #on_exit
+                    #on_enter
+                    ::core::option::Option::Some(Self::#to)
+                }),
+            );
+        }
+        let events_count = self.events().len();
+        if self.transitions.len() < self.states.len() * events_count {
+            arms = arms.fallback(quote!(::core::option::Option::None));
+        }
+        let all_states = self.states.iter().map(|state| &state.name);
+        let default = self.initial.as_ref().map(|initial| {
+            quote! {
+                #lints
+                impl ::core::default::Default for #name {
+                    fn default() -> Self {
+                        Self::#initial
+                    }
+                }
+            }
+        });
+
+        Ok(quote! {
+            #graph
+            #states
+            #events
+            #lints
+            impl #name {
+                /// All states
+                #vis const STATES: &'static [Self] = &[#(Self::#all_states),*];
+
+                /// The state after `event`, or `None` if there is no transition for it. Runs the
+                /// exit hooks of this state and the enter hooks of the next one.
+                #[must_use]
+                #vis fn transition(self, event: #event_name) -> ::core::option::Option<Self> {
+                    #arms
+                }
+            }
+            #default
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut error = Error::builder();
+        for (i, state) in self.states.iter().enumerate() {
+            if self.states[..i]
+                .iter()
+                .any(|other| other.name == state.name)
+            {
+                error.with(
+                    state.name.span(),
+                    format!("duplicate state `{}`", state.name),
+                );
+            }
+        }
+        let check_state = |error: &mut ErrorBuilder, state: &Ident| {
+            if !self.states.iter().any(|other| other.name == *state) {
+                error.with(state.span(), format!("unknown state `{}`", state));
+            }
+        };
+        for (i, transition) in self.transitions.iter().enumerate() {
+            check_state(&mut error, &transition.from);
+            check_state(&mut error, &transition.to);
+            let duplicate = self.transitions[..i]
+                .iter()
+                .any(|other| other.from == transition.from && other.event == transition.event);
+            if duplicate {
+                error.with(
+                    transition.event.span(),
+                    format!(
+                        "there already is a transition from `{}` on `{}`",
+                        transition.from, transition.event
+                    ),
+                );
+            }
+        }
+        if let Some(initial) = &self.initial {
+            check_state(&mut error, initial);
+        }
+        error.ok_or_build()
+    }
+
+    /// Only called after `validate`
+    fn state_def(&self, name: &Ident) -> &StateDef {
+        self.states
+            .iter()
+            .find(|state| state.name == *name)
+            .expect("transitions are validated")
+    }
+
+    fn graph_docs(&self) -> TokenStream {
+        let mut graph = String::from("```dot\ndigraph {\n");
+        if let Some(initial) = &self.initial {
+            graph += &format!("    {} [shape = doublecircle];\n", initial.unraw());
+        }
+        for Transition { from, event, to } in &self.transitions {
+            graph += &format!(
+                "    {} -> {} [label = \"{}\"];\n",
+                from.unraw(),
+                to.unraw(),
+                event.unraw()
+            );
+        }
+        graph += "}\n```";
+        doc("{graph}", &[("graph", DocValue::raw(graph))])
+    }
+}
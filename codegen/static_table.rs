@@ -0,0 +1,172 @@
+#![allow(dead_code)]
+
+use super::{LintPolicy, Provenance};
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+
+/// A builder for a `static` table of structured data, e.g. the routes, tests or plugins
+/// collected by a macro.
+///
+/// By default, this renders `static NAME: &'static [T] = &[rows...];`. Rows are expressions of
+/// type `T` and have to be usable in a `static` initializer (constants, literals, struct
+/// expressions, function pointers, ...).
+/// ```ignore
+/// let mut table = StaticTable::new(format_ident!("ROUTES"), quote!(Route)).vis(quote!(pub));
+/// for route in &routes {
+///     let (path, handler) = (&route.path, &route.handler);
+///     table = table.row(quote!(Route { path: #path, handler: #handler }));
+/// }
+/// ```
+#[derive(Clone)]
+pub(crate) struct StaticTable {
+    name: Ident,
+    ty: TokenStream,
+    vis: TokenStream,
+    attrs: Vec<TokenStream>,
+    rows: Vec<TokenStream>,
+    array: bool,
+    link_section: Option<String>,
+    #[cfg(feature = "linkme")]
+    distributed_slice: Option<TokenStream>,
+    lints: LintPolicy,
+    provenance: Option<Provenance>,
+}
+
+impl StaticTable {
+    /// Start a private, empty table with the given name and element type
+    pub fn new(name: Ident, ty: impl ToTokens) -> Self {
+        Self {
+            name,
+            ty: ty.into_token_stream(),
+            vis: TokenStream::new(),
+            attrs: vec![],
+            rows: vec![],
+            array: false,
+            link_section: None,
+            #[cfg(feature = "linkme")]
+            distributed_slice: None,
+            lints: LintPolicy::default(),
+            provenance: Provenance::current(),
+        }
+    }
+
+    /// Set the visibility
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Add an attribute, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        let attr = attr.into_token_stream();
+        self.attrs.push(quote!(#[#attr]));
+        self
+    }
+    /// Add a row
+    pub fn row(mut self, row: impl ToTokens) -> Self {
+        self.rows.push(row.into_token_stream());
+        self
+    }
+    /// Add rows
+    pub fn rows(mut self, rows: impl IntoIterator<Item = impl ToTokens>) -> Self {
+        self.rows
+            .extend(rows.into_iter().map(|row| row.into_token_stream()));
+        self
+    }
+    /// Use an array type `[T; N]` instead of a slice `&'static [T]`, e.g. to keep the length
+    /// in the type
+    pub fn array(mut self, array: bool) -> Self {
+        self.array = array;
+        self
+    }
+    /// Put the table into a linker section: `#[link_section = "..."]` and `#[used]`, so it is
+    /// kept even if nothing refers to it
+    pub fn link_section(mut self, section: &str) -> Self {
+        self.link_section = Some(section.to_string());
+        self
+    }
+    /// Instead of one table, add every row to a `linkme` distributed slice declared elsewhere
+    /// (`#[distributed_slice] pub static SLICE: [T];`), so that multiple macro invocations in
+    /// different crates can contribute to the same registry. The rows are emitted as
+    /// `NAME_0`, `NAME_1`, ... and `link_section` is ignored. The user's crate needs a
+    /// dependency on `linkme`.
+    #[cfg(feature = "linkme")]
+    pub fn distributed_slice(mut self, slice: impl ToTokens) -> Self {
+        self.distributed_slice = Some(slice.into_token_stream());
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+    /// Replace the provenance note on the output, or remove it with `None`, see `Provenance`
+    pub fn provenance(mut self, provenance: Option<Provenance>) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// The name of the table
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+    /// The number of rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+    /// Check if there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Render the table
+    pub fn build(&self) -> TokenStream {
+        let Self {
+            name,
+            ty,
+            vis,
+            attrs,
+            rows,
+            array,
+            link_section,
+            lints,
+            provenance,
+            ..
+        } = self;
+        let link_section = link_section
+            .as_ref()
+            .map(|section| quote!(#[link_section = #section] #[used]));
+        #[cfg(feature = "linkme")]
+        if let Some(slice) = &self.distributed_slice {
+            let statics = rows.iter().enumerate().map(|(i, row)| {
+                let name = quote::format_ident!("{}_{}", name, i, span = name.span());
+                quote! {
+                    #lints
+                    #(#attrs)*
+                    #provenance
+                    #[::linkme::distributed_slice(#slice)]
+                    #vis static #name: #ty = #row;
+                }
+            });
+            return statics.collect();
+        }
+        let (ty, value) = if *array {
+            let len = Literal::usize_unsuffixed(rows.len());
+            (quote!([#ty; #len]), quote!([#(#rows),*]))
+        } else {
+            (quote!(&'static [#ty]), quote!(&[#(#rows),*]))
+        };
+        quote! {
+            #lints
+            #(#attrs)*
+            #provenance
+            #link_section
+            #vis static #name: #ty = #value;
+        }
+    }
+}
+
+impl ToTokens for StaticTable {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
@@ -0,0 +1,141 @@
+#![allow(dead_code)]
+
+use super::super::{Error, Result};
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use std::cell::RefCell;
+
+thread_local! {
+    static CURRENT: RefCell<Option<StdPolicy>> = const { RefCell::new(None) };
+}
+
+/// Which crates generated code takes standard library items from.
+///
+/// Hardcoding `::std::...` in generated code breaks `no_std` users, and `::core::...` alone
+/// doesn't have collections or `String`. Codegen helpers that refer to standard library items use
+/// `StdPolicy::current()`, which is `Std` unless changed with `scoped`.
+/// ```ignore
+/// // #[derive(MyDerive)] #[my_derive(no_std)] struct Foo { ... }
+/// let policy = StdPolicy::from_attrs(&input.attrs, "my_derive");
+/// let vec = policy.path("vec::Vec")?; // ::alloc::vec::Vec
+/// let fmt = policy.path("fmt::Display")?; // ::core::fmt::Display
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum StdPolicy {
+    /// Everything from `::std`
+    #[default]
+    Std,
+    /// `::core` where possible, `::alloc` for allocating types. The user's crate needs
+    /// `extern crate alloc;` if it uses them. Items that only exist in `std` are an error.
+    CoreAlloc,
+}
+
+/// Paths that only exist in `std`. Checked before `ALLOC_PATHS`, since `collections` is in
+/// `alloc` except for the hash maps.
+const STD_ONLY_PATHS: &[&str] = &[
+    "backtrace",
+    "collections::HashMap",
+    "collections::HashSet",
+    "collections::hash_map",
+    "collections::hash_set",
+    "env",
+    "fs",
+    "io",
+    "net",
+    "os",
+    "panic::catch_unwind",
+    "path",
+    "process",
+    "sync::Barrier",
+    "sync::Condvar",
+    "sync::LazyLock",
+    "sync::Mutex",
+    "sync::MutexGuard",
+    "sync::Once",
+    "sync::OnceLock",
+    "sync::RwLock",
+    "sync::mpsc",
+    "thread",
+    "time::Instant",
+    "time::SystemTime",
+    "time::UNIX_EPOCH",
+];
+
+/// Paths that exist in `alloc`. Everything else is assumed to be in `core`.
+const ALLOC_PATHS: &[&str] = &[
+    "borrow::Cow",
+    "borrow::ToOwned",
+    "boxed",
+    "collections",
+    "ffi::CString",
+    "fmt::format",
+    "format",
+    "rc",
+    "string",
+    "sync::Arc",
+    "sync::Weak",
+    "vec",
+];
+
+impl StdPolicy {
+    /// `CoreAlloc` if the attributes contain `#[name(no_std)]` (possibly among other arguments,
+    /// like `#[name(no_std, rename = "...")]`), `Std` otherwise
+    pub fn from_attrs(attrs: &[syn::Attribute], name: &str) -> Self {
+        let no_std = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(name))
+            .filter_map(|attr| attr.meta.require_list().ok())
+            .any(|list| {
+                super::super::tokens::split_top_level(list.tokens.clone(), ',')
+                    .iter()
+                    .any(|(arg, _)| arg.to_string() == "no_std")
+            });
+        if no_std {
+            Self::CoreAlloc
+        } else {
+            Self::Std
+        }
+    }
+
+    /// The crate that `path` (e.g. `"vec::Vec"`, without the crate) is taken from, or `None` if
+    /// it is not available with this policy
+    pub fn crate_of(self, path: &str) -> Option<&'static str> {
+        let matches = |prefix: &&str| {
+            path == *prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with("::"))
+        };
+        match self {
+            Self::Std => Some("std"),
+            Self::CoreAlloc if STD_ONLY_PATHS.iter().any(matches) => None,
+            Self::CoreAlloc if ALLOC_PATHS.iter().any(matches) => Some("alloc"),
+            Self::CoreAlloc => Some("core"),
+        }
+    }
+
+    /// The absolute path of a standard library item: `"vec::Vec"` -> `::std::vec::Vec`. Returns
+    /// an error if the item is not available in `no_std` with `CoreAlloc`. Panics if `path` is
+    /// not a valid path.
+    pub fn path(self, path: &str) -> Result<TokenStream> {
+        let Some(krate) = self.crate_of(path) else {
+            return Error::err(
+                Span::call_site(),
+                format!("`std::{}` is not available in `no_std` crates", path),
+            );
+        };
+        let full = format!("::{}::{}", krate, path);
+        let parsed: syn::Path = syn::parse_str(&full)
+            .unwrap_or_else(|_| panic!("StdPolicy::path: `{}` is not a valid path", path));
+        Ok(parsed.into_token_stream())
+    }
+
+    /// The policy set with `scoped`, or `Std` outside of it
+    pub fn current() -> Self {
+        CURRENT.with_borrow(|current| *current).unwrap_or_default()
+    }
+    /// Use this policy as `current` while running `f`, e.g. for the whole expansion of a macro
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::super::scope::scoped(&CURRENT, Some(self), f)
+    }
+}
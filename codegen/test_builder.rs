@@ -0,0 +1,194 @@
+#![allow(dead_code)]
+
+use super::super::{Case, Edition};
+use super::{FnBuilder, LintPolicy};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens, TokenStreamExt};
+use std::collections::HashSet;
+
+/// Unique test names from descriptions, for macros that generate one test per case of a spec.
+///
+/// Descriptions are turned into `snake_case` (`"parses empty input"` -> `parses_empty_input`),
+/// and repeated names get a numeric suffix (`parses_empty_input_2`).
+/// ```ignore
+/// let mut names = TestNames::new();
+/// for case in &spec.cases {
+///     let test = TestBuilder::new(names.name(&case.description, case.span))
+///         .body(quote!(assert_eq!(parse(#input), #expected);));
+///     output.extend(test.build());
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct TestNames {
+    used: HashSet<String>,
+}
+
+impl TestNames {
+    /// Start without any used names
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Mark a name as used, e.g. for tests the user wrote by hand in the same module
+    pub fn reserve(&mut self, name: &Ident) -> &mut Self {
+        self.used.insert(name.to_string());
+        self
+    }
+    /// A name for a test with the given description that wasn't returned before
+    pub fn name(&mut self, description: &str, span: Span) -> Ident {
+        let mut base = Case::Snake.convert(description);
+        if base.is_empty() {
+            base = "test".to_string();
+        } else if base.starts_with(|c: char| c.is_ascii_digit()) {
+            base = format!("test_{}", base);
+        } else if Edition::LATEST.is_keyword(&base) {
+            base.push('_');
+        }
+        let mut name = base.clone();
+        let mut counter = 1;
+        while self.used.contains(&name) {
+            counter += 1;
+            name = format!("{}_{}", base, counter);
+        }
+        self.used.insert(name.clone());
+        Ident::new(&name, span)
+    }
+}
+
+/// A builder for `#[test]` functions.
+/// ```ignore
+/// let test = TestBuilder::new(format_ident!("rejects_{}", case))
+///     .should_panic(Some("invalid input"))
+///     .body(quote!(parse(#input);));
+/// // #[tokio::test] async fn ...() -> Result<(), Error> { ... }
+/// let test = TestBuilder::new(name)
+///     .test_attr(quote!(::tokio::test))
+///     .asyncness(true)
+///     .ret(quote!(::core::result::Result<(), Error>))
+///     .body(quote!(client.get(#url).await?; Ok(())));
+/// ```
+#[derive(Clone)]
+pub(crate) struct TestBuilder {
+    name: Ident,
+    test_attr: TokenStream,
+    attrs: Vec<TokenStream>,
+    ignore: Option<Option<String>>,
+    should_panic: Option<Option<String>>,
+    asyncness: bool,
+    ret: Option<TokenStream>,
+    body: TokenStream,
+    lints: LintPolicy,
+}
+
+impl TestBuilder {
+    /// Start a `#[test]` with an empty body
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            test_attr: quote!(test),
+            attrs: vec![],
+            ignore: None,
+            should_panic: None,
+            asyncness: false,
+            ret: None,
+            body: TokenStream::new(),
+            lints: LintPolicy::default(),
+        }
+    }
+
+    /// Replace the test attribute, given without `#[...]`: `.test_attr(quote!(::tokio::test))`
+    pub fn test_attr(mut self, attr: impl ToTokens) -> Self {
+        self.test_attr = attr.into_token_stream();
+        self
+    }
+    /// Add another attribute, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(attr.into_token_stream());
+        self
+    }
+    /// Add `#[ignore]`, with an optional reason
+    pub fn ignore(mut self, reason: Option<&str>) -> Self {
+        self.ignore = Some(reason.map(str::to_string));
+        self
+    }
+    /// Add `#[should_panic]`, with an optional substring of the expected panic message
+    pub fn should_panic(mut self, expected: Option<&str>) -> Self {
+        self.should_panic = Some(expected.map(str::to_string));
+        self
+    }
+    /// Make the test `async`, for test attributes that support it
+    pub fn asyncness(mut self, asyncness: bool) -> Self {
+        self.asyncness = asyncness;
+        self
+    }
+    /// Set the return type, e.g. a `Result` to use `?` in the body
+    pub fn ret(mut self, ty: impl ToTokens) -> Self {
+        self.ret = Some(ty.into_token_stream());
+        self
+    }
+    /// Set the statements of the body
+    pub fn body(mut self, body: impl ToTokens) -> Self {
+        self.body = body.into_token_stream();
+        self
+    }
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// The name of the test
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    /// Render the test function
+    pub fn build(&self) -> TokenStream {
+        let mut builder = FnBuilder::new(self.name.clone())
+            .attr(&self.test_attr)
+            .asyncness(self.asyncness)
+            .body(&self.body)
+            .lints(self.lints.clone());
+        for attr in &self.attrs {
+            builder = builder.attr(attr);
+        }
+        match &self.ignore {
+            Some(Some(reason)) => builder = builder.attr(quote!(ignore = #reason)),
+            Some(None) => builder = builder.attr(quote!(ignore)),
+            None => {}
+        }
+        match &self.should_panic {
+            Some(Some(expected)) => {
+                builder = builder.attr(quote!(should_panic(expected = #expected)));
+            }
+            Some(None) => builder = builder.attr(quote!(should_panic)),
+            None => {}
+        }
+        if let Some(ret) = &self.ret {
+            builder = builder.ret(ret);
+        }
+        builder.build()
+    }
+}
+
+impl ToTokens for TestBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
+
+/// `#[cfg(test)] mod name { use super::*; tests... }`, with `LintPolicy::default()`
+pub(crate) fn test_module(
+    name: Ident,
+    tests: impl IntoIterator<Item = TestBuilder>,
+) -> TokenStream {
+    let tests = tests.into_iter();
+    let lints = LintPolicy::default();
+    quote! {
+        #lints
+        #[cfg(test)]
+        mod #name {
+            use super::*;
+            #(#tests)*
+        }
+    }
+}
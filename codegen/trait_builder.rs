@@ -0,0 +1,169 @@
+#![allow(dead_code)]
+
+use super::super::IdePolicy;
+use super::{FnBuilder, LintPolicy, Provenance};
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
+
+/// A builder for `trait` definitions, e.g. extension traits or sealed traits generated from the
+/// user's input.
+/// ```ignore
+/// let ext = TraitBuilder::new(format_ident!("{}Ext", name))
+///     .vis(quote!(pub))
+///     .supertrait(quote!(Sized))
+///     .assoc_type(format_ident!("Output"), Some(quote!(Clone)), None)
+///     .method(FnBuilder::new(format_ident!("describe")).receiver(quote!(&self)).ret(quote!(String)).no_body());
+/// ```
+#[derive(Clone)]
+pub(crate) struct TraitBuilder {
+    name: Ident,
+    attrs: Vec<TokenStream>,
+    vis: TokenStream,
+    unsafety: bool,
+    generics: Vec<TokenStream>,
+    supertraits: Vec<TokenStream>,
+    where_predicates: Vec<TokenStream>,
+    items: Vec<TokenStream>,
+    lints: LintPolicy,
+    provenance: Option<Provenance>,
+}
+
+impl TraitBuilder {
+    /// Start a private trait with the given name
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            attrs: vec![],
+            vis: TokenStream::new(),
+            unsafety: false,
+            generics: vec![],
+            supertraits: vec![],
+            where_predicates: vec![],
+            items: vec![],
+            lints: LintPolicy::default(),
+            provenance: Provenance::current(),
+        }
+    }
+
+    /// Add an attribute, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(quote!(#[#attr]));
+        self
+    }
+    /// Set the visibility
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Make the trait `unsafe` or not
+    pub fn unsafety(mut self, unsafety: bool) -> Self {
+        self.unsafety = unsafety;
+        self
+    }
+    /// Add a generic parameter: `.generic(quote!(T: Clone))`
+    pub fn generic(mut self, param: impl ToTokens) -> Self {
+        self.generics.push(param.into_token_stream());
+        self
+    }
+    /// Add a supertrait: `.supertrait(quote!(::core::fmt::Debug))`
+    pub fn supertrait(mut self, bound: impl ToTokens) -> Self {
+        self.supertraits.push(bound.into_token_stream());
+        self
+    }
+    /// Add a predicate to the where clause
+    pub fn where_predicate(mut self, predicate: impl ToTokens) -> Self {
+        self.where_predicates.push(predicate.into_token_stream());
+        self
+    }
+
+    /// Add an associated type `type name: bounds = default;`
+    pub fn assoc_type(
+        mut self,
+        name: Ident,
+        bounds: Option<TokenStream>,
+        default: Option<TokenStream>,
+    ) -> Self {
+        let bounds = bounds.map(|bounds| quote!(: #bounds));
+        let default = default.map(|default| quote!(= #default));
+        self.items.push(quote!(type #name #bounds #default;));
+        self
+    }
+    /// Add an associated constant `const name: ty = default;`
+    pub fn assoc_const(
+        mut self,
+        name: Ident,
+        ty: impl ToTokens,
+        default: Option<TokenStream>,
+    ) -> Self {
+        let default = default.map(|default| quote!(= #default));
+        self.items.push(quote!(const #name: #ty #default;));
+        self
+    }
+    /// Add a method. Use `FnBuilder::no_body` for required methods, and a body for provided ones.
+    pub fn method(mut self, method: FnBuilder) -> Self {
+        self.items.push(method.provenance(None).build());
+        self
+    }
+    /// Add an arbitrary item
+    pub fn item(mut self, item: impl ToTokens) -> Self {
+        self.items.push(item.into_token_stream());
+        self
+    }
+
+    /// Replace the lints that are allowed on the output, see `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+    /// Replace the provenance note on the output, or remove it with `None`, see `Provenance`
+    pub fn provenance(mut self, provenance: Option<Provenance>) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// The name of the trait
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    /// Render the trait
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "TraitBuilder::build", skip_all)
+    )]
+    pub fn build(&self) -> TokenStream {
+        let Self {
+            name,
+            attrs,
+            vis,
+            unsafety,
+            generics,
+            supertraits,
+            where_predicates,
+            items,
+            lints,
+            provenance,
+        } = self;
+        let span = IdePolicy::current().item_span(name);
+        let unsafety = unsafety.then(|| quote_spanned!(span=> unsafe));
+        let generics = (!generics.is_empty()).then(|| quote_spanned!(span=> <#(#generics),*>));
+        let supertraits =
+            (!supertraits.is_empty()).then(|| quote_spanned!(span=> : #(#supertraits)+*));
+        let where_clause = (!where_predicates.is_empty())
+            .then(|| quote_spanned!(span=> where #(#where_predicates,)*));
+        quote_spanned! {span=>
+            #lints
+            #(#attrs)*
+            #provenance
+            #vis #unsafety trait #name #generics #supertraits #where_clause {
+                #(#items)*
+            }
+        }
+    }
+}
+
+impl ToTokens for TraitBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
@@ -0,0 +1,387 @@
+#![allow(dead_code)]
+
+use super::super::IdePolicy;
+use super::{LintPolicy, Provenance};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
+
+/// A field of a `StructBuilder` or of a variant in an `EnumBuilder`
+#[derive(Clone)]
+pub(crate) struct FieldDef {
+    attrs: Vec<TokenStream>,
+    vis: TokenStream,
+    name: Option<Ident>,
+    ty: TokenStream,
+}
+
+impl FieldDef {
+    /// A named field `name: ty`
+    pub fn named(name: Ident, ty: impl ToTokens) -> Self {
+        Self {
+            attrs: vec![],
+            vis: TokenStream::new(),
+            name: Some(name),
+            ty: ty.into_token_stream(),
+        }
+    }
+    /// A tuple field
+    pub fn unnamed(ty: impl ToTokens) -> Self {
+        Self {
+            attrs: vec![],
+            vis: TokenStream::new(),
+            name: None,
+            ty: ty.into_token_stream(),
+        }
+    }
+    /// Copy a field from the input, including its attributes
+    pub fn from_field(field: &syn::Field) -> Self {
+        Self {
+            attrs: field
+                .attrs
+                .iter()
+                .map(ToTokens::into_token_stream)
+                .collect(),
+            vis: field.vis.to_token_stream(),
+            name: field.ident.clone(),
+            ty: field.ty.to_token_stream(),
+        }
+    }
+    /// Set the visibility
+    pub fn vis(mut self, vis: impl ToTokens) -> Self {
+        self.vis = vis.into_token_stream();
+        self
+    }
+    /// Add an attribute, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(quote!(#[#attr]));
+        self
+    }
+}
+
+impl ToTokens for FieldDef {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            attrs,
+            vis,
+            name,
+            ty,
+        } = self;
+        let name = name.as_ref().map(|name| quote!(#name:));
+        tokens.append_all(quote!(#(#attrs)* #vis #name #ty));
+    }
+}
+
+/// The fields of a struct or variant, and whether they are named. Panics if named and unnamed
+/// fields are mixed.
+fn render_fields(fields: &[FieldDef], span: Span) -> (TokenStream, bool) {
+    let Some(first) = fields.first() else {
+        return (TokenStream::new(), false);
+    };
+    let named = first.name.is_some();
+    assert!(
+        fields.iter().all(|field| field.name.is_some() == named),
+        "cannot mix named and unnamed fields"
+    );
+    if named {
+        (quote_spanned!(span=> { #(#fields,)* }), true)
+    } else {
+        (quote_spanned!(span=> (#(#fields),*)), false)
+    }
+}
+
+/// The parts that `StructBuilder` and `EnumBuilder` have in common
+#[derive(Clone)]
+struct TypeHeader {
+    attrs: Vec<TokenStream>,
+    derives: Vec<TokenStream>,
+    reprs: Vec<TokenStream>,
+    vis: TokenStream,
+    generics: Vec<TokenStream>,
+    where_predicates: Vec<TokenStream>,
+    lints: LintPolicy,
+    provenance: Option<Provenance>,
+}
+
+impl Default for TypeHeader {
+    fn default() -> Self {
+        Self {
+            attrs: vec![],
+            derives: vec![],
+            reprs: vec![],
+            vis: TokenStream::new(),
+            generics: vec![],
+            where_predicates: vec![],
+            lints: LintPolicy::default(),
+            provenance: Provenance::current(),
+        }
+    }
+}
+
+impl TypeHeader {
+    /// Attributes, visibility, keyword, name and generics, plus the where clause separately,
+    /// since it goes after the fields of tuple structs
+    fn render(&self, keyword: &str, name: &Ident) -> (TokenStream, Option<TokenStream>) {
+        let Self {
+            attrs,
+            derives,
+            reprs,
+            vis,
+            generics,
+            where_predicates,
+            lints,
+            provenance,
+        } = self;
+        let derives = (!derives.is_empty()).then(|| quote!(#[derive(#(#derives),*)]));
+        let reprs = (!reprs.is_empty()).then(|| quote!(#[repr(#(#reprs),*)]));
+        let generics = (!generics.is_empty()).then(|| quote!(<#(#generics),*>));
+        let where_clause =
+            (!where_predicates.is_empty()).then(|| quote!(where #(#where_predicates,)*));
+        let keyword = Ident::new(keyword, IdePolicy::current().item_span(name));
+        let header = quote! {
+            #lints
+            #derives
+            #reprs
+            #(#attrs)*
+            #provenance
+            #vis #keyword #name #generics
+        };
+        (header, where_clause)
+    }
+}
+
+/// Builder methods shared by `StructBuilder` and `EnumBuilder`
+macro_rules! header_methods {
+    () => {
+        /// Add a derive: `.derive(quote!(Clone))`. All derives are combined into one attribute.
+        pub fn derive(mut self, path: impl ToTokens) -> Self {
+            self.header.derives.push(path.into_token_stream());
+            self
+        }
+        /// Add a repr: `.repr(quote!(C))`. All reprs are combined into one attribute.
+        pub fn repr(mut self, repr: impl ToTokens) -> Self {
+            self.header.reprs.push(repr.into_token_stream());
+            self
+        }
+        /// Add an attribute, given without `#[...]`
+        pub fn attr(mut self, attr: impl ToTokens) -> Self {
+            let attr = attr.into_token_stream();
+            self.header.attrs.push(quote!(#[#attr]));
+            self
+        }
+        /// Copy existing attributes, e.g. doc comments and `#[cfg]`s from the input
+        pub fn attrs<'a>(mut self, attrs: impl IntoIterator<Item = &'a syn::Attribute>) -> Self {
+            self.header
+                .attrs
+                .extend(attrs.into_iter().map(ToTokens::into_token_stream));
+            self
+        }
+        /// Set the visibility
+        pub fn vis(mut self, vis: impl ToTokens) -> Self {
+            self.header.vis = vis.into_token_stream();
+            self
+        }
+        /// Add a generic parameter: `.generic(quote!(T: Clone))`
+        pub fn generic(mut self, param: impl ToTokens) -> Self {
+            self.header.generics.push(param.into_token_stream());
+            self
+        }
+        /// Copy the parameters and where clause of existing generics
+        pub fn generics(mut self, generics: &syn::Generics) -> Self {
+            self.header
+                .generics
+                .extend(generics.params.iter().map(ToTokens::into_token_stream));
+            if let Some(where_clause) = &generics.where_clause {
+                self.header
+                    .where_predicates
+                    .extend(where_clause.predicates.iter().map(ToTokens::into_token_stream));
+            }
+            self
+        }
+        /// Add a predicate to the where clause
+        pub fn where_predicate(mut self, predicate: impl ToTokens) -> Self {
+            self.header.where_predicates.push(predicate.into_token_stream());
+            self
+        }
+        /// Replace the lints that are allowed on the output, see `LintPolicy`
+        pub fn lints(mut self, policy: LintPolicy) -> Self {
+            self.header.lints = policy;
+            self
+        }
+        /// Replace the provenance note on the output, or remove it with `None`, see `Provenance`
+        pub fn provenance(mut self, provenance: Option<Provenance>) -> Self {
+            self.header.provenance = provenance;
+            self
+        }
+        /// The name of the type
+        pub fn name(&self) -> &Ident {
+            &self.name
+        }
+    };
+}
+
+/// A builder for struct definitions, for when fields or attributes are added conditionally.
+/// The struct is a unit struct without fields, a tuple struct if the fields are `unnamed`, and a
+/// struct with named fields otherwise.
+/// ```ignore
+/// let mut builder = StructBuilder::new(format_ident!("{}Builder", name))
+///     .vis(&input.vis)
+///     .derive(quote!(Default));
+/// for field in &fields {
+///     builder = builder.field(FieldDef::named(field.ident.clone(), quote!(Option<#ty>)));
+/// }
+/// ```
+#[derive(Clone)]
+pub(crate) struct StructBuilder {
+    name: Ident,
+    header: TypeHeader,
+    fields: Vec<FieldDef>,
+}
+
+impl StructBuilder {
+    /// Start a private unit struct with the given name
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            header: TypeHeader::default(),
+            fields: vec![],
+        }
+    }
+    header_methods!();
+
+    /// Add a field
+    pub fn field(mut self, field: FieldDef) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Render the struct. Panics if named and unnamed fields were mixed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "StructBuilder::build", skip_all)
+    )]
+    pub fn build(&self) -> TokenStream {
+        let (header, where_clause) = self.header.render("struct", &self.name);
+        let span = IdePolicy::current().item_span(&self.name);
+        match render_fields(&self.fields, span) {
+            (fields, true) => quote!(#header #where_clause #fields),
+            // `struct A<T>(T) where T: Clone;` and `struct A<T> where T: Clone;`
+            (fields, false) => quote_spanned!(span=> #header #fields #where_clause;),
+        }
+    }
+}
+
+impl ToTokens for StructBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
+
+/// A variant of an `EnumBuilder`
+#[derive(Clone)]
+pub(crate) struct VariantDef {
+    attrs: Vec<TokenStream>,
+    name: Ident,
+    fields: Vec<FieldDef>,
+    discriminant: Option<TokenStream>,
+}
+
+impl VariantDef {
+    /// A variant without fields. Add fields with `field` to make it a tuple or struct variant.
+    pub fn new(name: Ident) -> Self {
+        Self {
+            attrs: vec![],
+            name,
+            fields: vec![],
+            discriminant: None,
+        }
+    }
+    /// Copy a variant from the input, including its attributes
+    pub fn from_variant(variant: &syn::Variant) -> Self {
+        Self {
+            attrs: variant
+                .attrs
+                .iter()
+                .map(ToTokens::into_token_stream)
+                .collect(),
+            name: variant.ident.clone(),
+            fields: variant.fields.iter().map(FieldDef::from_field).collect(),
+            discriminant: variant
+                .discriminant
+                .as_ref()
+                .map(|(_, expr)| expr.to_token_stream()),
+        }
+    }
+    /// Add a field
+    pub fn field(mut self, field: FieldDef) -> Self {
+        self.fields.push(field);
+        self
+    }
+    /// Set the discriminant: `A = 1`
+    pub fn discriminant(mut self, value: impl ToTokens) -> Self {
+        self.discriminant = Some(value.into_token_stream());
+        self
+    }
+    /// Add an attribute, given without `#[...]`
+    pub fn attr(mut self, attr: impl ToTokens) -> Self {
+        self.attrs.push(quote!(#[#attr]));
+        self
+    }
+}
+
+impl ToTokens for VariantDef {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Self {
+            attrs,
+            name,
+            fields,
+            discriminant,
+        } = self;
+        let (fields, _) = render_fields(fields, IdePolicy::current().item_span(name));
+        let discriminant = discriminant.as_ref().map(|value| quote!(= #value));
+        tokens.append_all(quote!(#(#attrs)* #name #fields #discriminant));
+    }
+}
+
+/// A builder for enum definitions, see `StructBuilder`
+#[derive(Clone)]
+pub(crate) struct EnumBuilder {
+    name: Ident,
+    header: TypeHeader,
+    variants: Vec<VariantDef>,
+}
+
+impl EnumBuilder {
+    /// Start a private enum without variants
+    pub fn new(name: Ident) -> Self {
+        Self {
+            name,
+            header: TypeHeader::default(),
+            variants: vec![],
+        }
+    }
+    header_methods!();
+
+    /// Add a variant
+    pub fn variant(mut self, variant: VariantDef) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    /// Render the enum. Panics if named and unnamed fields were mixed in a variant.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "EnumBuilder::build", skip_all)
+    )]
+    pub fn build(&self) -> TokenStream {
+        let (header, where_clause) = self.header.render("enum", &self.name);
+        let variants = &self.variants;
+        let span = IdePolicy::current().item_span(&self.name);
+        quote_spanned!(span=> #header #where_clause { #(#variants,)* })
+    }
+}
+
+impl ToTokens for EnumBuilder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(self.build());
+    }
+}
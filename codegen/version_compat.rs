@@ -0,0 +1,92 @@
+#![allow(dead_code)]
+
+use super::super::RustFeature;
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+
+/// `let pattern = expr else { diverge };`, or the same as a `match` if the `TargetRustVersion`
+/// doesn't support `RustFeature::LetElse`:
+/// ```ignore
+/// // let (a, mut b) = match expr { Some((a, mut b)) => (a, b), _ => { return None; } };
+/// codegen::let_else(&syn::parse_quote!(Some((a, mut b))), quote!(expr), quote!(return None;))
+/// ```
+/// `diverge` has to diverge (`return`, `continue`, `panic!`, ...). The fallback rebinds the
+/// bindings of the pattern (the first alternative of or-patterns), so bindings in macros in the
+/// pattern are lost. Since paths and bindings look the same, plain names that start with an
+/// uppercase letter (`None`, `MAX`) are taken as constants or unit variants.
+pub(crate) fn let_else(pat: &syn::Pat, expr: impl ToTokens, diverge: impl ToTokens) -> TokenStream {
+    let expr = expr.into_token_stream();
+    let diverge = diverge.into_token_stream();
+    if RustFeature::LetElse.is_supported() {
+        return quote!(let #pat = #expr else { #diverge };);
+    }
+    let mut bindings = vec![];
+    pattern_bindings(pat, &mut bindings);
+    let names = bindings.iter().map(|(name, _)| name);
+    let outer = bindings.iter().map(|(name, mutable)| {
+        let mutability = mutable.then(|| quote!(mut));
+        quote!(#mutability #name)
+    });
+    quote! {
+        #[allow(unused_mut)]
+        let (#(#outer,)*) = match #expr {
+            #pat => (#(#names,)*),
+            _ => { #diverge }
+        };
+    }
+}
+
+/// The names of the bindings in `pat` and whether they are `mut`
+fn pattern_bindings(pat: &syn::Pat, out: &mut Vec<(Ident, bool)>) {
+    match pat {
+        syn::Pat::Ident(pat) => {
+            let is_path = pat.by_ref.is_none()
+                && pat.mutability.is_none()
+                && pat.subpat.is_none()
+                && pat.ident.to_string().starts_with(char::is_uppercase);
+            if !is_path {
+                out.push((pat.ident.clone(), pat.mutability.is_some()));
+            }
+            if let Some((_, sub)) = &pat.subpat {
+                pattern_bindings(sub, out);
+            }
+        }
+        syn::Pat::Or(pat) => {
+            if let Some(first) = pat.cases.first() {
+                pattern_bindings(first, out);
+            }
+        }
+        syn::Pat::Paren(pat) => pattern_bindings(&pat.pat, out),
+        syn::Pat::Reference(pat) => pattern_bindings(&pat.pat, out),
+        syn::Pat::Type(pat) => pattern_bindings(&pat.pat, out),
+        syn::Pat::Slice(pat) => pat.elems.iter().for_each(|pat| pattern_bindings(pat, out)),
+        syn::Pat::Tuple(pat) => pat.elems.iter().for_each(|pat| pattern_bindings(pat, out)),
+        syn::Pat::TupleStruct(pat) => pat.elems.iter().for_each(|pat| pattern_bindings(pat, out)),
+        syn::Pat::Struct(pat) => pat
+            .fields
+            .iter()
+            .for_each(|field| pattern_bindings(&field.pat, out)),
+        _ => {}
+    }
+}
+
+/// An inline `const { expr }` of type `ty`, or a block with a `const` item if the
+/// `TargetRustVersion` doesn't support `RustFeature::InlineConst`. Unlike an inline `const`,
+/// the item can't use generic parameters of the surrounding function, so use
+/// `RustFeature::InlineConst.require` instead if `expr` needs them.
+/// ```ignore
+/// // evaluated at compile time: const { WIDTH * HEIGHT }
+/// let size = codegen::inline_const(quote!(usize), quote!(#width * #height));
+/// quote!(let buffer = vec![0u8; #size];)
+/// ```
+pub(crate) fn inline_const(ty: impl ToTokens, expr: impl ToTokens) -> TokenStream {
+    let expr = expr.into_token_stream();
+    if RustFeature::InlineConst.is_supported() {
+        return quote!(const { #expr });
+    }
+    let ty = ty.into_token_stream();
+    quote!({
+        const __VALUE: #ty = #expr;
+        __VALUE
+    })
+}
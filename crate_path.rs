@@ -0,0 +1,254 @@
+#![allow(dead_code)]
+
+use super::{AttrKey, AttrValueKind, AttrValues};
+use proc_macro2::{Ident, Span};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The path that generated code should use to refer to the crate `name` (as written in
+/// `[package] name`), e.g. the runtime crate of a macro. Reads the `Cargo.toml` of the crate
+/// that is being compiled, so that renamed dependencies work:
+/// - `my-runtime = "1"` gives `::my_runtime`
+/// - `rt = { package = "my-runtime", version = "1" }` gives `::rt`
+/// - inside of `my-runtime` itself (e.g. if it uses its own macros), `crate`
+///
+/// Normal, dev, build and target-specific dependencies are searched, but not renames in
+/// `[workspace.dependencies]`. If the crate is not found (or there is no manifest, e.g. in unit
+/// tests), `::my_runtime` is used, which works for the common case. The dependencies are cached
+/// per manifest directory.
+///
+/// Users can override the path with a `crate = "..."` key, see `AttrKey::crate_path` and
+/// `AttrValues::crate_path_or`.
+/// ```ignore
+/// let rt = crate_path("my-runtime");
+/// quote!(impl #rt::Component for #name { ... })
+/// ```
+pub(crate) fn crate_path(name: &str) -> syn::Path {
+    match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(dir) => resolve(&dir, name),
+        Err(_) => absolute(&name.replace('-', "_")),
+    }
+}
+
+impl AttrKey {
+    /// The `crate = "..."` key for overriding the path of a runtime crate, which takes a path as
+    /// is or in a string: `#[my_attr(crate = "::renamed")]` or `#[my_attr(crate = ::renamed)]`
+    pub fn crate_path() -> Self {
+        AttrKey::new("crate", AttrValueKind::Path)
+    }
+}
+
+impl AttrValues {
+    /// The path given for `key` (usually `"crate"`, see `AttrKey::crate_path`), or `crate_path`
+    /// of `name` if it wasn't given
+    pub fn crate_path_or(&self, key: &str, name: &str) -> syn::Path {
+        self.path(key).cloned().unwrap_or_else(|| crate_path(name))
+    }
+}
+
+struct Manifest {
+    dir: String,
+    package: Option<String>,
+    /// `(name in the code, package name)` of all dependencies
+    dependencies: Vec<(String, String)>,
+}
+
+fn resolve(dir: &str, name: &str) -> syn::Path {
+    static CACHE: Mutex<Option<Manifest>> = Mutex::new(None);
+
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.as_ref().is_none_or(|manifest| manifest.dir != dir) {
+        *cache = Some(read_manifest(dir));
+    }
+    let manifest = cache.as_ref().expect("the cache was just filled");
+
+    let lib_name = name.replace('-', "_");
+    if manifest.package.as_deref() == Some(name)
+        && std::env::var("CARGO_CRATE_NAME").is_ok_and(|krate| krate == lib_name)
+    {
+        return syn::parse_quote!(crate);
+    }
+    let alias = manifest
+        .dependencies
+        .iter()
+        .find(|(_, package)| package == name)
+        .map_or(lib_name, |(alias, _)| alias.replace('-', "_"));
+    absolute(&alias)
+}
+
+fn absolute(name: &str) -> syn::Path {
+    let ident = Ident::new(name, Span::call_site());
+    syn::parse_quote!(::#ident)
+}
+
+fn read_manifest(dir: &str) -> Manifest {
+    let text = std::fs::read_to_string(Path::new(dir).join("Cargo.toml")).unwrap_or_default();
+    let mut manifest = Manifest {
+        dir: dir.to_string(),
+        package: None,
+        dependencies: vec![],
+    };
+    parse_manifest(&text, &mut manifest);
+    manifest
+}
+
+/// Minimal line-based lookup of the package name and the dependencies, in the same spirit as the
+/// `edition` lookup of `Edition::detect`. Handles `alias = "1"`, `alias = { package = "..." }`
+/// (also spread over several lines), `alias.package = "..."` and `[dependencies.alias]` sections.
+fn parse_manifest(text: &str, manifest: &mut Manifest) {
+    let mut section = String::new();
+    let mut lines = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim());
+    while let Some(line) = lines.next() {
+        if line.starts_with('[') {
+            section = line
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim()
+                .to_string();
+            if let Some(alias) = dependency_table(&section) {
+                add_dependency(manifest, alias, None);
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if section == "package" && key == "name" {
+            manifest.package = Some(unquote(value).to_string());
+        } else if is_dependency_section(&section) {
+            let (alias, field) = match key.split_once('.') {
+                Some((alias, field)) => (alias.trim(), Some(field.trim())),
+                None => (key, None),
+            };
+            let package = match field {
+                Some("package") => Some(unquote(value).to_string()),
+                // `alias.workspace = true` or `alias.version = "1"`
+                Some(_) => None,
+                None if value.starts_with('{') && !value.contains('}') => {
+                    let mut table = value.to_string();
+                    for line in lines.by_ref() {
+                        table.push(' ');
+                        table.push_str(line);
+                        if line.contains('}') {
+                            break;
+                        }
+                    }
+                    inline_package(&table).map(|package| unquote(package).to_string())
+                }
+                None => inline_package(value).map(|package| unquote(package).to_string()),
+            };
+            add_dependency(manifest, alias, package);
+        } else if key == "package" {
+            if let Some(alias) = dependency_table(&section) {
+                add_dependency(manifest, alias, Some(unquote(value).to_string()));
+            }
+        }
+    }
+}
+
+/// Add a dependency, or set the package name of one that was already added, since dotted keys
+/// and `[dependencies.alias]` sections can spread it over several lines
+fn add_dependency(manifest: &mut Manifest, alias: &str, package: Option<String>) {
+    let alias = unquote(alias);
+    let existing = manifest
+        .dependencies
+        .iter_mut()
+        .find(|(name, _)| name == alias);
+    match (existing, package) {
+        (Some(dependency), Some(package)) => dependency.1 = package,
+        (Some(_), None) => {}
+        (None, package) => {
+            let package = package.unwrap_or_else(|| alias.to_string());
+            manifest.dependencies.push((alias.to_string(), package));
+        }
+    }
+}
+
+fn is_dependency_section(section: &str) -> bool {
+    let section = section
+        .strip_prefix("target.")
+        .and_then(|rest| rest.rsplit_once('.'))
+        .map_or(section, |(_, kind)| kind);
+    matches!(
+        section,
+        "dependencies" | "dev-dependencies" | "build-dependencies"
+    )
+}
+
+/// The alias of a `[dependencies.alias]` section
+fn dependency_table(section: &str) -> Option<&str> {
+    let (kind, alias) = section.rsplit_once('.')?;
+    is_dependency_section(kind).then_some(alias.trim())
+}
+
+/// The `package` key of an inline table: `{ package = "name", version = "1" }`
+fn inline_package(value: &str) -> Option<&str> {
+    let inner = value.strip_prefix('{')?.strip_suffix('}')?;
+    inner.split(',').find_map(|entry| {
+        let (key, value) = entry.split_once('=')?;
+        (key.trim() == "package").then(|| value.trim())
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(text: &str) -> Vec<(String, String)> {
+        let mut manifest = Manifest {
+            dir: String::new(),
+            package: None,
+            dependencies: vec![],
+        };
+        parse_manifest(text, &mut manifest);
+        manifest.dependencies
+    }
+
+    fn pair(alias: &str, package: &str) -> (String, String) {
+        (alias.to_string(), package.to_string())
+    }
+
+    #[test]
+    fn dotted_package_keys() {
+        let text = r#"
+            [dependencies]
+            rt.version = "1"
+            rt.package = "my-runtime"
+            plain.workspace = true
+
+            [dependencies.other]
+            version = "1"
+            package = "other-runtime"
+        "#;
+        assert_eq!(
+            dependencies(text),
+            [
+                pair("rt", "my-runtime"),
+                pair("plain", "plain"),
+                pair("other", "other-runtime")
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_line_inline_tables() {
+        let text = r#"
+            [dev-dependencies]
+            rt = {
+                version = "1", # comment
+                package = "my-runtime",
+            }
+            serde = { version = "1", features = ["derive"] }
+        "#;
+        assert_eq!(
+            dependencies(text),
+            [pair("rt", "my-runtime"), pair("serde", "serde")]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+#![allow(dead_code)]
+
+use std::{cell::RefCell, path::Path, sync::Mutex};
+
+/// Overrides the edition that `Edition::detect` finds, e.g. `MACRO_EDITION=2018`, for crates whose
+/// edition can't be read from their `Cargo.toml` (non-cargo builds)
+pub(crate) const EDITION_VAR: &str = "MACRO_EDITION";
+
+thread_local! {
+    static CURRENT: RefCell<Option<Edition>> = const { RefCell::new(None) };
+}
+
+/// A Rust edition
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub(crate) enum Edition {
+    E2015,
+    E2018,
+    E2021,
+    E2024,
+}
+
+impl Edition {
+    /// The newest edition known to this crate
+    pub const LATEST: Edition = Edition::E2024;
+
+    /// Parse an edition from its year, e.g. `"2021"`
+    pub fn from_year(year: &str) -> Option<Edition> {
+        match year {
+            "2015" => Some(Edition::E2015),
+            "2018" => Some(Edition::E2018),
+            "2021" => Some(Edition::E2021),
+            "2024" => Some(Edition::E2024),
+            _ => None,
+        }
+    }
+    /// The year of the edition, e.g. `"2021"`
+    pub fn year(self) -> &'static str {
+        match self {
+            Edition::E2015 => "2015",
+            Edition::E2018 => "2018",
+            Edition::E2021 => "2021",
+            Edition::E2024 => "2024",
+        }
+    }
+
+    /// The edition of the crate that is currently being compiled, i.e. the crate the macro is
+    /// used in, or the edition set with `scoped`. Falls back to `Edition::LATEST` if it can't be
+    /// determined, which is the safe choice for anything that avoids keywords.
+    pub fn current() -> Edition {
+        CURRENT
+            .with_borrow(|current| *current)
+            .or_else(Edition::detect)
+            .unwrap_or(Edition::LATEST)
+    }
+    /// Use this edition as `current` while running `f`, e.g. in unit tests, where
+    /// `CARGO_MANIFEST_DIR` is the macro crate instead of the crate the macro is used in
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::scope::scoped(&CURRENT, Some(self), f)
+    }
+    /// Try to determine the edition of the crate that is currently being compiled from
+    /// `MACRO_EDITION`, or by reading the `edition` key from its `Cargo.toml` (in
+    /// `CARGO_MANIFEST_DIR`), including `edition.workspace = true`. The manifest lookup is cached
+    /// per manifest directory.
+    pub fn detect() -> Option<Edition> {
+        static CACHE: Mutex<Option<(String, Option<Edition>)>> = Mutex::new(None);
+
+        if let Some(edition) = std::env::var(EDITION_VAR)
+            .ok()
+            .and_then(|year| Edition::from_year(year.trim()))
+        {
+            return Some(edition);
+        }
+
+        let dir = std::env::var("CARGO_MANIFEST_DIR").ok()?;
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_dir, edition)) = &*cache {
+            if *cached_dir == dir {
+                return *edition;
+            }
+        }
+        let edition = detect_from_manifest_dir(Path::new(&dir));
+        *cache = Some((dir, edition));
+        edition
+    }
+}
+
+/// The edition of the crate that the macro is expanded in, for edition-dependent codegen like
+/// `codegen::trait_object` or `codegen::core_path`. Same as `Edition::current()`.
+pub(crate) fn build_edition() -> Edition {
+    Edition::current()
+}
+
+fn detect_from_manifest_dir(dir: &Path) -> Option<Edition> {
+    match package_key(dir, "edition")? {
+        Some(year) => Edition::from_year(&year),
+        // cargo defaults to 2015 if no edition is specified
+        None => Some(Edition::E2015),
+    }
+}
+
+/// The value of `key` in the `[package]` section of the `Cargo.toml` in `dir`, following
+/// `key.workspace = true` to the `[workspace.package]` section of a parent manifest. `None` if
+/// the manifest (or the inherited key) can't be found, `Some(None)` if the key isn't set.
+pub(crate) fn package_key(dir: &Path, key: &str) -> Option<Option<String>> {
+    let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    match manifest_key(&manifest, "package", key) {
+        Some(ManifestValue::Value(value)) => Some(Some(value)),
+        Some(ManifestValue::Workspace) => dir.ancestors().skip(1).find_map(|parent| {
+            let manifest = std::fs::read_to_string(parent.join("Cargo.toml")).ok()?;
+            match manifest_key(&manifest, "workspace.package", key)? {
+                ManifestValue::Value(value) => Some(Some(value)),
+                ManifestValue::Workspace => None,
+            }
+        }),
+        None => Some(None),
+    }
+}
+
+enum ManifestValue {
+    Value(String),
+    Workspace,
+}
+
+/// Minimal line-based lookup of a string key of a `Cargo.toml` section. This avoids a
+/// dependency on a full TOML parser for a few keys.
+fn manifest_key(manifest: &str, section: &str, key_name: &str) -> Option<ManifestValue> {
+    let mut in_section = false;
+    for line in manifest.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.starts_with('[') {
+            in_section = line.trim_matches(|c| c == '[' || c == ']').trim() == section;
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key == format!("{}.workspace", key_name)
+            || (key == key_name && value.contains("workspace"))
+        {
+            return Some(ManifestValue::Workspace);
+        }
+        if key == key_name {
+            let value = value.trim_matches(|c| c == '"' || c == '\'');
+            return Some(ManifestValue::Value(value.to_string()));
+        }
+    }
+    None
+}
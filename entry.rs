@@ -0,0 +1,264 @@
+#![allow(dead_code)]
+
+use super::{tokens, Error};
+use proc_macro2::{Span, TokenStream};
+use std::cell::RefCell;
+use std::panic::{AssertUnwindSafe, Location};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Once;
+
+/// The names of the macros to dump, separated by `,`, or `*` for all macros
+pub(crate) const MACRO_DEBUG_VAR: &str = "MACRO_DEBUG";
+/// Set to `out_dir` to write the dumps to `$OUT_DIR/macro_debug/` instead of stderr
+pub(crate) const MACRO_DEBUG_OUT_VAR: &str = "MACRO_DEBUG_OUT";
+
+static INVOCATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// `Some` while a `MacroEntry` runs, filled with the location by the panic hook
+    static PANIC_LOCATION: RefCell<Option<Option<String>>> = const { RefCell::new(None) };
+}
+
+/// The wrapper around the implementation of a proc macro, for everything that should happen in
+/// every invocation. Converts between `proc_macro` and `proc_macro2` tokens and turns errors into
+/// `compile_error!`s.
+///
+/// Panics in the implementation are turned into a compile error with the panic message and
+/// location, the version of the macro crate and a line asking to report the bug at the
+/// `repository` (or `homepage`) of the macro crate's `Cargo.toml`, instead of the compiler's
+/// opaque "proc macro panicked". The version and URL can be overridden.
+///
+/// If the environment variable `MACRO_DEBUG` contains the name of the macro when the crate using
+/// it is compiled, the input and output of every invocation are pretty-printed to stderr, or to
+/// files in `$OUT_DIR/macro_debug/` with `MACRO_DEBUG_OUT=out_dir`. This is a "cargo expand for
+/// one macro" that bug reporters can use without extra tools:
+/// `MACRO_DEBUG=getters cargo build`
+///
+/// The input and output types are generic, so this doesn't need the compiler's `proc_macro`
+/// crate. With the `no-proc-macro` feature (e.g. for `watt`, where the macro is compiled to wasm
+/// and only sees `proc_macro2` tokens), pass and return `proc_macro2::TokenStream`. There is no
+/// unwinding on wasm, so panics abort the macro there instead of becoming an error report.
+/// ```ignore
+/// #[proc_macro_derive(Getters, attributes(getter))]
+/// pub fn getters(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+///     MacroEntry::new("getters").run(input, derive_getters)
+/// }
+/// #[proc_macro_attribute]
+/// pub fn my_attr(args: TokenStream, item: TokenStream) -> TokenStream {
+///     MacroEntry::new("my_attr").run_attribute(args, item, my_attr_impl)
+/// }
+/// // watt
+/// #[no_mangle]
+/// pub extern "C" fn getters(input: TokenStream) -> TokenStream {
+///     MacroEntry::new("getters").run(input, derive_getters)
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct MacroEntry {
+    name: String,
+    version: String,
+    bug_url: Option<String>,
+}
+
+impl MacroEntry {
+    /// The name is the one users put in `MACRO_DEBUG`, usually the name of the macro. The
+    /// version and bug URL are taken from the `Cargo.toml` of the macro crate.
+    pub fn new(name: &str) -> Self {
+        let bug_url = [
+            option_env!("CARGO_PKG_REPOSITORY"),
+            option_env!("CARGO_PKG_HOMEPAGE"),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|url| !url.is_empty());
+        Self {
+            name: name.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            bug_url: bug_url.map(str::to_string),
+        }
+    }
+
+    /// Set the version that is shown in internal error reports
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+    /// Set where internal errors should be reported, or `None` to not ask for reports
+    pub fn bug_url(mut self, url: Option<&str>) -> Self {
+        self.bug_url = url.map(str::to_string);
+        self
+    }
+
+    /// The name of the macro
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run a function-like or derive macro
+    pub fn run<I, O, E>(
+        &self,
+        input: I,
+        macro_fn: impl FnOnce(TokenStream) -> std::result::Result<TokenStream, E>,
+    ) -> O
+    where
+        I: Into<TokenStream>,
+        O: From<TokenStream>,
+        E: Into<Error>,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("expand", macro_name = %self.name).entered();
+        let input = input.into();
+        let debug_input = self.debug_enabled().then(|| input.clone());
+        let output = self.expand(|| macro_fn(input));
+        if let Some(input) = debug_input {
+            self.dump(&[("input", input)], &output);
+        }
+        output.into()
+    }
+
+    /// Run an attribute macro, which gets the arguments of the attribute and the item
+    pub fn run_attribute<I, O, E>(
+        &self,
+        args: I,
+        item: I,
+        macro_fn: impl FnOnce(TokenStream, TokenStream) -> std::result::Result<TokenStream, E>,
+    ) -> O
+    where
+        I: Into<TokenStream>,
+        O: From<TokenStream>,
+        E: Into<Error>,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("expand", macro_name = %self.name).entered();
+        let (args, item) = (args.into(), item.into());
+        let debug_input = self
+            .debug_enabled()
+            .then(|| [("args", args.clone()), ("item", item.clone())]);
+        let output = self.expand(|| macro_fn(args, item));
+        if let Some(input) = debug_input {
+            self.dump(&input, &output);
+        }
+        output.into()
+    }
+
+    fn expand<E: Into<Error>>(
+        &self,
+        macro_fn: impl FnOnce() -> std::result::Result<TokenStream, E>,
+    ) -> TokenStream {
+        install_panic_hook();
+        let outer = PANIC_LOCATION.with(|location| location.replace(Some(None)));
+        let result = std::panic::catch_unwind(AssertUnwindSafe(macro_fn));
+        let location = PANIC_LOCATION
+            .with(|location| location.replace(outer))
+            .flatten();
+        let output = match result {
+            Ok(Ok(output)) => output,
+            Ok(Err(error)) => TokenStream::from(error.into()),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Error::new(Span::call_site(), self.internal_error(&message, location)).into()
+            }
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(tokens = tokens::stats(&output).tokens, "expanded");
+        output
+    }
+
+    fn internal_error(&self, message: &str, location: Option<String>) -> String {
+        let mut report = format!(
+            "internal error in `{}` {}: {}",
+            self.name, self.version, message
+        );
+        if let Some(location) = location {
+            report += &format!("\n  at {}", location);
+        }
+        match &self.bug_url {
+            Some(url) => report += &format!("\nthis is a bug, please report it at {}", url),
+            None => report += "\nthis is a bug in the macro",
+        }
+        report
+    }
+
+    /// Check if `MACRO_DEBUG` selects this macro
+    pub fn debug_enabled(&self) -> bool {
+        std::env::var(MACRO_DEBUG_VAR).is_ok_and(|value| {
+            value
+                .split(',')
+                .any(|name| name.trim() == self.name || name.trim() == "*")
+        })
+    }
+
+    fn dump(&self, inputs: &[(&str, TokenStream)], output: &TokenStream) {
+        let invocation = INVOCATION.fetch_add(1, Ordering::Relaxed);
+        let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "?".to_string());
+        let mut text = format!(
+            "// {}: `{}` in crate `{}`, invocation {}\n",
+            MACRO_DEBUG_VAR, self.name, krate, invocation
+        );
+        for (title, tokens) in inputs {
+            text += &format!("// ---- {} ----\n", title);
+            text += &tokens::format_generated(tokens.clone());
+        }
+        text += "// ---- output ----\n";
+        text += &tokens::format_generated(output.clone());
+
+        if let Some(dir) = self.dump_dir() {
+            let path = dir.join(format!("{}_{}.rs", self.name, invocation));
+            let written = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, &text));
+            match written {
+                Ok(()) => eprintln!("{}: wrote {}", MACRO_DEBUG_VAR, path.display()),
+                Err(e) => eprintln!(
+                    "{}: failed to write {}: {}",
+                    MACRO_DEBUG_VAR,
+                    path.display(),
+                    e
+                ),
+            }
+        } else {
+            eprintln!("{}", text);
+        }
+    }
+
+    fn dump_dir(&self) -> Option<PathBuf> {
+        if std::env::var(MACRO_DEBUG_OUT_VAR).ok()? != "out_dir" {
+            return None;
+        }
+        match std::env::var_os("OUT_DIR") {
+            Some(dir) => Some(PathBuf::from(dir).join("macro_debug")),
+            None => {
+                eprintln!(
+                    "{}: OUT_DIR is only set for crates with a build script, printing instead",
+                    MACRO_DEBUG_VAR
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Record the location of panics inside of `MacroEntry::expand` instead of printing them, since
+/// they become compile errors. Other panics go to the previous hook.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(Location::to_string);
+            let recorded = PANIC_LOCATION.with(|current| match &mut *current.borrow_mut() {
+                Some(slot) => {
+                    *slot = location;
+                    true
+                }
+                None => false,
+            });
+            if !recorded {
+                previous(info);
+            }
+        }));
+    });
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-use proc_macro2::{Span, TokenStream};
+use super::tokens::TokenRope;
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 use std::fmt::Display;
 
 /// A proc-macro error that can be turned into a compile error. More versatile than `syn::Error`
@@ -14,11 +15,16 @@ impl Error {
     /// Create a new error with a message and a span. Note that `span()` does not work reliably
     /// on stable, so `new_spanned` should be preferred in most cases.
     pub fn new(span: Span, message: impl Display) -> Self {
-        syn::Error::new(span, message).into()
+        Self(compile_error(span, span, &message.to_string()))
     }
     /// Create a new error with a message and the spans taken from the tokens
     pub fn new_spanned(tokens: impl quote::ToTokens, message: impl Display) -> Self {
-        syn::Error::new_spanned(tokens, message).into()
+        let mut iter = quote::ToTokens::into_token_stream(tokens).into_iter();
+        let start = iter
+            .next()
+            .map_or_else(Span::call_site, |token| token.span());
+        let end = iter.last().map_or(start, |token| token.span());
+        Self(compile_error(start, end, &message.to_string()))
     }
     /// Create a new error with a message and the spans taken from items in an iterator
     pub fn new_from_spans<T: quote::ToTokens>(
@@ -54,12 +60,12 @@ impl Error {
 }
 
 /// A builder for creating multiple errors at once
-pub(crate) struct ErrorBuilder(TokenStream);
+pub(crate) struct ErrorBuilder(TokenRope);
 
 impl ErrorBuilder {
     /// Use `Error::builder()` instead
     fn new() -> Self {
-        Self(TokenStream::new())
+        Self(TokenRope::new())
     }
 
     /// Add an error with a message and a span. Same as `Error::new`
@@ -86,7 +92,7 @@ impl ErrorBuilder {
     }
     /// Add an already created error
     pub fn with_error(&mut self, error: impl Into<Error>) -> &mut Self {
-        self.0.extend(TokenStream::from(error.into()));
+        self.0.push(error.into().0);
         self
     }
     /// Add an already created error
@@ -101,7 +107,7 @@ impl ErrorBuilder {
 
     /// Build the errors into a single error
     pub fn build(&mut self) -> Error {
-        Error(std::mem::take(&mut self.0))
+        Error(std::mem::take(&mut self.0).finish())
     }
     /// Build the errors into a single error and return it as a result
     pub fn build_err<R>(&mut self) -> Result<R> {
@@ -128,6 +134,32 @@ impl ErrorBuilder {
     }
 }
 
+/// `::core::compile_error! { "message" }`, with the path at `start` and the message at `end`, so
+/// that the error covers both on stable (where spans can't be joined). The same tokens that
+/// `syn::Error::to_compile_error` produces, but without needing `syn`.
+fn compile_error(start: Span, end: Span, message: &str) -> TokenStream {
+    let punct = |ch, spacing| {
+        let mut punct = Punct::new(ch, spacing);
+        punct.set_span(start);
+        TokenTree::Punct(punct)
+    };
+    let mut literal = Literal::string(message);
+    literal.set_span(end);
+    let mut body = Group::new(Delimiter::Brace, TokenTree::Literal(literal).into());
+    body.set_span(end);
+    TokenStream::from_iter([
+        punct(':', Spacing::Joint),
+        punct(':', Spacing::Alone),
+        TokenTree::Ident(Ident::new("core", start)),
+        punct(':', Spacing::Joint),
+        punct(':', Spacing::Alone),
+        TokenTree::Ident(Ident::new("compile_error", start)),
+        punct('!', Spacing::Alone),
+        TokenTree::Group(body),
+    ])
+}
+
+#[cfg(not(feature = "no-syn"))]
 impl From<syn::Error> for Error {
     fn from(err: syn::Error) -> Self {
         Error(err.to_compile_error())
@@ -145,6 +177,9 @@ impl From<Error> for TokenStream {
         err.0
     }
 }
+/// Not available with the `no-proc-macro` feature, which keeps the compiler's `proc_macro` crate
+/// out of the library code, e.g. for macros compiled to wasm and run by `watt`
+#[cfg(not(feature = "no-proc-macro"))]
 impl From<Error> for proc_macro::TokenStream {
     fn from(err: Error) -> Self {
         err.0.into()
@@ -0,0 +1,198 @@
+#![allow(dead_code)]
+
+use super::{AttrSchema, AttrValueKind, AttrValues, Case, Error, Result};
+use proc_macro2::TokenStream;
+
+/// Options of an attribute, parsed into a struct. Usually implemented with `from_attr!`.
+pub(crate) trait FromAttr: Sized {
+    /// The schema with one key per field
+    fn schema() -> AttrSchema;
+    /// Build the options from the values parsed with `schema`
+    fn from_values(values: &AttrValues) -> Result<Self>;
+
+    /// Parse all attributes with the name of the schema, see `AttrSchema::parse`
+    fn from_attrs(attrs: &[syn::Attribute]) -> Result<Self> {
+        Self::from_values(&Self::schema().parse(attrs)?)
+    }
+    /// Parse the arguments of an attribute macro, see `AttrSchema::parse_args`
+    fn from_args(args: TokenStream) -> Result<Self> {
+        Self::from_values(&Self::schema().parse_args(args)?)
+    }
+}
+
+/// A type that a field of a `from_attr!` struct can have
+pub(crate) trait FromAttrValue: Sized {
+    /// The kind of the key for fields of this type
+    const KIND: AttrValueKind;
+    /// The value of `key`, or `None` if it wasn't given
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>>;
+    /// The value if the key wasn't given, or `None` if the key is required
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+impl FromAttrValue for bool {
+    const KIND: AttrValueKind = AttrValueKind::Bool;
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+        Ok(values.has(key).then(|| values.bool(key)))
+    }
+    fn missing() -> Option<Self> {
+        Some(false)
+    }
+}
+
+impl FromAttrValue for String {
+    const KIND: AttrValueKind = AttrValueKind::Str;
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+        Ok(values.str(key))
+    }
+}
+
+impl FromAttrValue for Case {
+    const KIND: AttrValueKind = AttrValueKind::Str;
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+        match values.get(key) {
+            Some(super::AttrValue::Str(lit)) => Case::from_lit(lit).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl FromAttrValue for syn::Path {
+    const KIND: AttrValueKind = AttrValueKind::Path;
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+        Ok(values.path(key).cloned())
+    }
+}
+
+impl FromAttrValue for syn::Expr {
+    const KIND: AttrValueKind = AttrValueKind::Expr;
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+        Ok(values.expr(key).cloned())
+    }
+}
+
+impl<T: FromAttrValue> FromAttrValue for Option<T> {
+    const KIND: AttrValueKind = T::KIND;
+    fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+        Ok(T::from_attr_value(values, key)?.map(Some))
+    }
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+macro_rules! int_values {
+    ($($int:ty),*) => {$(
+        impl FromAttrValue for $int {
+            const KIND: AttrValueKind = AttrValueKind::Int;
+            fn from_attr_value(values: &AttrValues, key: &str) -> Result<Option<Self>> {
+                values.int(key)
+            }
+        }
+    )*};
+}
+int_values!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// The name of the key for a field, without the `r#` of raw identifiers
+pub(crate) fn from_attr_key(field: &str) -> &str {
+    field.strip_prefix("r#").unwrap_or(field)
+}
+
+/// The value of a field of a `from_attr!` struct
+pub(crate) fn from_attr_field<T: FromAttrValue>(
+    values: &AttrValues,
+    key: &str,
+    default: Option<fn() -> T>,
+) -> Result<T> {
+    let value = T::from_attr_value(values, key)?
+        .or_else(|| default.map(|default| default()))
+        .or_else(T::missing);
+    match value {
+        Some(value) => Ok(value),
+        None => Error::err(
+            proc_macro2::Span::call_site(),
+            format!("missing required key `{}`", key),
+        ),
+    }
+}
+
+/// Declare a struct for the options of an attribute and implement `FromAttr` for it, so the
+/// options and their parsing are one declaration. Each field becomes a key named like the field,
+/// with the kind taken from its type: `bool` is a flag or `key = true/false`, `String` and `Case`
+/// are string literals, integers are integer literals, `syn::Path` and `syn::Expr` are paths and
+/// expressions. `Option` fields and fields with a default (`= expr`) are optional, all others
+/// are required.
+///
+/// This is a `macro_rules!` instead of a derive, since a derive would have to live in another
+/// proc-macro crate. Only available with the `from-attr` feature. `FromAttr`, `FromAttrValue`,
+/// `from_attr_key`, `from_attr_field`, `AttrSchema`, `AttrKey`, `AttrValues` and `Result` have
+/// to be in scope (`use utils::*;`).
+/// ```ignore
+/// from_attr! {
+///     #[from_attr("getter")]
+///     /// The options of `#[getter(...)]`
+///     struct GetterOpts {
+///         rename: Option<String>,
+///         skip: bool,
+///         max: u32 = 10,
+///         r#type: Option<syn::Path>,
+///     }
+/// }
+/// let opts = GetterOpts::from_attrs(&field.attrs)?;
+/// ```
+#[allow(unused_macros)]
+macro_rules! from_attr {
+    (
+        #[from_attr($attr:literal)]
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty $(= $default:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl FromAttr for $name {
+            fn schema() -> AttrSchema {
+                AttrSchema::new($attr)
+                    $(.key(
+                        AttrKey::new(
+                            from_attr_key(stringify!($field)),
+                            <$ty as FromAttrValue>::KIND,
+                        )
+                        .required(
+                            <$ty as FromAttrValue>::missing().is_none()
+                                && !from_attr!(@has_default $($default)?),
+                        ),
+                    ))*
+            }
+            fn from_values(values: &AttrValues) -> Result<Self> {
+                Ok(Self {
+                    $($field: from_attr_field::<$ty>(
+                        values,
+                        from_attr_key(stringify!($field)),
+                        from_attr!(@default $ty $(, $default)?),
+                    )?,)*
+                })
+            }
+        }
+    };
+    (@has_default) => { false };
+    (@has_default $default:expr) => { true };
+    (@default $ty:ty) => { ::core::option::Option::None };
+    (@default $ty:ty, $default:expr) => {
+        ::core::option::Option::Some((|| $default) as fn() -> $ty)
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use from_attr;
@@ -0,0 +1,56 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Ident, Span};
+use std::cell::RefCell;
+
+thread_local! {
+    static CURRENT: RefCell<IdePolicy> = const { RefCell::new(IdePolicy::Default) };
+}
+
+/// How much generated code is shaped for IDEs like rust-analyzer, which map generated tokens
+/// back to the source through their spans. Tokens with `Span::call_site()` all point at the
+/// whole macro invocation, so go-to-definition, hover and rename can't tell generated items
+/// apart.
+///
+/// With `IdePolicy::Friendly` (set for a whole expansion with `scoped`):
+/// - `SpanPolicy::CallSite` uses the span of the first part that has one, so `get_foo` made from
+///   a user's `foo` points at `foo`
+/// - `FnBuilder`, `StructBuilder`, `EnumBuilder` and `TraitBuilder` give their own tokens (`fn`,
+///   `struct`, braces, ...) the span of the item's name instead of the call site
+///
+/// Interpolated user tokens always keep their spans. What the builders can't enforce: prefer
+/// passing user identifiers through over building new ones with `ident::concat` or
+/// `format_ident!`, and don't `respan` user code.
+/// ```ignore
+/// #[proc_macro_derive(Getters)]
+/// pub fn getters(input: TokenStream) -> TokenStream {
+///     IdePolicy::Friendly.scoped(|| derive_getters(input.into()).into())
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum IdePolicy {
+    /// Use the call site for generated tokens, like `quote!`
+    #[default]
+    Default,
+    /// Use spans from user tokens wherever possible
+    Friendly,
+}
+
+impl IdePolicy {
+    /// The policy set with `scoped`, `Default` outside of it
+    pub fn current() -> IdePolicy {
+        CURRENT.with_borrow(|current| *current)
+    }
+    /// Use this policy while running `f`
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::scope::scoped(&CURRENT, self, f)
+    }
+
+    /// The span for the tokens that a builder adds around an item called `name`
+    pub fn item_span(self, name: &Ident) -> Span {
+        match self {
+            IdePolicy::Default => Span::call_site(),
+            IdePolicy::Friendly => name.span(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+#![allow(dead_code)]
+
+use super::{concat, SpanPolicy};
+use proc_macro2::Ident;
+use std::cell::Cell;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Set to anything but `0` to make `gensym` reproducible in the whole build
+pub(crate) const DETERMINISTIC_VAR: &str = "DETERMINISTIC_EXPANSION";
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static SCOPED_COUNTER: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Create a new identifier `__{prefix}_{suffix}` with `Span::mixed_site()`, which is different
+/// from every other identifier created by `gensym` in this process.
+///
+/// By default, the suffix contains a random part, so that names of `#[no_mangle]` or
+/// `#[export_name]` symbols from different macro crates don't collide. That makes the output
+/// different in every build, so it is replaced by a plain counter:
+/// - inside of `ident::deterministic`, with a counter that starts at `0` for every call, which is
+///   what snapshot tests need (`testing::FakeMacroContext` does this automatically)
+/// - if the environment variable `DETERMINISTIC_EXPANSION` is set (to anything but `0`) or if
+///   `SOURCE_DATE_EPOCH` is set, which reproducible builds use
+///
+/// Names that only have to differ between inputs should use `ident::mangle` instead, which is
+/// always reproducible.
+///
+/// Panics if `prefix` can't be part of an identifier.
+pub(crate) fn gensym(prefix: &str) -> Ident {
+    let scoped = SCOPED_COUNTER.with(|counter| {
+        let n = counter.get()?;
+        counter.set(Some(n + 1));
+        Some(n)
+    });
+    let suffix = match scoped {
+        Some(n) => n.to_string(),
+        None if is_deterministic() => COUNTER.fetch_add(1, Ordering::Relaxed).to_string(),
+        None => format!(
+            "{:08x}_{}",
+            process_seed(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    };
+    concat(&[&"__", &prefix, &"_", &suffix], SpanPolicy::MixedSite)
+        .unwrap_or_else(|_| panic!("`{}` is not a valid identifier prefix", prefix))
+}
+
+/// Run `f` with reproducible `gensym` names, counting from `0`. The previous mode is restored
+/// afterwards, so nested calls start over and don't affect the outer count.
+/// ```ignore
+/// let first = ident::deterministic(|| derive_getters(input.clone()));
+/// let second = ident::deterministic(|| derive_getters(input));
+/// assert_eq!(first.to_string(), second.to_string());
+/// ```
+pub(crate) fn deterministic<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<u64>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED_COUNTER.with(|counter| counter.set(self.0));
+        }
+    }
+    let _restore = Restore(SCOPED_COUNTER.with(|counter| counter.replace(Some(0))));
+    f()
+}
+
+/// Check if `gensym` currently produces reproducible names
+pub(crate) fn is_deterministic() -> bool {
+    if SCOPED_COUNTER.with(Cell::get).is_some() {
+        return true;
+    }
+    static FROM_ENV: OnceLock<bool> = OnceLock::new();
+    *FROM_ENV.get_or_init(|| {
+        std::env::var_os(DETERMINISTIC_VAR).is_some_and(|value| value != "0")
+            || std::env::var_os("SOURCE_DATE_EPOCH").is_some()
+    })
+}
+
+fn process_seed() -> u32 {
+    static SEED: OnceLock<u32> = OnceLock::new();
+    *SEED.get_or_init(|| {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u32(std::process::id());
+        hasher.finish() as u32
+    })
+}
@@ -0,0 +1,112 @@
+#![allow(dead_code)]
+
+use super::super::{Edition, Error, Result};
+use super::parse_ident;
+use proc_macro2::{Ident, Span};
+
+/// Keywords that can't be used as raw identifiers either
+pub(crate) const PATH_KEYWORDS: &[&str] = &["self", "Self", "super", "crate", "_"];
+
+/// Keywords (strict and reserved) that exist in every edition
+const KEYWORDS_2015: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield",
+];
+/// Keywords added in the 2018 edition
+const KEYWORDS_2018: &[&str] = &["async", "await", "dyn", "try"];
+/// Keywords added in the 2024 edition
+const KEYWORDS_2024: &[&str] = &["gen"];
+
+impl Edition {
+    /// Check if `name` is a keyword (strict or reserved) in this edition
+    pub fn is_keyword(self, name: &str) -> bool {
+        KEYWORDS_2015.contains(&name)
+            || (self >= Edition::E2018 && KEYWORDS_2018.contains(&name))
+            || (self >= Edition::E2024 && KEYWORDS_2024.contains(&name))
+    }
+    /// All keywords (strict and reserved) of this edition
+    pub fn keywords(self) -> Vec<&'static str> {
+        let mut keywords = KEYWORDS_2015.to_vec();
+        if self >= Edition::E2018 {
+            keywords.extend(KEYWORDS_2018);
+        }
+        if self >= Edition::E2024 {
+            keywords.extend(KEYWORDS_2024);
+        }
+        keywords
+    }
+}
+
+/// Create an identifier from a user-provided name (e.g. from `#[my_attr(rename = "...")]`),
+/// turning it into a raw identifier if it is a keyword in `edition`. Names that can't be raw
+/// identifiers (`self`, `super`, ...) produce an error.
+///
+/// `Edition::current()` is usually the right choice for `edition`, since that is the edition of
+/// the crate the identifier is emitted into. Raw identifiers are valid in all editions, so
+/// `Edition::LATEST` is also safe and keeps the output valid if the user switches editions.
+pub(crate) fn escaped(name: &str, span: Span, edition: Edition) -> Result<Ident> {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    if PATH_KEYWORDS.contains(&name) {
+        return Error::err(span, format!("`{}` cannot be used as a name here", name));
+    }
+    let ident = parse_ident(name, span)?;
+    // the NFC-normalized name, so the raw and non-raw identifiers are spelled the same
+    let name = ident.to_string();
+    if edition.is_keyword(&name) {
+        Ok(Ident::new_raw(&name, span))
+    } else {
+        Ok(ident)
+    }
+}
+
+/// Same as `escaped` with `Edition::current()`, using the value and span of a string literal
+#[cfg(not(feature = "no-syn"))]
+pub(crate) fn escaped_from_lit(lit: &syn::LitStr) -> Result<Ident> {
+    escaped(&lit.value(), lit.span(), Edition::current())
+}
+
+/// Turn an existing identifier into a raw identifier if it is a keyword in `edition`. This is
+/// needed when an identifier written in one edition (e.g. a `gen` field in a 2021 crate) ends up
+/// in code for a newer edition. Identifiers that can't be raw are returned unchanged.
+pub(crate) fn escape_ident(ident: &Ident, edition: Edition) -> Ident {
+    let name = ident.to_string();
+    if name.starts_with("r#") || PATH_KEYWORDS.contains(&name.as_str()) {
+        return ident.clone();
+    }
+    if edition.is_keyword(&name) {
+        Ident::new_raw(&name, ident.span())
+    } else {
+        ident.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escaped_name(name: &str, edition: Edition) -> String {
+        let ident = escaped(name, Span::call_site(), edition).ok().unwrap();
+        ident.to_string()
+    }
+
+    #[test]
+    fn keywords_become_raw() {
+        assert_eq!(escaped_name("match", Edition::E2015), "r#match");
+        assert_eq!(escaped_name("r#async", Edition::E2018), "r#async");
+        assert_eq!(escaped_name("async", Edition::E2015), "async");
+        assert_eq!(escaped_name("gen", Edition::E2024), "r#gen");
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn names_are_normalized() {
+        // `e` followed by a combining acute accent
+        assert_eq!(
+            escaped_name("caf\u{65}\u{301}", Edition::LATEST),
+            "caf\u{e9}"
+        );
+    }
+}
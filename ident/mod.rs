@@ -0,0 +1,289 @@
+#![allow(dead_code)]
+
+use super::{Error, IdePolicy, Result};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::IdentFragment;
+use std::fmt::Write;
+
+mod cache;
+mod gensym;
+mod keywords;
+mod names;
+mod reserved;
+mod unicode;
+pub(crate) use cache::*;
+pub(crate) use gensym::*;
+pub(crate) use keywords::*;
+pub(crate) use names::*;
+pub(crate) use reserved::*;
+pub(crate) use unicode::*;
+
+/// Where the span of a generated identifier should come from
+#[derive(Clone, Copy)]
+pub(crate) enum SpanPolicy {
+    /// Use the span of the first part that has one (usually the first `Ident`), falling back to
+    /// `Span::call_site()`. This is what `format_ident!` does.
+    FirstSpanned,
+    /// Use the span of the part at the given index, falling back to `Span::call_site()` if that
+    /// part has no span (e.g. it is a string or number)
+    Part(usize),
+    /// Use `Span::call_site()`. The identifier can be referenced by user code. With
+    /// `IdePolicy::Friendly`, this is `FirstSpanned` instead.
+    CallSite,
+    /// Use `Span::mixed_site()`. The identifier is hygienic and cannot be referenced by user code.
+    MixedSite,
+    /// Use the given span
+    Explicit(Span),
+}
+
+impl SpanPolicy {
+    /// Resolve the policy to a concrete span for the given parts
+    pub fn resolve(self, parts: &[&dyn IdentFragment]) -> Span {
+        match self {
+            SpanPolicy::FirstSpanned => parts.iter().find_map(|part| part.span()),
+            SpanPolicy::Part(index) => parts.get(index).and_then(|part| part.span()),
+            SpanPolicy::CallSite if IdePolicy::current() == IdePolicy::Friendly => {
+                parts.iter().find_map(|part| part.span())
+            }
+            SpanPolicy::CallSite => None,
+            SpanPolicy::MixedSite => Some(Span::mixed_site()),
+            SpanPolicy::Explicit(span) => Some(span),
+        }
+        .unwrap_or_else(Span::call_site)
+    }
+}
+
+/// Join idents, strings and numbers into a new identifier, with the span chosen by `policy`.
+///
+/// Raw identifiers are joined without their `r#` prefix, like `format_ident!` does.
+/// ```ignore
+/// let getter = ident::concat(&[&"get_", &field_name], SpanPolicy::Part(1))?;
+/// let tuple_field = ident::concat(&[&"field_", &index], SpanPolicy::MixedSite)?;
+/// ```
+pub(crate) fn concat(parts: &[&dyn IdentFragment], policy: SpanPolicy) -> Result<Ident> {
+    let span = policy.resolve(parts);
+    let mut name = String::new();
+    for part in parts {
+        // IdentFragment::fmt only fails if the underlying formatter fails, which String doesn't
+        write!(name, "{}", Fragment(*part)).expect("writing to a String cannot fail");
+    }
+    parse_ident(&name, span)
+}
+
+/// Generate the identifiers `{prefix}0` to `{prefix}{count - 1}` together with the matching tuple
+/// indices `0` to `count - 1`, all with the given span. This is the usual setup for destructuring
+/// or constructing tuple structs:
+/// ```ignore
+/// let (names, indices) = ident::numbered("field_", fields.len(), Span::mixed_site());
+/// quote! {
+///     let #name(#(#names),*) = self;
+///     #name(#(other.#indices + #names),*)
+/// }
+/// ```
+///
+/// Panics if `prefix` can't start an identifier.
+#[cfg(not(feature = "no-syn"))]
+pub(crate) fn numbered(prefix: &str, count: usize, span: Span) -> (Vec<Ident>, Vec<syn::Index>) {
+    (0..count)
+        .map(|i| {
+            let ident = concat(&[&prefix, &i], SpanPolicy::Explicit(span))
+                .unwrap_or_else(|_| panic!("`{}` is not a valid identifier prefix", prefix));
+            (
+                ident,
+                syn::Index {
+                    index: i as u32,
+                    span,
+                },
+            )
+        })
+        .unzip()
+}
+
+/// Create an identifier `{namespace}_{hash}` where `hash` is a hash of the content of `tokens`.
+///
+/// The hash only depends on the text of the tokens (not their spans), and is computed with a
+/// fixed algorithm, so the same input produces the same name in every build and on every
+/// machine. This is meant for symbols that have to be unique, like `#[no_mangle]` statics or
+/// `#[link_section]` registry entries, so `tokens` should contain everything that distinguishes
+/// one invocation from another (e.g. the path and name of the annotated item).
+///
+/// Panics if `namespace` can't start an identifier.
+pub(crate) fn mangle(namespace: &str, tokens: impl quote::ToTokens) -> Ident {
+    let mut hasher = super::tokens::StableHasher::new();
+    hasher.write_str(namespace);
+    hasher.write_tokens(tokens.into_token_stream());
+    let hash = format!("{:016x}", hasher.finish());
+    concat(&[&namespace, &"_", &hash], SpanPolicy::CallSite)
+        .unwrap_or_else(|_| panic!("`{}` is not a valid identifier prefix", namespace))
+}
+
+/// Turn a type into a snake_case identifier, for naming things that are generated per type:
+/// `Vec<Foo>` -> `vec_foo`, `&mut [u8; 4]` -> `ref_mut_array_u8_4`, `(A, B)` -> `tuple_a_b`.
+///
+/// Only the last segment of each path is used, so `std::vec::Vec` and `Vec` produce the same
+/// name, and lifetimes are skipped. If the result is a keyword, a `_` is appended. The span of the
+/// identifier is the span of the first token of the type.
+#[cfg(not(feature = "no-syn"))]
+pub(crate) fn type_to_fn_name(ty: &syn::Type) -> Ident {
+    let mut words = vec![];
+    type_words(ty, &mut words);
+    if words.is_empty() {
+        words.push("type".to_string());
+    }
+    let mut name = words.join("_");
+    if syn::parse_str::<Ident>(&name).is_err() {
+        name.push('_');
+    }
+    let span = quote::ToTokens::to_token_stream(ty)
+        .into_iter()
+        .next()
+        .map_or_else(Span::call_site, |token| token.span());
+    parse_ident(&name, span)
+        .unwrap_or_else(|_| panic!("generated name `{}` is not a valid identifier", name))
+}
+
+#[cfg(not(feature = "no-syn"))]
+fn type_words(ty: &syn::Type, words: &mut Vec<String>) {
+    use syn::Type;
+    match ty {
+        Type::Path(path) => {
+            if let Some(qself) = &path.qself {
+                type_words(&qself.ty, words);
+            }
+            path_words(&path.path, words);
+        }
+        Type::Reference(reference) => {
+            words.push("ref".to_string());
+            if reference.mutability.is_some() {
+                words.push("mut".to_string());
+            }
+            type_words(&reference.elem, words);
+        }
+        Type::Ptr(ptr) => {
+            words.push("ptr".to_string());
+            if ptr.mutability.is_some() {
+                words.push("mut".to_string());
+            }
+            type_words(&ptr.elem, words);
+        }
+        Type::Slice(slice) => {
+            words.push("slice".to_string());
+            type_words(&slice.elem, words);
+        }
+        Type::Array(array) => {
+            words.push("array".to_string());
+            type_words(&array.elem, words);
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(len),
+                ..
+            }) = &array.len
+            {
+                words.push(len.base10_digits().to_string());
+            }
+        }
+        Type::Tuple(tuple) if tuple.elems.is_empty() => words.push("unit".to_string()),
+        Type::Tuple(tuple) => {
+            words.push("tuple".to_string());
+            tuple.elems.iter().for_each(|elem| type_words(elem, words));
+        }
+        Type::Paren(paren) => type_words(&paren.elem, words),
+        Type::Group(group) => type_words(&group.elem, words),
+        Type::TraitObject(object) => {
+            words.push("dyn".to_string());
+            bound_words(object.bounds.iter(), words);
+        }
+        Type::ImplTrait(object) => {
+            words.push("impl".to_string());
+            bound_words(object.bounds.iter(), words);
+        }
+        Type::BareFn(function) => {
+            words.push("fn".to_string());
+            for arg in &function.inputs {
+                type_words(&arg.ty, words);
+            }
+            if let syn::ReturnType::Type(_, output) = &function.output {
+                type_words(output, words);
+            }
+        }
+        Type::Never(_) => words.push("never".to_string()),
+        Type::Infer(_) => words.push("infer".to_string()),
+        _ => words.push("type".to_string()),
+    }
+}
+
+#[cfg(not(feature = "no-syn"))]
+fn path_words(path: &syn::Path, words: &mut Vec<String>) {
+    let Some(last) = path.segments.last() else {
+        return;
+    };
+    let name = last.ident.to_string();
+    let name = super::Case::Snake.convert(name.strip_prefix("r#").unwrap_or(&name));
+    words.extend(
+        name.split('_')
+            .filter(|word| !word.is_empty())
+            .map(String::from),
+    );
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => {
+            for argument in &arguments.args {
+                match argument {
+                    syn::GenericArgument::Type(ty) => type_words(ty, words),
+                    syn::GenericArgument::AssocType(assoc) => type_words(&assoc.ty, words),
+                    syn::GenericArgument::Const(syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(value),
+                        ..
+                    })) => words.push(value.base10_digits().to_string()),
+                    _ => {}
+                }
+            }
+        }
+        syn::PathArguments::Parenthesized(arguments) => {
+            arguments.inputs.iter().for_each(|ty| type_words(ty, words));
+            if let syn::ReturnType::Type(_, output) = &arguments.output {
+                type_words(output, words);
+            }
+        }
+        syn::PathArguments::None => {}
+    }
+}
+
+#[cfg(not(feature = "no-syn"))]
+fn bound_words<'a>(bounds: impl Iterator<Item = &'a syn::TypeParamBound>, words: &mut Vec<String>) {
+    for bound in bounds {
+        if let syn::TypeParamBound::Trait(bound) = bound {
+            path_words(&bound.path, words);
+        }
+    }
+}
+
+/// Create an identifier from a string, returning an error instead of panicking if the string is
+/// not a valid identifier. Keywords are allowed. The name is normalized to NFC first, see `nfc`.
+pub(crate) fn parse_ident(name: &str, span: Span) -> Result<Ident> {
+    let name = nfc(name);
+    if !is_valid_ident(&name) {
+        return Error::err(span, format!("`{}` is not a valid identifier", name));
+    }
+    Ok(Ident::new(&name, span))
+}
+
+/// Check if a string is a valid (non-raw) identifier. Keywords are considered valid.
+pub(crate) fn is_valid_ident(name: &str) -> bool {
+    // let the lexer decide instead of approximating the XID rules
+    let Ok(tokens) = name.parse::<TokenStream>() else {
+        return false;
+    };
+    let mut iter = tokens.into_iter();
+    match (iter.next(), iter.next()) {
+        (Some(TokenTree::Ident(ident)), None) => ident == name && !name.starts_with("r#"),
+        _ => false,
+    }
+}
+
+/// Adapter to use `IdentFragment::fmt` through `Display`
+struct Fragment<'a>(&'a dyn IdentFragment);
+
+impl std::fmt::Display for Fragment<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        IdentFragment::fmt(self.0, f)
+    }
+}
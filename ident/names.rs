@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+
+use super::super::Case;
+use super::{concat, is_valid_ident, SpanPolicy};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+/// Naming conventions for helper items generated for a type, so that all of them follow the same
+/// pattern and are easy to grep for in expanded code.
+///
+/// All names start with a prefix (`__` by default), which marks them as internal and keeps them
+/// out of the way of user-defined names. Items with these names should be marked with
+/// `GeneratedNames::hidden_attr()` if they are visible to users.
+/// ```ignore
+/// let names = GeneratedNames::new();
+/// let builder = names.builder_of(&input.ident); // __FooBuilder
+/// let module = names.module_of(&input.ident); // __foo_impl
+/// ```
+#[derive(Clone)]
+pub(crate) struct GeneratedNames {
+    prefix: String,
+    span: SpanPolicy,
+}
+
+impl GeneratedNames {
+    /// The default naming conventions, with a `__` prefix and the span of the type
+    pub fn new() -> Self {
+        Self {
+            prefix: "__".to_string(),
+            span: SpanPolicy::FirstSpanned,
+        }
+    }
+    /// Use a custom prefix instead of `__`, e.g. to include the name of the macro.
+    ///
+    /// Panics if the prefix can't start an identifier.
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        assert!(
+            is_valid_ident(&format!("{}a", prefix)),
+            "`{}` is not a valid identifier prefix",
+            prefix
+        );
+        Self {
+            prefix,
+            span: SpanPolicy::FirstSpanned,
+        }
+    }
+    /// Set where the spans of the generated names come from. The default is the span of the type,
+    /// which `SpanPolicy::FirstSpanned` and `SpanPolicy::Part` refer to.
+    pub fn span_policy(mut self, span: SpanPolicy) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// The prefix used for all names
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The name of a builder type for `ty`: `Foo` -> `__FooBuilder`
+    pub fn builder_of(&self, ty: &Ident) -> Ident {
+        self.named(ty, Case::Pascal, "Builder")
+    }
+    /// The name of a module containing helper items for `ty`: `Foo` -> `__foo_impl`
+    pub fn module_of(&self, ty: &Ident) -> Ident {
+        self.named(ty, Case::Snake, "_impl")
+    }
+    /// The name of a constant belonging to `ty`: `Foo` -> `__FOO`
+    pub fn const_of(&self, ty: &Ident) -> Ident {
+        self.named(ty, Case::ScreamingSnake, "")
+    }
+    /// The name of a static belonging to `ty`: `Foo` -> `__FOO_STATIC`
+    pub fn static_of(&self, ty: &Ident) -> Ident {
+        self.named(ty, Case::ScreamingSnake, "_STATIC")
+    }
+    /// The name of a helper function for `ty` with the given purpose:
+    /// `(Foo, "validate")` -> `__foo_validate`
+    pub fn fn_of(&self, ty: &Ident, purpose: &str) -> Ident {
+        self.named(
+            ty,
+            Case::Snake,
+            &format!("_{}", Case::Snake.convert(purpose)),
+        )
+    }
+    /// The name of a helper type for `ty` with the given purpose:
+    /// `(Foo, "visitor")` -> `__FooVisitor`
+    pub fn type_of(&self, ty: &Ident, purpose: &str) -> Ident {
+        self.named(ty, Case::Pascal, &Case::Pascal.convert(purpose))
+    }
+
+    /// Build a name from the prefix, `ty` converted to `case` and a suffix
+    pub fn named(&self, ty: &Ident, case: Case, suffix: &str) -> Ident {
+        let name = ty.to_string();
+        let name = case.convert(name.strip_prefix("r#").unwrap_or(&name));
+        let span = self.span.resolve(&[ty]);
+        concat(&[&self.prefix, &name, &suffix], SpanPolicy::Explicit(span))
+            .unwrap_or_else(|_| panic!("generated name for `{}` is not a valid identifier", ty))
+    }
+
+    /// `#[doc(hidden)]`, for items named by this type that are visible to users
+    pub fn hidden_attr() -> TokenStream {
+        quote! { #[doc(hidden)] }
+    }
+}
+
+impl Default for GeneratedNames {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,259 @@
+#![allow(dead_code)]
+
+use super::super::{Error, Warning};
+use proc_macro2::Ident;
+
+/// Methods of well-known traits that generated inherent methods should not be named like
+pub(crate) const WELL_KNOWN_TRAIT_METHODS: &[(&str, &[&str])] = &[
+    ("Clone", &["clone", "clone_from"]),
+    ("Default", &["default"]),
+    ("Deref", &["deref"]),
+    ("DerefMut", &["deref_mut"]),
+    ("Drop", &["drop"]),
+    ("PartialEq", &["eq", "ne"]),
+    ("PartialOrd", &["partial_cmp", "lt", "le", "gt", "ge"]),
+    ("Ord", &["cmp", "max", "min", "clamp"]),
+    ("Hash", &["hash"]),
+    ("Debug", &["fmt"]),
+    ("ToString", &["to_string"]),
+    ("ToOwned", &["to_owned", "clone_into"]),
+    ("Borrow", &["borrow"]),
+    ("BorrowMut", &["borrow_mut"]),
+    ("AsRef", &["as_ref"]),
+    ("AsMut", &["as_mut"]),
+    ("From", &["from"]),
+    ("Into", &["into"]),
+    ("TryFrom", &["try_from"]),
+    ("TryInto", &["try_into"]),
+    ("FromStr", &["from_str"]),
+    ("IntoIterator", &["into_iter"]),
+    ("Any", &["type_id"]),
+    ("Error", &["source", "description", "cause"]),
+    ("Index", &["index"]),
+    ("IndexMut", &["index_mut"]),
+    ("Add", &["add"]),
+    ("Sub", &["sub"]),
+    ("Mul", &["mul"]),
+    ("Div", &["div"]),
+    ("Rem", &["rem"]),
+    ("Neg", &["neg"]),
+    ("Not", &["not"]),
+    (
+        "Iterator",
+        &[
+            "next",
+            "size_hint",
+            "count",
+            "last",
+            "nth",
+            "step_by",
+            "chain",
+            "zip",
+            "map",
+            "for_each",
+            "filter",
+            "filter_map",
+            "enumerate",
+            "peekable",
+            "skip_while",
+            "take_while",
+            "skip",
+            "take",
+            "scan",
+            "flat_map",
+            "flatten",
+            "fuse",
+            "inspect",
+            "by_ref",
+            "collect",
+            "partition",
+            "fold",
+            "reduce",
+            "all",
+            "any",
+            "find",
+            "find_map",
+            "position",
+            "max_by_key",
+            "max_by",
+            "min_by_key",
+            "min_by",
+            "rev",
+            "unzip",
+            "copied",
+            "cloned",
+            "cycle",
+            "sum",
+            "product",
+        ],
+    ),
+];
+
+/// What a generated method name collides with
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum CollisionKind {
+    /// A method of the named trait
+    TraitMethod(&'static str),
+    /// A method that the type already declares
+    Declared(Ident),
+}
+
+/// A generated method name that collides with an existing method
+#[derive(Clone, Debug)]
+pub(crate) struct MethodCollision {
+    /// The name that was checked
+    pub name: Ident,
+    /// What it collides with
+    pub kind: CollisionKind,
+}
+
+impl MethodCollision {
+    /// The message describing the collision
+    pub fn message(&self) -> String {
+        match &self.kind {
+            CollisionKind::TraitMethod(name) => format!(
+                "generated method `{}` has the same name as `{}::{}`, which makes calls to it ambiguous and hides the trait method",
+                self.name, name, self.name
+            ),
+            CollisionKind::Declared(_) => format!(
+                "generated method `{}` has the same name as a method that the type already declares",
+                self.name
+            ),
+        }
+    }
+    /// Turn the collision into a warning at the checked name
+    pub fn to_warning(&self) -> Warning {
+        Warning::new(self.name.span(), self.message())
+    }
+    /// Turn the collision into an error at the checked name, which is usually more appropriate
+    /// for `CollisionKind::Declared` since that code would not compile anyway.
+    pub fn to_error(&self) -> Error {
+        let mut error = Error::builder();
+        error.with(self.name.span(), self.message());
+        if let CollisionKind::Declared(existing) = &self.kind {
+            error.with(existing.span(), "the existing method is declared here");
+        }
+        error.build()
+    }
+}
+
+/// Checks names of generated inherent methods against the methods of well-known traits and the
+/// methods the type already declares.
+/// ```ignore
+/// let checker = MethodNameChecker::new().with_impl_block(&user_impl);
+/// for field in fields {
+///     if let Some(collision) = checker.check(&getter_name(field)) {
+///         output.extend(collision.to_warning().into_token_stream());
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub(crate) struct MethodNameChecker {
+    traits: Vec<(&'static str, &'static [&'static str])>,
+    declared: Vec<Ident>,
+}
+
+impl MethodNameChecker {
+    /// A checker for the methods in `WELL_KNOWN_TRAIT_METHODS`
+    pub fn new() -> Self {
+        Self {
+            traits: WELL_KNOWN_TRAIT_METHODS.to_vec(),
+            declared: vec![],
+        }
+    }
+    /// A checker that doesn't check against any trait methods
+    pub fn empty() -> Self {
+        Self {
+            traits: vec![],
+            declared: vec![],
+        }
+    }
+
+    /// Also check against the methods of another trait
+    pub fn with_trait(mut self, name: &'static str, methods: &'static [&'static str]) -> Self {
+        self.traits.push((name, methods));
+        self
+    }
+    /// Also check against methods that the type declares
+    pub fn with_declared(mut self, methods: impl IntoIterator<Item = Ident>) -> Self {
+        self.declared.extend(methods);
+        self
+    }
+    /// Also check against the methods declared in an impl block of the type
+    #[cfg(not(feature = "no-syn"))]
+    pub fn with_impl_block(self, block: &syn::ItemImpl) -> Self {
+        let methods = block.items.iter().filter_map(|item| match item {
+            syn::ImplItem::Fn(method) => Some(method.sig.ident.clone()),
+            _ => None,
+        });
+        self.with_declared(methods)
+    }
+
+    /// Check a single name. Raw and non-raw names are the same, so `r#match` collides with `match`.
+    pub fn check(&self, name: &Ident) -> Option<MethodCollision> {
+        let unraw = |ident: &Ident| {
+            let name = ident.to_string();
+            name.strip_prefix("r#").unwrap_or(&name).to_string()
+        };
+        let checked = unraw(name);
+        let kind = if let Some(existing) = self.declared.iter().find(|m| unraw(m) == checked) {
+            CollisionKind::Declared(existing.clone())
+        } else {
+            let (trait_name, _) = self
+                .traits
+                .iter()
+                .find(|(_, methods)| methods.contains(&checked.as_str()))?;
+            CollisionKind::TraitMethod(trait_name)
+        };
+        Some(MethodCollision {
+            name: name.clone(),
+            kind,
+        })
+    }
+    /// Check all the names, returning the collisions
+    pub fn check_all<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a Ident>,
+    ) -> Vec<MethodCollision> {
+        names
+            .into_iter()
+            .filter_map(|name| self.check(name))
+            .collect()
+    }
+    /// Check all the names, returning a warning for each collision
+    pub fn warnings<'a>(&self, names: impl IntoIterator<Item = &'a Ident>) -> Vec<Warning> {
+        self.check_all(names)
+            .iter()
+            .map(MethodCollision::to_warning)
+            .collect()
+    }
+}
+
+impl Default for MethodNameChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    #[test]
+    fn raw_declared_methods_collide() {
+        let ident = |name: &str| match name.strip_prefix("r#") {
+            Some(name) => Ident::new_raw(name, Span::call_site()),
+            None => Ident::new(name, Span::call_site()),
+        };
+        let checker = MethodNameChecker::empty().with_declared([ident("r#match"), ident("r#foo")]);
+        for (name, existing) in [("foo", "r#foo"), ("r#foo", "r#foo"), ("r#match", "r#match")] {
+            let collision = checker.check(&ident(name)).unwrap();
+            assert_eq!(collision.kind, CollisionKind::Declared(ident(existing)));
+        }
+        assert!(checker.check(&ident("bar")).is_none());
+        let checker = MethodNameChecker::empty().with_declared([ident("foo")]);
+        assert!(checker.check(&ident("r#foo")).is_some());
+        assert!(MethodNameChecker::new().check(&ident("r#into")).is_some());
+    }
+}
@@ -0,0 +1,163 @@
+#![allow(dead_code)]
+
+use super::super::Warning;
+use proc_macro2::Span;
+use std::{borrow::Cow, fmt::Display};
+
+/// Normalize a name to Unicode Normalization Form C, which is the form rustc uses for all
+/// identifiers. Without this, a name typed as a decomposed sequence (`e` + combining accent)
+/// would not match the identifier written in user code.
+///
+/// Requires the `unicode-normalization` feature for non-ASCII names. Without it, names are
+/// returned unchanged.
+pub(crate) fn nfc(name: &str) -> Cow<'_, str> {
+    #[cfg(feature = "unicode-normalization")]
+    if !name.is_ascii() {
+        use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+        if is_nfc_quick(name.chars()) != IsNormalized::Yes {
+            return Cow::Owned(name.nfc().collect());
+        }
+    }
+    Cow::Borrowed(name)
+}
+
+/// A character in a name that looks like a different ASCII character
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Confusable {
+    /// Byte index of the character in the name
+    pub index: usize,
+    /// The character that was found
+    pub found: char,
+    /// The ASCII character it resembles
+    pub looks_like: char,
+}
+
+impl Confusable {
+    /// Turn this into a warning pointing at `span`, for the name that contained the character
+    pub fn to_warning(self, span: Span, name: &str) -> Warning {
+        Warning::new(span, format!("in `{}`: {}", name, self))
+    }
+}
+
+impl Display for Confusable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the character '{}' (U+{:04X}) looks like '{}' but is a different character",
+            self.found, self.found as u32, self.looks_like
+        )
+    }
+}
+
+/// Find all characters in a name that look like ASCII letters or digits but aren't, like the
+/// Cyrillic `а` in `dаta`. A name like that produces an identifier that doesn't match what the
+/// user (and everyone reading their code) thinks it is.
+///
+/// Covers the common Cyrillic, Greek and Latin lookalikes, plus fullwidth forms. With the
+/// `unicode-normalization` feature, anything that is compatibility-equivalent (NFKC) to an
+/// ASCII character is reported as well.
+/// ```ignore
+/// let name = rename.value();
+/// for confusable in ident::detect_confusables(&name) {
+///     output.extend(confusable.to_warning(rename.span(), &name).into_token_stream());
+/// }
+/// ```
+pub(crate) fn detect_confusables(name: &str) -> Vec<Confusable> {
+    name.char_indices()
+        .filter(|(_, c)| !c.is_ascii())
+        .filter_map(|(index, found)| {
+            let looks_like = ascii_lookalike(found)?;
+            Some(Confusable {
+                index,
+                found,
+                looks_like,
+            })
+        })
+        .collect()
+}
+
+/// The ASCII character that `c` is easily confused with, if any
+fn ascii_lookalike(c: char) -> Option<char> {
+    if let Some((_, ascii)) = CONFUSABLES.iter().find(|(confusable, _)| *confusable == c) {
+        return Some(*ascii);
+    }
+    // fullwidth forms
+    let fullwidth = match c {
+        '\u{FF10}'..='\u{FF19}' => Some((c as u32 - 0xFF10 + '0' as u32) as u8),
+        '\u{FF21}'..='\u{FF3A}' => Some((c as u32 - 0xFF21 + 'A' as u32) as u8),
+        '\u{FF41}'..='\u{FF5A}' => Some((c as u32 - 0xFF41 + 'a' as u32) as u8),
+        '\u{FF3F}' => Some(b'_'),
+        _ => None,
+    };
+    if let Some(ascii) = fullwidth {
+        return Some(ascii as char);
+    }
+    #[cfg(feature = "unicode-normalization")]
+    {
+        use unicode_normalization::UnicodeNormalization;
+        let mut normalized = std::iter::once(c).nfkc();
+        if let (Some(ascii), None) = (normalized.next(), normalized.next()) {
+            if ascii.is_ascii_alphanumeric() || ascii == '_' {
+                return Some(ascii);
+            }
+        }
+    }
+    None
+}
+
+/// Characters that are valid in identifiers and look (nearly) identical to an ASCII letter
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('\u{0430}', 'a'),
+    ('\u{0435}', 'e'),
+    ('\u{043E}', 'o'),
+    ('\u{0440}', 'p'),
+    ('\u{0441}', 'c'),
+    ('\u{0443}', 'y'),
+    ('\u{0445}', 'x'),
+    ('\u{0455}', 's'),
+    ('\u{0456}', 'i'),
+    ('\u{0458}', 'j'),
+    ('\u{04BB}', 'h'),
+    ('\u{0501}', 'd'),
+    ('\u{051B}', 'q'),
+    ('\u{051D}', 'w'),
+    ('\u{0405}', 'S'),
+    ('\u{0406}', 'I'),
+    ('\u{0408}', 'J'),
+    ('\u{0410}', 'A'),
+    ('\u{0412}', 'B'),
+    ('\u{0415}', 'E'),
+    ('\u{041A}', 'K'),
+    ('\u{041C}', 'M'),
+    ('\u{041D}', 'H'),
+    ('\u{041E}', 'O'),
+    ('\u{0420}', 'P'),
+    ('\u{0421}', 'C'),
+    ('\u{0422}', 'T'),
+    ('\u{0425}', 'X'),
+    // Greek
+    ('\u{03B1}', 'a'),
+    ('\u{03B9}', 'i'),
+    ('\u{03BD}', 'v'),
+    ('\u{03BF}', 'o'),
+    ('\u{03C1}', 'p'),
+    ('\u{0391}', 'A'),
+    ('\u{0392}', 'B'),
+    ('\u{0395}', 'E'),
+    ('\u{0396}', 'Z'),
+    ('\u{0397}', 'H'),
+    ('\u{0399}', 'I'),
+    ('\u{039A}', 'K'),
+    ('\u{039C}', 'M'),
+    ('\u{039D}', 'N'),
+    ('\u{039F}', 'O'),
+    ('\u{03A1}', 'P'),
+    ('\u{03A4}', 'T'),
+    ('\u{03A5}', 'Y'),
+    ('\u{03A7}', 'X'),
+    // Latin
+    ('\u{0131}', 'i'),
+    ('\u{0261}', 'g'),
+    ('\u{01C0}', 'l'),
+];
@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+
+use super::{Error, Result};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote_spanned;
+use std::path::{Path, PathBuf};
+
+/// A file that a macro loads, like a schema or template, with a path relative to the file that
+/// the macro is invoked in, the same as `include_str!`. If the compiler doesn't say which file
+/// that is (outside of a macro, or with the `no-proc-macro` feature), the path is relative to
+/// `CARGO_MANIFEST_DIR` instead.
+///
+/// Errors point at the span the file was resolved with, usually the string literal with the
+/// path. The compiler doesn't know that the macro depends on the file, so the output should
+/// contain `include_bytes`/`include_str` or `tracking_item`, which make changes to the file
+/// recompile the crate.
+/// ```ignore
+/// // schema!("schema.json")
+/// let file = IncludeFile::from_lit(&path);
+/// let schema = parse_schema(&file.read_to_string()?)?;
+/// let tracking = file.tracking_item();
+/// quote!(#tracking #(#generated)*)
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct IncludeFile {
+    path: PathBuf,
+    span: Span,
+}
+
+impl IncludeFile {
+    /// Resolve `path` for a macro invoked at `span`. Absolute paths are used as-is.
+    pub fn resolve(path: &str, span: Span) -> IncludeFile {
+        let path = Path::new(path);
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            base_dir(span).join(path)
+        };
+        IncludeFile { path, span }
+    }
+    /// Resolve the path in a string literal, with errors at the literal
+    #[cfg(not(feature = "no-syn"))]
+    pub fn from_lit(lit: &syn::LitStr) -> IncludeFile {
+        IncludeFile::resolve(&lit.value(), lit.span())
+    }
+
+    /// The resolved path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// The span for errors about the file
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Read the file, with an error at the span if that fails
+    pub fn read(&self) -> Result<Vec<u8>> {
+        std::fs::read(&self.path).map_err(|e| self.io_error(e))
+    }
+    /// Read the file as UTF-8, see `read`
+    pub fn read_to_string(&self) -> Result<String> {
+        std::fs::read_to_string(&self.path).map_err(|e| self.io_error(e))
+    }
+    fn io_error(&self, error: std::io::Error) -> Error {
+        Error::new(
+            self.span,
+            format!("couldn't read `{}`: {}", self.path.display(), error),
+        )
+    }
+
+    /// `::core::include_bytes!("/absolute/path")`, which embeds the file and makes the compiler
+    /// track it
+    pub fn include_bytes(&self) -> TokenStream {
+        let path = self.absolute_path_lit();
+        quote_spanned!(self.span=> ::core::include_bytes!(#path))
+    }
+    /// `::core::include_str!("/absolute/path")`, see `include_bytes`
+    pub fn include_str(&self) -> TokenStream {
+        let path = self.absolute_path_lit();
+        quote_spanned!(self.span=> ::core::include_str!(#path))
+    }
+    /// `const _: &[u8] = include_bytes!(...);`, an item that only makes the compiler track the
+    /// file, for macros that use the contents at expansion time instead of embedding them
+    pub fn tracking_item(&self) -> TokenStream {
+        let include = self.include_bytes();
+        quote_spanned!(self.span=> const _: &[u8] = #include;)
+    }
+
+    /// `include_bytes!` resolves relative paths against the invoking file, which might not be
+    /// the base that was used here. Non-UTF-8 paths are embedded lossily.
+    fn absolute_path_lit(&self) -> Literal {
+        let path = match std::env::current_dir() {
+            Ok(dir) if self.path.is_relative() => dir.join(&self.path),
+            _ => self.path.clone(),
+        };
+        Literal::string(&path.to_string_lossy())
+    }
+}
+
+/// The directory of the file that the macro is invoked in, or `CARGO_MANIFEST_DIR`
+fn base_dir(span: Span) -> PathBuf {
+    #[cfg(not(feature = "no-proc-macro"))]
+    if proc_macro::is_available() {
+        let file = span.unwrap().local_file();
+        if let Some(dir) = file.as_deref().and_then(Path::parent) {
+            return dir.to_path_buf();
+        }
+    }
+    let _ = span;
+    std::env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from)
+}
@@ -0,0 +1,135 @@
+#![allow(dead_code)]
+
+use std::fmt::Display;
+
+/// The error of `ToProcMacro::try_to_proc_macro` outside of a running proc macro, where the
+/// compiler's `proc_macro` types don't exist (e.g. in unit tests or build scripts)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct NotInMacro;
+
+impl Display for NotInMacro {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`proc_macro` types can only be used while a proc macro is running, not in unit \
+             tests or build scripts; use `proc_macro2` there"
+        )
+    }
+}
+
+impl std::error::Error for NotInMacro {}
+
+/// Conversion of `proc_macro2` types into the compiler's `proc_macro` types, keeping the spans.
+///
+/// `proc_macro2` only has `From` impls for `TokenStream`, so the other types usually go through
+/// a stream and back. These conversions only work while a macro is running: `try_to_proc_macro`
+/// returns `NotInMacro` otherwise, and `to_proc_macro` panics with the same message instead of
+/// `proc_macro`'s "procedural macro API is used outside of a procedural macro".
+/// ```ignore
+/// let span = name.span().to_proc_macro();
+/// span.warning("deprecated").emit(); // nightly-only diagnostics API
+/// ```
+pub(crate) trait ToProcMacro {
+    /// The `proc_macro` type
+    type Output;
+    /// Convert the value, or return `NotInMacro` outside of a running proc macro
+    fn try_to_proc_macro(&self) -> Result<Self::Output, NotInMacro>;
+    /// Convert the value. Panics outside of a running proc macro.
+    #[track_caller]
+    fn to_proc_macro(&self) -> Self::Output {
+        match self.try_to_proc_macro() {
+            Ok(value) => value,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+/// Conversion of the compiler's `proc_macro` types into `proc_macro2` types, keeping the spans.
+/// Always works, since `proc_macro` values only exist while a macro is running.
+pub(crate) trait ToProcMacro2 {
+    /// The `proc_macro2` type
+    type Output;
+    /// Convert the value
+    fn to_proc_macro2(&self) -> Self::Output;
+}
+
+fn check_available() -> Result<(), NotInMacro> {
+    if proc_macro::is_available() {
+        Ok(())
+    } else {
+        Err(NotInMacro)
+    }
+}
+
+impl ToProcMacro for proc_macro2::TokenStream {
+    type Output = proc_macro::TokenStream;
+    fn try_to_proc_macro(&self) -> Result<Self::Output, NotInMacro> {
+        check_available()?;
+        Ok(self.clone().into())
+    }
+}
+
+impl ToProcMacro for proc_macro2::TokenTree {
+    type Output = proc_macro::TokenTree;
+    fn try_to_proc_macro(&self) -> Result<Self::Output, NotInMacro> {
+        let stream = proc_macro2::TokenStream::from(self.clone()).try_to_proc_macro()?;
+        let tree = stream.into_iter().next();
+        Ok(tree.expect("a stream of one token tree converts to one token tree"))
+    }
+}
+
+impl ToProcMacro for proc_macro2::Span {
+    type Output = proc_macro::Span;
+    fn try_to_proc_macro(&self) -> Result<Self::Output, NotInMacro> {
+        check_available()?;
+        Ok(self.unwrap())
+    }
+}
+
+/// Both conversions for the token tree types, which go through `TokenTree`
+macro_rules! tree_conversions {
+    ($($name:ident),*) => {$(
+        impl ToProcMacro for proc_macro2::$name {
+            type Output = proc_macro::$name;
+            fn try_to_proc_macro(&self) -> Result<Self::Output, NotInMacro> {
+                match proc_macro2::TokenTree::$name(self.clone()).try_to_proc_macro()? {
+                    proc_macro::TokenTree::$name(value) => Ok(value),
+                    _ => unreachable!("the token tree keeps its kind"),
+                }
+            }
+        }
+        impl ToProcMacro2 for proc_macro::$name {
+            type Output = proc_macro2::$name;
+            fn to_proc_macro2(&self) -> Self::Output {
+                match proc_macro::TokenTree::$name(self.clone()).to_proc_macro2() {
+                    proc_macro2::TokenTree::$name(value) => value,
+                    _ => unreachable!("the token tree keeps its kind"),
+                }
+            }
+        }
+    )*};
+}
+tree_conversions!(Group, Ident, Punct, Literal);
+
+impl ToProcMacro2 for proc_macro::TokenStream {
+    type Output = proc_macro2::TokenStream;
+    fn to_proc_macro2(&self) -> Self::Output {
+        self.clone().into()
+    }
+}
+
+impl ToProcMacro2 for proc_macro::TokenTree {
+    type Output = proc_macro2::TokenTree;
+    fn to_proc_macro2(&self) -> Self::Output {
+        let stream = proc_macro::TokenStream::from(self.clone()).to_proc_macro2();
+        let tree = stream.into_iter().next();
+        tree.expect("a stream of one token tree converts to one token tree")
+    }
+}
+
+impl ToProcMacro2 for proc_macro::Span {
+    type Output = proc_macro2::Span;
+    fn to_proc_macro2(&self) -> Self::Output {
+        (*self).into()
+    }
+}
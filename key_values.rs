@@ -0,0 +1,210 @@
+#![allow(dead_code)]
+
+use super::lit::{self, LitValue, ParsedLit};
+use super::tokens::Cursor;
+use super::{Error, Result};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+
+/// The value of one key in `KeyValues`
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum KeyValue {
+    /// Only the key: `skip`
+    Flag,
+    /// `key = ...`, with all tokens up to the next top-level `,`
+    Value(#[cfg_attr(feature = "serde", serde(with = "super::tokens::serde_tokens"))] TokenStream),
+    /// `key(...)`, with the contents of the parentheses
+    List(#[cfg_attr(feature = "serde", serde(with = "super::tokens::serde_tokens"))] TokenStream),
+}
+
+/// Comma-separated `key`, `key = value` and `key(...)` arguments of an attribute or a
+/// function-like macro, parsed from plain tokens. The counterpart of `AttrSchema` that doesn't
+/// need `syn`, so it also works with the `no-syn` feature. Values are checked when they are
+/// read, so the getters return errors at the value if it has the wrong shape.
+/// ```ignore
+/// // #[my_attr(name = "foo", skip, limit = 10)]
+/// let args = KeyValues::parse(args, &["name", "skip", "limit"])?;
+/// let name = args.str("name")?.unwrap_or_default();
+/// let skip = args.flag("skip")?;
+/// let limit = args.lit("limit")?.and_then(|lit| lit.as_i128());
+/// ```
+/// With the `serde` feature, the arguments can be serialized, e.g. to cache them under
+/// `OUT_DIR`. Spans are not serialized, so errors about deserialized values point at the call
+/// site.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<SerdeEntry>", into = "Vec<SerdeEntry>")
+)]
+pub(crate) struct KeyValues {
+    entries: Vec<(Ident, KeyValue)>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeEntry(
+    #[serde(with = "super::tokens::serde_ident")] Ident,
+    KeyValue,
+);
+
+#[cfg(feature = "serde")]
+impl From<Vec<SerdeEntry>> for KeyValues {
+    fn from(entries: Vec<SerdeEntry>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|SerdeEntry(key, value)| (key, value));
+        Self {
+            entries: entries.collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<KeyValues> for Vec<SerdeEntry> {
+    fn from(values: KeyValues) -> Self {
+        let entries = values.entries.into_iter();
+        entries.map(|(key, value)| SerdeEntry(key, value)).collect()
+    }
+}
+
+impl KeyValues {
+    /// Parse the arguments, with an error for every key that isn't in `known` or is given more
+    /// than once
+    pub fn parse(args: TokenStream, known: &[&str]) -> Result<Self> {
+        let mut cursor = Cursor::new(args);
+        let mut entries: Vec<(Ident, KeyValue)> = vec![];
+        let mut errors = Error::builder();
+        while !cursor.is_empty() {
+            let key = cursor.expect_any_ident()?;
+            let value = if cursor.eat_punct("=").is_some() {
+                let mut value = TokenStream::new();
+                while cursor.peek().is_some() && !cursor.peek_punct(",") {
+                    value.extend(cursor.advance());
+                }
+                if value.is_empty() {
+                    return Err(cursor.error(format!("expected a value for `{}`", key)));
+                }
+                KeyValue::Value(value)
+            } else if matches!(cursor.peek(), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis)
+            {
+                KeyValue::List(cursor.expect_group(Delimiter::Parenthesis)?.0.rest())
+            } else {
+                KeyValue::Flag
+            };
+            if !cursor.is_empty() {
+                cursor.expect_punct(",")?;
+            }
+
+            let name = unraw(&key);
+            if !known.contains(&name.as_str()) {
+                let expected: Vec<String> = known.iter().map(|key| format!("`{}`", key)).collect();
+                errors.with(
+                    key.span(),
+                    format!(
+                        "unknown key `{}`, expected one of: {}",
+                        name,
+                        expected.join(", ")
+                    ),
+                );
+            } else if let Some((previous, _)) = entries.iter().find(|(k, _)| unraw(k) == name) {
+                errors
+                    .with(key.span(), format!("duplicate key `{}`", name))
+                    .with(previous.span(), "first given here");
+            } else {
+                entries.push((key, value));
+            }
+        }
+        errors.ok_or_build()?;
+        Ok(Self { entries })
+    }
+
+    /// The keys in the order they were given
+    pub fn keys(&self) -> impl Iterator<Item = &Ident> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+    /// The value of `key`, if it was given
+    pub fn get(&self, key: &str) -> Option<&KeyValue> {
+        self.entry(key).map(|(_, value)| value)
+    }
+    /// The span of `key`, if it was given
+    pub fn span(&self, key: &str) -> Option<Span> {
+        self.entry(key).map(|(ident, _)| ident.span())
+    }
+
+    /// Whether the flag `key` was given. Errors if it was given with a value.
+    pub fn flag(&self, key: &str) -> Result<bool> {
+        match self.entry(key) {
+            None => Ok(false),
+            Some((_, KeyValue::Flag)) => Ok(true),
+            Some((ident, _)) => Error::err(ident.span(), format!("`{}` doesn't take a value", key)),
+        }
+    }
+    /// The tokens of `key = ...`. Errors if the key was given without a value.
+    pub fn tokens(&self, key: &str) -> Result<Option<&TokenStream>> {
+        match self.entry(key) {
+            None => Ok(None),
+            Some((_, KeyValue::Value(value))) => Ok(Some(value)),
+            Some((ident, _)) => {
+                Error::err(ident.span(), format!("expected `{} = ...`", unraw(ident)))
+            }
+        }
+    }
+    /// The literal of `key = <literal>`
+    pub fn lit(&self, key: &str) -> Result<Option<ParsedLit>> {
+        let Some(value) = self.tokens(key)? else {
+            return Ok(None);
+        };
+        match single(value) {
+            Some(TokenTree::Literal(literal)) => lit::parse(&literal).map(Some),
+            _ => Error::err_spanned(value, "expected a literal"),
+        }
+    }
+    /// The value of `key = "string"`
+    pub fn str(&self, key: &str) -> Result<Option<String>> {
+        match self.lit(key)? {
+            None => Ok(None),
+            Some(ParsedLit {
+                value: LitValue::Str(value),
+                ..
+            }) => Ok(Some(value)),
+            Some(lit) => Error::err(lit.span, "expected a string literal"),
+        }
+    }
+    /// The identifier of `key = ident`
+    pub fn ident(&self, key: &str) -> Result<Option<Ident>> {
+        let Some(value) = self.tokens(key)? else {
+            return Ok(None);
+        };
+        match single(value) {
+            Some(TokenTree::Ident(ident)) => Ok(Some(ident)),
+            _ => Error::err_spanned(value, "expected an identifier"),
+        }
+    }
+    /// The nested arguments of `key(...)`, parsed with the keys in `known`
+    pub fn list(&self, key: &str, known: &[&str]) -> Result<Option<KeyValues>> {
+        match self.entry(key) {
+            None => Ok(None),
+            Some((_, KeyValue::List(tokens))) => KeyValues::parse(tokens.clone(), known).map(Some),
+            Some((ident, _)) => {
+                Error::err(ident.span(), format!("expected `{}(...)`", unraw(ident)))
+            }
+        }
+    }
+
+    fn entry(&self, key: &str) -> Option<&(Ident, KeyValue)> {
+        self.entries.iter().find(|(ident, _)| unraw(ident) == key)
+    }
+}
+
+fn unraw(ident: &Ident) -> String {
+    let name = ident.to_string();
+    name.strip_prefix("r#").map(str::to_string).unwrap_or(name)
+}
+
+/// The only token of the stream, looking through `None`-delimited groups
+fn single(tokens: &TokenStream) -> Option<TokenTree> {
+    let mut cursor = Cursor::new(tokens.clone());
+    let token = cursor.advance()?;
+    cursor.is_empty().then_some(token)
+}
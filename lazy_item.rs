@@ -0,0 +1,263 @@
+#![allow(dead_code)]
+
+use super::Result;
+use proc_macro2::{Delimiter, Group, Ident, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens, TokenStreamExt};
+use syn::parse::{Parse, ParseStream};
+
+/// A function with only the signature parsed and the body kept as tokens, for attribute macros
+/// that wrap or inspect the signature of functions with large bodies. `syn::ItemFn` parses every
+/// statement and expression of the body, which is most of the work for big functions.
+///
+/// Also accepts functions without a body (`fn foo();`), like required trait methods.
+/// ```ignore
+/// // #[timed] fn handler() { ... }
+/// fn timed(_args: TokenStream, item: TokenStream) -> Result<TokenStream> {
+///     let mut item = LazyFn::parse(item)?;
+///     let name = item.sig.ident.to_string();
+///     let body = item.body.as_ref().map(Group::stream);
+///     item.set_body(quote!(let _timer = Timer::new(#name); #body));
+///     Ok(item.into_token_stream())
+/// }
+/// ```
+#[derive(Clone)]
+pub(crate) struct LazyFn {
+    pub attrs: Vec<syn::Attribute>,
+    pub vis: syn::Visibility,
+    pub sig: syn::Signature,
+    /// The braces of the body with their contents, or `None` for `fn foo();`
+    pub body: Option<Group>,
+}
+
+impl LazyFn {
+    /// Parse a function, see `syn::parse2`
+    pub fn parse(tokens: TokenStream) -> Result<Self> {
+        Ok(syn::parse2(tokens)?)
+    }
+    /// Parse the body, for when it is needed after all
+    pub fn parse_body(&self) -> Result<Option<syn::Block>> {
+        let body = self.body.as_ref().map(ToTokens::to_token_stream);
+        Ok(body.map(syn::parse2).transpose()?)
+    }
+    /// Replace the body with the given statements, keeping the span of the old braces
+    pub fn set_body(&mut self, stmts: impl ToTokens) {
+        let mut group = Group::new(Delimiter::Brace, stmts.into_token_stream());
+        if let Some(old) = &self.body {
+            group.set_span(old.span());
+        }
+        self.body = Some(group);
+    }
+}
+
+impl Parse for LazyFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        let body = if input.peek(syn::Token![;]) {
+            input.parse::<syn::Token![;]>()?;
+            None
+        } else {
+            Some(brace_group(input)?)
+        };
+        Ok(Self {
+            attrs,
+            vis,
+            sig,
+            body,
+        })
+    }
+}
+
+impl ToTokens for LazyFn {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(&self.attrs);
+        self.vis.to_tokens(tokens);
+        self.sig.to_tokens(tokens);
+        match &self.body {
+            Some(body) => body.to_tokens(tokens),
+            None => <syn::Token![;]>::default().to_tokens(tokens),
+        }
+    }
+}
+
+/// A struct with the field types kept as tokens, for macros that only need the names and
+/// attributes of the fields. Types are parsed on demand with `LazyField::parse_ty`.
+/// ```ignore
+/// let item = LazyStruct::parse(input)?;
+/// let names: Vec<_> = item.fields.iter().filter_map(|field| field.ident.as_ref()).collect();
+/// ```
+#[derive(Clone)]
+pub(crate) struct LazyStruct {
+    pub attrs: Vec<syn::Attribute>,
+    pub vis: syn::Visibility,
+    pub ident: Ident,
+    /// The generic parameters and the where clause
+    pub generics: syn::Generics,
+    pub style: LazyStructStyle,
+    pub fields: Vec<LazyField>,
+}
+
+/// How the fields of a `LazyStruct` are written
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum LazyStructStyle {
+    /// `struct A { a: u8 }`
+    Named,
+    /// `struct A(u8);`
+    Tuple,
+    /// `struct A;`
+    Unit,
+}
+
+/// A field of a `LazyStruct`
+#[derive(Clone)]
+pub(crate) struct LazyField {
+    pub attrs: Vec<syn::Attribute>,
+    pub vis: syn::Visibility,
+    /// The name, or `None` in tuple structs
+    pub ident: Option<Ident>,
+    /// The tokens of the type
+    pub ty: TokenStream,
+}
+
+impl LazyField {
+    /// Parse the type
+    pub fn parse_ty(&self) -> Result<syn::Type> {
+        Ok(syn::parse2(self.ty.clone())?)
+    }
+}
+
+impl LazyStruct {
+    /// Parse a struct, see `syn::parse2`
+    pub fn parse(tokens: TokenStream) -> Result<Self> {
+        Ok(syn::parse2(tokens)?)
+    }
+}
+
+impl Parse for LazyStruct {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<syn::Token![struct]>()?;
+        let ident = input.parse()?;
+        let mut generics: syn::Generics = input.parse()?;
+        let (style, fields) = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let fields = parse_fields(&content, false)?;
+            generics.where_clause = input.parse()?;
+            input.parse::<syn::Token![;]>()?;
+            (LazyStructStyle::Tuple, fields)
+        } else {
+            generics.where_clause = input.parse()?;
+            if input.peek(syn::Token![;]) {
+                input.parse::<syn::Token![;]>()?;
+                (LazyStructStyle::Unit, vec![])
+            } else {
+                let content;
+                syn::braced!(content in input);
+                (LazyStructStyle::Named, parse_fields(&content, true)?)
+            }
+        };
+        Ok(Self {
+            attrs,
+            vis,
+            ident,
+            generics,
+            style,
+            fields,
+        })
+    }
+}
+
+impl ToTokens for LazyStruct {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(&self.attrs);
+        self.vis.to_tokens(tokens);
+        <syn::Token![struct]>::default().to_tokens(tokens);
+        self.ident.to_tokens(tokens);
+        self.generics.to_tokens(tokens);
+        let where_clause = &self.generics.where_clause;
+        let fields = self.fields.iter().map(|field| {
+            let LazyField {
+                attrs,
+                vis,
+                ident,
+                ty,
+            } = field;
+            let ident = ident.as_ref().map(|ident| quote!(#ident:));
+            quote!(#(#attrs)* #vis #ident #ty)
+        });
+        tokens.extend(match self.style {
+            LazyStructStyle::Named => quote!(#where_clause { #(#fields),* }),
+            LazyStructStyle::Tuple => quote!((#(#fields),*) #where_clause;),
+            LazyStructStyle::Unit => quote!(#where_clause;),
+        });
+    }
+}
+
+fn parse_fields(input: ParseStream, named: bool) -> syn::Result<Vec<LazyField>> {
+    let mut fields = vec![];
+    while !input.is_empty() {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let ident = if named {
+            let ident = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            Some(ident)
+        } else {
+            None
+        };
+        let ty = type_tokens(input)?;
+        fields.push(LazyField {
+            attrs,
+            vis,
+            ident,
+            ty,
+        });
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<syn::Token![,]>()?;
+    }
+    Ok(fields)
+}
+
+/// The tokens up to the next `,` that is not inside of `<...>`
+fn type_tokens(input: ParseStream) -> syn::Result<TokenStream> {
+    input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = TokenStream::new();
+        let mut depth = 0usize;
+        let mut after_joint_minus = false;
+        while let Some((token, next)) = rest.token_tree() {
+            if let TokenTree::Punct(punct) = &token {
+                match punct.as_char() {
+                    ',' if depth == 0 => break,
+                    '<' => depth += 1,
+                    // `->` in `fn() -> T` is not a closing bracket
+                    '>' if !after_joint_minus => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                after_joint_minus = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+            } else {
+                after_joint_minus = false;
+            }
+            tokens.append(token);
+            rest = next;
+        }
+        if tokens.is_empty() {
+            return Err(cursor.error("expected a type"));
+        }
+        Ok((tokens, rest))
+    })
+}
+
+fn brace_group(input: ParseStream) -> syn::Result<Group> {
+    input.step(|cursor| match cursor.token_tree() {
+        Some((TokenTree::Group(group), rest)) if group.delimiter() == Delimiter::Brace => {
+            Ok((group, rest))
+        }
+        _ => Err(cursor.error("expected `{`")),
+    })
+}
@@ -0,0 +1,133 @@
+#![allow(dead_code)]
+
+use super::{LitValue, ParsedLit, StrStyle};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{ToTokens, TokenStreamExt};
+
+/// Integer types that can be passed to `lit::int`
+pub(crate) trait IntLiteral: Copy {
+    /// The suffix for this type, e.g. `"u8"`
+    const SUFFIX: &'static str;
+    /// The sign and magnitude
+    fn split(self) -> (bool, u128);
+}
+
+macro_rules! int_literal {
+    (unsigned: $($ty:ident),*; signed: $($signed:ident),*) => {
+        $(impl IntLiteral for $ty {
+            const SUFFIX: &'static str = stringify!($ty);
+            fn split(self) -> (bool, u128) {
+                (false, self as u128)
+            }
+        })*
+        $(impl IntLiteral for $signed {
+            const SUFFIX: &'static str = stringify!($signed);
+            fn split(self) -> (bool, u128) {
+                (self < 0, self.unsigned_abs() as u128)
+            }
+        })*
+    };
+}
+int_literal!(unsigned: u8, u16, u32, u64, u128, usize; signed: i8, i16, i32, i64, i128, isize);
+
+/// Float types that can be passed to `lit::float`
+pub(crate) trait FloatLiteral: Copy {
+    /// The suffix for this type, e.g. `"f32"`
+    const SUFFIX: &'static str;
+    /// The value as `f64`
+    fn to_f64(self) -> f64;
+}
+
+impl FloatLiteral for f32 {
+    const SUFFIX: &'static str = "f32";
+    fn to_f64(self) -> f64 {
+        // through the string representation, so `0.1f32` doesn't become `0.10000000149011612`
+        self.to_string().parse().unwrap_or(self as f64)
+    }
+}
+
+impl FloatLiteral for f64 {
+    const SUFFIX: &'static str = "f64";
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// An integer literal with the suffix of its type: `lit::int(42u64)` is `42u64`. Use
+/// `.suffix("usize")` for a different type or `.suffix("")` for an unsuffixed literal.
+/// ```ignore
+/// let index = lit::int(i).suffix("usize").span(field.span());
+/// quote!(self.values[#index])
+/// ```
+pub(crate) fn int<T: IntLiteral>(value: T) -> ParsedLit {
+    let (negative, magnitude) = value.split();
+    new(LitValue::Int(magnitude), negative, T::SUFFIX)
+}
+
+/// A float literal with the suffix of its type: `lit::float(1.5f32)` is `1.5f32`. Panics if the
+/// value is not finite.
+pub(crate) fn float<T: FloatLiteral>(value: T) -> ParsedLit {
+    let value = value.to_f64();
+    assert!(value.is_finite(), "lit::float: {} has no literal", value);
+    // `{:?}` always includes a `.` or an exponent, so the literal doesn't turn into an integer
+    let text = format!("{:?}", value.abs());
+    new(LitValue::Float(text), value.is_sign_negative(), T::SUFFIX)
+}
+
+/// A string literal with escapes: `"..."`
+pub(crate) fn str(value: &str) -> ParsedLit {
+    new(LitValue::Str(value.to_string()), false, "")
+}
+
+/// A raw string literal with at least `min_hashes` `#`: `r#"..."#`. More `#` are used if the
+/// value contains `"#`.
+pub(crate) fn str_raw(value: &str, min_hashes: usize) -> ParsedLit {
+    let mut lit = str(value);
+    lit.style = StrStyle::Raw(min_hashes);
+    lit
+}
+
+/// A byte string literal: `b"..."`
+pub(crate) fn byte_str(value: &[u8]) -> ParsedLit {
+    new(LitValue::ByteStr(value.to_vec()), false, "")
+}
+
+/// A character literal: `'a'`
+pub(crate) fn char(value: char) -> ParsedLit {
+    new(LitValue::Char(value), false, "")
+}
+
+/// A byte literal: `b'a'`
+pub(crate) fn byte(value: u8) -> ParsedLit {
+    new(LitValue::Byte(value), false, "")
+}
+
+fn new(value: LitValue, negative: bool, suffix: &str) -> ParsedLit {
+    ParsedLit {
+        value,
+        style: StrStyle::Cooked,
+        negative,
+        suffix: suffix.to_string(),
+        span: Span::call_site(),
+    }
+}
+
+impl ParsedLit {
+    /// Replace the suffix. Panics in `to_literal` if it is not a valid identifier.
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+    /// Replace the span
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+impl ToTokens for ParsedLit {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let literal: Literal = self.to_literal();
+        tokens.append(literal);
+    }
+}
@@ -0,0 +1,443 @@
+#![allow(dead_code)]
+
+use super::{Error, Result};
+use proc_macro2::{Literal, Span};
+
+mod build;
+pub(crate) use build::*;
+
+/// How a string, byte string or C string literal is written
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum StrStyle {
+    /// `"..."` with escapes
+    Cooked,
+    /// `r#"..."#` with the given number of `#`
+    Raw(usize),
+}
+
+/// The value of a literal, with all escapes resolved
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum LitValue {
+    /// `"..."` and `r"..."`
+    Str(String),
+    /// `b"..."` and `br"..."`
+    ByteStr(Vec<u8>),
+    /// `c"..."` and `cr"..."`, without the terminating nul
+    CStr(Vec<u8>),
+    /// `'a'`
+    Char(char),
+    /// `b'a'`
+    Byte(u8),
+    /// `42`, `0xff_u8`, ... (without the sign, see `ParsedLit::negative`)
+    Int(u128),
+    /// `1.5`, `1e10`, `2f32`, ..., as written but without underscores and suffix
+    Float(String),
+}
+
+/// A literal taken apart by `parse`. Change any of the fields and turn it back into a literal
+/// with `to_literal`:
+/// ```ignore
+/// // make sure a user-provided raw string can be embedded in `r#"..."#`
+/// let mut lit = lit::parse(&literal)?;
+/// lit.style = StrStyle::Raw(1);
+/// let literal = lit.to_literal();
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct ParsedLit {
+    /// The value of the literal
+    pub value: LitValue,
+    /// The style of string-like literals, `Cooked` for all others
+    pub style: StrStyle,
+    /// Whether the literal has a leading `-`. Only possible for numbers, and only for literals
+    /// created with e.g. `Literal::i32_suffixed(-1)`, since `-1` in source code is two tokens.
+    pub negative: bool,
+    /// The suffix, e.g. `u8` in `1u8`. Empty if there is none.
+    pub suffix: String,
+    /// The span of the literal
+    pub span: Span,
+}
+
+impl ParsedLit {
+    /// The value as a string, if this is a string literal
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            LitValue::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// The value as bytes, if this is a byte string or C string literal
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.value {
+            LitValue::ByteStr(value) | LitValue::CStr(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// The value of an integer literal, if it fits into an `i128`
+    pub fn as_i128(&self) -> Option<i128> {
+        let LitValue::Int(value) = self.value else {
+            return None;
+        };
+        if self.negative {
+            0i128.checked_sub_unsigned(value)
+        } else {
+            i128::try_from(value).ok()
+        }
+    }
+    /// The value of an integer or float literal as a float
+    pub fn as_f64(&self) -> Option<f64> {
+        let value = match &self.value {
+            LitValue::Int(value) => *value as f64,
+            LitValue::Float(value) => value.parse().ok()?,
+            _ => return None,
+        };
+        Some(if self.negative { -value } else { value })
+    }
+
+    /// Turn the value back into a literal token with `span`, `style` and `suffix`.
+    ///
+    /// Raw strings get as many `#` as needed for their content if `style` has too few. Byte
+    /// strings and C strings that can't be written as raw strings (because they aren't valid
+    /// UTF-8) are written cooked. Integers are always written in decimal.
+    ///
+    /// Panics if the suffix is not a valid identifier.
+    pub fn to_literal(&self) -> Literal {
+        let mut text = String::new();
+        if self.negative {
+            text.push('-');
+        }
+        let raw_hashes = |value: &str| match self.style {
+            StrStyle::Cooked => None,
+            StrStyle::Raw(hashes) => Some(hashes.max(min_raw_hashes(value))),
+        };
+        match &self.value {
+            LitValue::Str(value) => match raw_hashes(value) {
+                Some(hashes) => push_raw(&mut text, "r", value, hashes),
+                None => text += &format!("{:?}", value),
+            },
+            LitValue::ByteStr(value) | LitValue::CStr(value) => {
+                let prefix = if matches!(self.value, LitValue::CStr(_)) {
+                    "c"
+                } else {
+                    "b"
+                };
+                let raw = std::str::from_utf8(value)
+                    .ok()
+                    .filter(|s| prefix == "c" || s.is_ascii())
+                    .and_then(|s| Some((s, raw_hashes(s)?)));
+                match raw {
+                    Some((s, hashes)) => push_raw(&mut text, &format!("{}r", prefix), s, hashes),
+                    None => {
+                        text += prefix;
+                        text.push('"');
+                        for byte in value {
+                            push_escaped_byte(&mut text, *byte, '"');
+                        }
+                        text.push('"');
+                    }
+                }
+            }
+            LitValue::Char(c) => text += &format!("{:?}", c),
+            LitValue::Byte(byte) => {
+                text += "b'";
+                push_escaped_byte(&mut text, *byte, '\'');
+                text.push('\'');
+            }
+            LitValue::Int(value) => text += &value.to_string(),
+            LitValue::Float(value) => text += value,
+        }
+        if !self.suffix.is_empty() {
+            assert!(
+                super::ident::is_valid_ident(&self.suffix),
+                "`{}` is not a valid literal suffix",
+                self.suffix
+            );
+            text += &self.suffix;
+        }
+        let mut literal: Literal = text
+            .parse()
+            .unwrap_or_else(|_| panic!("failed to create literal `{}`", text));
+        literal.set_span(self.span);
+        literal
+    }
+}
+
+/// Parse a literal into its value, style and suffix. Returns an error at the literal if it is
+/// malformed (which can only happen for literals that were not lexed by the compiler, e.g. an out
+/// of range `\u{...}` escape).
+pub(crate) fn parse(literal: &Literal) -> Result<ParsedLit> {
+    let span = literal.span();
+    let text = literal.to_string();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+    let fail = |message: &str| Error::new(span, format!("{}: `{}`", message, literal));
+    let mut parsed = ParsedLit {
+        value: LitValue::Int(0),
+        style: StrStyle::Cooked,
+        negative,
+        suffix: String::new(),
+        span,
+    };
+    let first = text.chars().next().ok_or_else(|| fail("empty literal"))?;
+    let string_like = match first {
+        '"' | 'r' => Some((Mode::Str, text)),
+        'b' if matches!(text.as_bytes().get(1), Some(b'"' | b'r')) => {
+            Some((Mode::Byte, &text[1..]))
+        }
+        'c' => Some((Mode::C, &text[1..])),
+        _ => None,
+    };
+    if let Some((mode, rest)) = string_like {
+        let (body, style, suffix) =
+            split_string(rest).ok_or_else(|| fail("invalid string literal"))?;
+        let bytes = match style {
+            StrStyle::Cooked => unescape(body, mode).map_err(|e| fail(&e))?,
+            StrStyle::Raw(_) => body.as_bytes().to_vec(),
+        };
+        parsed.value = match mode {
+            Mode::Str => {
+                LitValue::Str(String::from_utf8(bytes).map_err(|_| fail("invalid UTF-8"))?)
+            }
+            Mode::Byte => LitValue::ByteStr(bytes),
+            Mode::C => LitValue::CStr(bytes),
+        };
+        parsed.style = style;
+        parsed.suffix = suffix.to_string();
+    } else if first == '\'' || text.starts_with("b'") {
+        let mode = if first == '\'' { Mode::Str } else { Mode::Byte };
+        let rest = if first == '\'' { text } else { &text[1..] };
+        let end = rest
+            .rfind('\'')
+            .filter(|end| *end > 0)
+            .ok_or_else(|| fail("invalid character literal"))?;
+        let bytes = unescape(&rest[1..end], mode).map_err(|e| fail(&e))?;
+        parsed.value = match mode {
+            Mode::Byte => match bytes[..] {
+                [byte] => LitValue::Byte(byte),
+                _ => return Err(fail("byte literal must contain exactly one byte")),
+            },
+            _ => {
+                let value = String::from_utf8(bytes).map_err(|_| fail("invalid UTF-8"))?;
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => LitValue::Char(c),
+                    _ => return Err(fail("character literal must contain exactly one character")),
+                }
+            }
+        };
+        parsed.suffix = rest[end + 1..].to_string();
+    } else if first.is_ascii_digit() {
+        let (value, suffix) = parse_number(text).map_err(|e| fail(&e))?;
+        parsed.value = value;
+        parsed.suffix = suffix.to_string();
+    } else {
+        return Err(fail("unknown literal"));
+    }
+    Ok(parsed)
+}
+
+/// Shorthand for `parse` that expects a string literal and returns its value
+pub(crate) fn parse_str(literal: &Literal) -> Result<String> {
+    match parse(literal)? {
+        ParsedLit {
+            value: LitValue::Str(value),
+            ..
+        } => Ok(value),
+        _ => Error::err(literal.span(), "expected a string literal"),
+    }
+}
+
+/// The smallest number of `#` needed to write `value` as a raw string
+pub(crate) fn min_raw_hashes(value: &str) -> usize {
+    value
+        .match_indices('"')
+        .map(|(i, _)| value[i + 1..].bytes().take_while(|b| *b == b'#').count() + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The span of the bytes at `range` in the value of a string literal, for errors that point
+/// inside of the literal. Falls back to the span of the whole literal if the compiler doesn't
+/// support it (`Span::subspan` is nightly-only) or if the literal contains escapes, since
+/// offsets in the value don't map to the source text then.
+#[cfg(not(feature = "no-syn"))]
+pub(crate) fn value_span(lit: &syn::LitStr, range: std::ops::Range<usize>) -> Span {
+    let token = lit.token();
+    let text = token.to_string();
+    let value = lit.value();
+    let prefix = if text.starts_with('r') {
+        text.find('"').map(|i| i + 1)
+    } else if text.get(1..text.len() - 1) == Some(value.as_str()) {
+        Some(1)
+    } else {
+        None
+    };
+    prefix
+        .and_then(|prefix| super::lit_subspan(&token, prefix + range.start..prefix + range.end))
+        .unwrap_or_else(|| lit.span())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Strings and chars: `\x` up to 0x7f, `\u{...}` allowed
+    Str,
+    /// Bytes and byte strings: `\x` up to 0xff, ASCII only
+    Byte,
+    /// C strings: `\x` up to 0xff, `\u{...}` allowed
+    C,
+}
+
+/// Split `"..."suffix` or `r#"..."#suffix` into content, style and suffix
+fn split_string(text: &str) -> Option<(&str, StrStyle, &str)> {
+    if let Some(rest) = text.strip_prefix('r') {
+        let hashes = rest.bytes().take_while(|b| *b == b'#').count();
+        let rest = rest[hashes..].strip_prefix('"')?;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let end = rest.rfind(&terminator)?;
+        Some((
+            &rest[..end],
+            StrStyle::Raw(hashes),
+            &rest[end + terminator.len()..],
+        ))
+    } else {
+        let rest = text.strip_prefix('"')?;
+        let end = rest.rfind('"')?;
+        Some((&rest[..end], StrStyle::Cooked, &rest[end + 1..]))
+    }
+}
+
+fn unescape(body: &str, mode: Mode) -> std::result::Result<Vec<u8>, String> {
+    let mut out = vec![];
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            if mode == Mode::Byte && !c.is_ascii() {
+                return Err("non-ASCII character in byte literal".to_string());
+            }
+            let mut buf = [0; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let escape = chars.next().ok_or("unterminated escape")?;
+        let byte = match escape {
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            '\\' => b'\\',
+            '0' => b'\0',
+            '\'' => b'\'',
+            '"' => b'"',
+            'x' => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let value = u8::from_str_radix(&digits, 16)
+                    .map_err(|_| format!("invalid escape `\\x{}`", digits))?;
+                if mode == Mode::Str && value > 0x7f {
+                    return Err(format!(
+                        "`\\x{}` is out of range, must be at most 7f",
+                        digits
+                    ));
+                }
+                value
+            }
+            'u' if mode != Mode::Byte => {
+                let mut digits = String::new();
+                if chars.next() != Some('{') {
+                    return Err("invalid unicode escape".to_string());
+                }
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    digits.push(c);
+                }
+                let c = u32::from_str_radix(&digits.replace('_', ""), 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid unicode escape `\\u{{{}}}`", digits))?;
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+            '\n' | '\r' => {
+                // line continuation: skip the line break and leading whitespace
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                continue;
+            }
+            other => return Err(format!("unknown escape `\\{}`", other)),
+        };
+        out.push(byte);
+    }
+    Ok(out)
+}
+
+/// Parse an integer or float literal without sign, returning the value and the suffix
+fn parse_number(text: &str) -> std::result::Result<(LitValue, &str), String> {
+    let (radix, digits_start) = match text.get(..2) {
+        Some("0x") => (16, 2),
+        Some("0o") => (8, 2),
+        Some("0b") => (2, 2),
+        _ => (10, 0),
+    };
+    let rest = &text[digits_start..];
+    let is_digit = |c: char| c == '_' || c.is_digit(if radix == 16 { 16 } else { 10 });
+    let mut end = rest.find(|c: char| !is_digit(c)).unwrap_or(rest.len());
+    let mut is_float = false;
+    if radix == 10 {
+        let bytes = rest.as_bytes();
+        if bytes.get(end) == Some(&b'.') {
+            is_float = true;
+            end += 1;
+            end += rest[end..]
+                .find(|c: char| !is_digit(c))
+                .unwrap_or(rest.len() - end);
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let mut exponent = end + 1;
+            if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+                exponent += 1;
+            }
+            let exponent_len = rest[exponent..]
+                .find(|c: char| !is_digit(c))
+                .unwrap_or(rest.len() - exponent);
+            if rest[exponent..exponent + exponent_len].contains(|c: char| c.is_ascii_digit()) {
+                is_float = true;
+                end = exponent + exponent_len;
+            }
+        }
+    }
+    let (digits, suffix) = rest.split_at(end);
+    let digits = digits.replace('_', "");
+    if is_float || (radix == 10 && matches!(suffix, "f32" | "f64")) {
+        return Ok((LitValue::Float(digits), suffix));
+    }
+    if digits.is_empty() {
+        return Err("missing digits".to_string());
+    }
+    let value = u128::from_str_radix(&digits, radix).map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => "integer literal is too large".to_string(),
+        _ => format!("invalid digit for a base {} literal", radix),
+    })?;
+    Ok((LitValue::Int(value), suffix))
+}
+
+fn push_raw(text: &mut String, prefix: &str, value: &str, hashes: usize) {
+    let hashes = "#".repeat(hashes);
+    *text += &format!("{}{}\"{}\"{}", prefix, hashes, value, hashes);
+}
+
+fn push_escaped_byte(text: &mut String, byte: u8, quote: char) {
+    match byte {
+        b'\n' => *text += "\\n",
+        b'\r' => *text += "\\r",
+        b'\t' => *text += "\\t",
+        b'\\' => *text += "\\\\",
+        b'\0' => *text += "\\0",
+        _ if byte as char == quote => {
+            text.push('\\');
+            text.push(quote);
+        }
+        0x20..=0x7e => text.push(byte as char),
+        _ => *text += &format!("\\x{:02x}", byte),
+    }
+}
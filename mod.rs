@@ -1,4 +1,58 @@
 #![allow(dead_code)]
 
+#[cfg(not(feature = "no-syn"))]
+mod attr_schema;
+mod case;
+#[cfg(not(feature = "no-syn"))]
+mod crate_path;
+mod edition;
+mod entry;
 mod error;
+#[cfg(all(feature = "from-attr", not(feature = "no-syn")))]
+mod from_attr;
+mod ide_policy;
+mod include_file;
+mod key_values;
+#[cfg(not(feature = "no-syn"))]
+mod lazy_item;
+#[cfg(not(feature = "no-syn"))]
+mod path_ext;
+mod rust_version;
+mod scope;
+mod toolchain;
+mod tracked;
+mod warning;
+#[cfg(not(feature = "no-syn"))]
+pub(crate) use attr_schema::*;
+pub(crate) use case::*;
+#[cfg(not(feature = "no-syn"))]
+pub(crate) use crate_path::*;
+pub(crate) use edition::*;
+pub(crate) use entry::*;
 pub(crate) use error::*;
+#[cfg(all(feature = "from-attr", not(feature = "no-syn")))]
+pub(crate) use from_attr::*;
+pub(crate) use ide_policy::*;
+pub(crate) use include_file::*;
+pub(crate) use key_values::*;
+#[cfg(not(feature = "no-syn"))]
+pub(crate) use lazy_item::*;
+#[cfg(not(feature = "no-syn"))]
+pub(crate) use path_ext::*;
+pub(crate) use rust_version::*;
+pub(crate) use toolchain::*;
+pub(crate) use tracked::*;
+pub(crate) use warning::*;
+
+#[cfg(not(feature = "no-syn"))]
+pub(crate) mod codegen;
+pub(crate) mod ident;
+#[cfg(not(feature = "no-proc-macro"))]
+pub(crate) mod interop;
+pub(crate) mod lit;
+#[cfg(all(feature = "syn1", not(feature = "no-syn")))]
+pub(crate) mod syn1_compat;
+#[cfg(all(feature = "test-util", not(feature = "no-syn")))]
+pub(crate) mod testing;
+pub(crate) mod tokens;
+pub(crate) mod trace;
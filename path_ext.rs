@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+use proc_macro2::Ident;
+use syn::{Path, PathSegment};
+
+/// Convenience functions for manipulating `syn::Path`s without having to deal with the segment
+/// list directly
+pub(crate) trait PathExt {
+    /// Add a segment to the end of the path: `a::b` -> `a::b::c`
+    fn append(&mut self, segment: impl Into<PathSegment>) -> &mut Self;
+    /// Same as `append`, but returns a new path instead of modifying this one
+    fn joined(&self, segment: impl Into<PathSegment>) -> Self;
+    /// Replace the identifier of the last segment, keeping its generic arguments:
+    /// `a::B<T>` -> `a::C<T>`. Does nothing if the path is empty.
+    fn replace_last(&mut self, ident: Ident) -> &mut Self;
+    /// Remove the leading `::` of the path, if any: `::a::b` -> `a::b`
+    fn strip_leading_colons(&mut self) -> &mut Self;
+    /// Check if the last segment of the path is `name`, ignoring generic arguments. This matches
+    /// `Option`, `Option<T>`, `std::option::Option<T>` and `::core::option::Option` for `"Option"`.
+    ///
+    /// Note that this can't know what the path actually resolves to, so `my_mod::Option` would
+    /// match as well.
+    fn is_ident_or_path_to(&self, name: &str) -> bool;
+    /// The identifier of the last segment, if the path is not empty
+    fn last_ident(&self) -> Option<&Ident>;
+    /// The generic arguments of the last segment, if there are any
+    fn last_arguments(&self) -> Option<&syn::PathArguments>;
+}
+
+impl PathExt for Path {
+    fn append(&mut self, segment: impl Into<PathSegment>) -> &mut Self {
+        self.segments.push(segment.into());
+        self
+    }
+    fn joined(&self, segment: impl Into<PathSegment>) -> Self {
+        let mut path = self.clone();
+        path.append(segment);
+        path
+    }
+    fn replace_last(&mut self, ident: Ident) -> &mut Self {
+        if let Some(last) = self.segments.last_mut() {
+            last.ident = ident;
+        }
+        self
+    }
+    fn strip_leading_colons(&mut self) -> &mut Self {
+        self.leading_colon = None;
+        self
+    }
+    fn is_ident_or_path_to(&self, name: &str) -> bool {
+        self.last_ident().is_some_and(|ident| ident == name)
+    }
+    fn last_ident(&self) -> Option<&Ident> {
+        self.segments.last().map(|segment| &segment.ident)
+    }
+    fn last_arguments(&self) -> Option<&syn::PathArguments> {
+        self.segments
+            .last()
+            .map(|segment| &segment.arguments)
+            .filter(|arguments| !arguments.is_none())
+    }
+}
@@ -0,0 +1,173 @@
+#![allow(dead_code)]
+
+use super::{package_key, Error, Result};
+use proc_macro2::Span;
+use std::{cell::RefCell, fmt, path::Path, sync::Mutex};
+
+/// Overrides the Rust version that `TargetRustVersion::detect` finds, e.g.
+/// `MACRO_RUST_VERSION=1.60`
+pub(crate) const RUST_VERSION_VAR: &str = "MACRO_RUST_VERSION";
+
+thread_local! {
+    static CURRENT: RefCell<Option<TargetRustVersion>> = const { RefCell::new(None) };
+}
+
+/// The oldest Rust version (MSRV) that the crate the macro is used in supports. Generators check
+/// it with `RustFeature::is_supported` to avoid newer syntax, and `RustFeature::require` reports
+/// an error if the macro can't do without a feature.
+/// ```ignore
+/// // #[my_macro(msrv = "1.60")]
+/// let version = TargetRustVersion::from_lit(&msrv)?;
+/// let output = version.scoped(|| generate(input));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub(crate) struct TargetRustVersion {
+    /// The `x` in `1.x.y`
+    pub minor: u32,
+    /// The `y` in `1.x.y`
+    pub patch: u32,
+}
+
+impl TargetRustVersion {
+    /// The version `1.minor.0`
+    pub const fn new(minor: u32) -> TargetRustVersion {
+        TargetRustVersion { minor, patch: 0 }
+    }
+
+    /// Parse a version like in `rust-version`: `"1.65"` or `"1.65.0"`
+    pub fn parse(version: &str) -> Option<TargetRustVersion> {
+        let mut parts = version.trim().split('.');
+        if parts.next()? != "1" {
+            return None;
+        }
+        let minor = parts.next()?.parse().ok()?;
+        let patch = match parts.next() {
+            Some(patch) => patch.parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(TargetRustVersion { minor, patch })
+    }
+    /// Parse a version from a string literal of an attribute argument
+    #[cfg(not(feature = "no-syn"))]
+    pub fn from_lit(lit: &syn::LitStr) -> Result<TargetRustVersion> {
+        TargetRustVersion::parse(&lit.value())
+            .ok_or_else(|| Error::new_spanned(lit, "invalid Rust version. Expected e.g. \"1.65\""))
+    }
+
+    /// The version set with `scoped`, or the detected one. `None` if the crate doesn't declare
+    /// a Rust version, in which case all features are used.
+    pub fn current() -> Option<TargetRustVersion> {
+        CURRENT
+            .with_borrow(|current| *current)
+            .or_else(TargetRustVersion::detect)
+    }
+    /// Use this version as `current` while running `f`
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::scope::scoped(&CURRENT, Some(self), f)
+    }
+    /// Try to determine the Rust version of the crate that is currently being compiled from
+    /// `MACRO_RUST_VERSION`, or from the `rust-version` key of its `Cargo.toml`, including
+    /// `rust-version.workspace = true`. The manifest lookup is cached per manifest directory.
+    pub fn detect() -> Option<TargetRustVersion> {
+        static CACHE: Mutex<Option<(String, Option<TargetRustVersion>)>> = Mutex::new(None);
+
+        if let Ok(version) = std::env::var(RUST_VERSION_VAR) {
+            return TargetRustVersion::parse(&version);
+        }
+
+        let dir = std::env::var("CARGO_MANIFEST_DIR").ok()?;
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_dir, version)) = &*cache {
+            if *cached_dir == dir {
+                return *version;
+            }
+        }
+        let version = package_key(Path::new(&dir), "rust-version")
+            .flatten()
+            .and_then(|version| TargetRustVersion::parse(&version));
+        *cache = Some((dir, version));
+        version
+    }
+
+    /// Whether code for this version can use `feature`
+    pub fn supports(self, feature: RustFeature) -> bool {
+        self >= feature.min_version()
+    }
+}
+
+impl fmt::Display for TargetRustVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.patch == 0 {
+            write!(f, "1.{}", self.minor)
+        } else {
+            write!(f, "1.{}.{}", self.minor, self.patch)
+        }
+    }
+}
+
+/// A language feature that generated code might use, for checks against `TargetRustVersion`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RustFeature {
+    /// `panic!` and `assert!` in constants
+    ConstPanic,
+    /// Trait bounds on generic parameters of a `const fn`
+    ConstFnTraitBounds,
+    /// `let pattern = expr else { ... };`
+    LetElse,
+    /// Inline `const { ... }` blocks
+    InlineConst,
+    /// `&mut` references in a `const fn`
+    ConstMutRefs,
+}
+
+impl RustFeature {
+    /// The first Rust version with the feature
+    pub fn min_version(self) -> TargetRustVersion {
+        TargetRustVersion::new(match self {
+            RustFeature::ConstPanic => 57,
+            RustFeature::ConstFnTraitBounds => 61,
+            RustFeature::LetElse => 65,
+            RustFeature::InlineConst => 79,
+            RustFeature::ConstMutRefs => 83,
+        })
+    }
+    /// A description for error messages
+    pub fn description(self) -> &'static str {
+        match self {
+            RustFeature::ConstPanic => "`panic!` in constants",
+            RustFeature::ConstFnTraitBounds => "trait bounds on `const fn` parameters",
+            RustFeature::LetElse => "`let ... else`",
+            RustFeature::InlineConst => "inline `const` blocks",
+            RustFeature::ConstMutRefs => "`&mut` in `const fn`",
+        }
+    }
+
+    /// Whether the current `TargetRustVersion` supports the feature, which is always the case if
+    /// the crate doesn't declare a version
+    pub fn is_supported(self) -> bool {
+        TargetRustVersion::current().is_none_or(|version| version.supports(self))
+    }
+    /// An error at `span` if the current `TargetRustVersion` doesn't support the feature, for
+    /// code that has no fallback without it:
+    /// ```ignore
+    /// // error: Rust 1.79 is required for inline `const` blocks, but the crate supports Rust 1.70
+    /// RustFeature::InlineConst.require(attr.span())?;
+    /// ```
+    pub fn require(self, span: Span) -> Result<()> {
+        match TargetRustVersion::current() {
+            Some(version) if !version.supports(self) => Error::err(
+                span,
+                format!(
+                    "Rust {} is required for {}, but the crate supports Rust {}",
+                    self.min_version(),
+                    self.description(),
+                    version,
+                ),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
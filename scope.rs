@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::thread::LocalKey;
+
+/// Set the thread-local `key` to `value` while running `f`, and restore the previous value
+/// afterwards, even if `f` panics. Shared by the `scoped` methods of the policies.
+pub(crate) fn scoped<T: 'static, R>(
+    key: &'static LocalKey<RefCell<T>>,
+    value: T,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Restore<T: 'static> {
+        key: &'static LocalKey<RefCell<T>>,
+        previous: Option<T>,
+    }
+    impl<T> Drop for Restore<T> {
+        fn drop(&mut self) {
+            if let Some(previous) = self.previous.take() {
+                self.key.set(previous);
+            }
+        }
+    }
+    let _restore = Restore {
+        key,
+        previous: Some(key.replace(value)),
+    };
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    thread_local! {
+        static VALUE: RefCell<u8> = const { RefCell::new(0) };
+    }
+
+    #[test]
+    fn restores_the_previous_value() {
+        scoped(&VALUE, 1, || {
+            scoped(&VALUE, 2, || assert_eq!(VALUE.with_borrow(|v| *v), 2));
+            assert_eq!(VALUE.with_borrow(|v| *v), 1);
+            let panicked = std::panic::catch_unwind(|| scoped(&VALUE, 3, || panic!("in f")));
+            assert!(panicked.is_err());
+            assert_eq!(VALUE.with_borrow(|v| *v), 1);
+        });
+        assert_eq!(VALUE.with_borrow(|v| *v), 0);
+    }
+}
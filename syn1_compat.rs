@@ -0,0 +1,54 @@
+#![allow(dead_code)]
+
+use super::{Error, Result};
+use quote::ToTokens;
+
+/// Convert a syn 1 value (or anything else that prints to tokens) to the syn 2 type `T`.
+///
+/// This crate is written against syn 2, so macro crates that still use syn 1 convert their types
+/// at the boundary. Both versions share `proc-macro2` and `quote`, so a value can be printed to
+/// tokens and parsed with the other version, which keeps all spans. Only available with the
+/// `syn1` feature, which adds the dependency `syn1 = { package = "syn", version = "1" }`.
+///
+/// There is no `syn2` feature: syn 2 is the implicit default and always used (unless `no-syn`
+/// removes it, along with this module). `syn1` doesn't replace it, it only adds syn 1 next to it
+/// for these conversions, so the two versions are never selected against each other.
+/// ```ignore
+/// let input = syn1::parse_macro_input!(input as syn1::DeriveInput);
+/// let values = schema().parse(&syn1_compat::attrs_to_syn2(&input.attrs)?)?;
+/// let input: syn::DeriveInput = syn1_compat::to_syn2(&input)?;
+/// ```
+pub(crate) fn to_syn2<T: syn::parse::Parse>(value: &impl ToTokens) -> Result<T> {
+    Ok(syn::parse2(value.to_token_stream())?)
+}
+
+/// Convert a syn 2 value to the syn 1 type `T`, e.g. to hand generated items back to code that
+/// still uses syn 1
+pub(crate) fn to_syn1<T: syn1::parse::Parse>(value: &impl ToTokens) -> Result<T> {
+    syn1::parse2(value.to_token_stream()).map_err(Error::from)
+}
+
+/// Convert syn 1 attributes (e.g. `DeriveInput::attrs` or `Field::attrs`) for `AttrSchema` and
+/// the other attribute helpers. Inner attributes (`#![...]`) stay inner attributes.
+pub(crate) fn attrs_to_syn2(attrs: &[syn1::Attribute]) -> Result<Vec<syn::Attribute>> {
+    attrs
+        .iter()
+        .map(|attr| {
+            let parser = match attr.style {
+                syn1::AttrStyle::Outer => syn::Attribute::parse_outer,
+                syn1::AttrStyle::Inner(_) => syn::Attribute::parse_inner,
+            };
+            let mut parsed = syn::parse::Parser::parse2(parser, attr.to_token_stream())?;
+            match parsed.pop() {
+                Some(parsed) => Ok(parsed),
+                None => Error::err_spanned(attr, "failed to convert the attribute"),
+            }
+        })
+        .collect()
+}
+
+impl From<syn1::Error> for Error {
+    fn from(err: syn1::Error) -> Self {
+        Error::from(err.to_compile_error())
+    }
+}
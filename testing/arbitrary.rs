@@ -0,0 +1,367 @@
+#![allow(dead_code)]
+
+use super::super::ident::PATH_KEYWORDS;
+use super::super::{Case, Edition};
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+use quote::{quote, ToTokens};
+
+/// Size limits for the generated inputs. Smaller limits find the same bugs faster and shrink to
+/// more readable failures.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ShapeLimits {
+    /// Maximum number of fields per struct or variant
+    pub fields: usize,
+    /// Maximum number of enum variants
+    pub variants: usize,
+    /// Maximum number of generic type parameters
+    pub generics: usize,
+    /// Maximum number of attributes per item, field or variant
+    pub attrs: usize,
+    /// Maximum nesting of types like `Vec<Option<T>>`
+    pub type_depth: u32,
+    /// Maximum nesting of groups in token streams
+    pub group_depth: u32,
+    /// Maximum number of token trees per stream or group
+    pub tokens: usize,
+}
+
+impl Default for ShapeLimits {
+    fn default() -> Self {
+        Self {
+            fields: 5,
+            variants: 4,
+            generics: 2,
+            attrs: 2,
+            type_depth: 3,
+            group_depth: 3,
+            tokens: 8,
+        }
+    }
+}
+
+fn ident_matching(pattern: &'static str) -> impl Strategy<Value = Ident> {
+    pattern.prop_filter_map("keyword", |name: String| {
+        (name != "_" && !Edition::LATEST.is_keyword(&name))
+            .then(|| Ident::new(&name, Span::call_site()))
+    })
+}
+
+/// A `snake_case` identifier that is not a keyword
+pub(crate) fn arb_ident() -> impl Strategy<Value = Ident> {
+    ident_matching("[a-z][a-z0-9_]{0,7}")
+}
+
+/// A `CamelCase` identifier that is not a keyword, for types, variants and generic parameters
+pub(crate) fn arb_type_ident() -> impl Strategy<Value = Ident> {
+    ident_matching("[A-Z][a-zA-Z0-9]{0,7}")
+}
+
+/// A raw identifier like `r#type` or `r#foo`. Keywords that can't be raw (`self`, ...) are
+/// excluded.
+pub(crate) fn arb_raw_ident() -> impl Strategy<Value = Ident> {
+    let keywords: Vec<&str> = Edition::LATEST
+        .keywords()
+        .into_iter()
+        .filter(|keyword| !PATH_KEYWORDS.contains(keyword))
+        .collect();
+    prop_oneof![
+        select(keywords).prop_map(String::from),
+        "[a-z][a-z0-9_]{0,7}",
+    ]
+    .prop_map(|name| Ident::new_raw(&name, Span::call_site()))
+}
+
+/// An identifier with non-ASCII letters, like `größe` or `λ_2`, already in NFC (see
+/// `ident::nfc`)
+pub(crate) fn arb_unicode_ident() -> impl Strategy<Value = Ident> {
+    ident_matching("[a-zà-öø-ÿα-ωа-я][a-z0-9_à-öø-ÿα-ωа-я]{0,7}")
+}
+
+/// Any of `arb_ident`, `arb_type_ident`, `arb_raw_ident` and `arb_unicode_ident`
+pub(crate) fn arb_any_ident() -> impl Strategy<Value = Ident> {
+    prop_oneof![
+        arb_ident(),
+        arb_type_ident(),
+        arb_raw_ident(),
+        arb_unicode_ident(),
+    ]
+}
+
+/// A name with 1 to `max_words` words in the given case, e.g. `http_server2` for `Case::Snake`.
+/// Words have at least two letters, since `PascalCase` like `AB` can't be split into `a_b` again.
+/// Such names round-trip between all cases except `Case::Lower` and `Case::Upper`, which lose the
+/// word boundaries, see `assert_case_round_trip`.
+pub(crate) fn arb_case_name(case: Case, max_words: usize) -> impl Strategy<Value = String> {
+    vec("[a-z]{2,6}[0-9]{0,2}", 1..=max_words.max(1))
+        .prop_map(move |words| case.convert(&words.join("_")))
+}
+
+/// Assert that converting `name` to `via` and back to `case` produces `name` again, e.g. that
+/// `snake_case` -> `PascalCase` -> `snake_case` doesn't lose or merge words
+/// ```ignore
+/// proptest! {
+///     #[test]
+///     fn snake_pascal_round_trip(name in arb_case_name(Case::Snake, 4)) {
+///         assert_case_round_trip(&name, Case::Snake, Case::Pascal);
+///     }
+/// }
+/// ```
+#[track_caller]
+pub(crate) fn assert_case_round_trip(name: &str, case: Case, via: Case) {
+    let converted = via.convert(name);
+    let back = case.convert(&converted);
+    assert_eq!(
+        back,
+        name,
+        "`{}` -> {} `{}` -> {} `{}`",
+        name,
+        via.name(),
+        converted,
+        case.name(),
+        back
+    );
+}
+
+/// An integer, float, string, byte string or char literal
+pub(crate) fn arb_literal() -> impl Strategy<Value = Literal> {
+    prop_oneof![
+        any::<u32>().prop_map(Literal::u32_unsuffixed),
+        any::<u64>().prop_map(Literal::u64_suffixed),
+        (0.0..1e6f64).prop_map(Literal::f64_unsuffixed),
+        any::<String>().prop_map(|s| Literal::string(&s)),
+        any::<Vec<u8>>().prop_map(|b| Literal::byte_string(&b)),
+        any::<char>().prop_map(Literal::character),
+    ]
+}
+
+/// A type built from primitives, `String`, the given type parameters and common wrappers
+pub(crate) fn arb_type(limits: ShapeLimits, params: Vec<Ident>) -> BoxedStrategy<syn::Type> {
+    type_tokens(limits, params)
+        .prop_map(|ty| syn::parse2(ty).expect("generated an invalid type"))
+        .boxed()
+}
+
+fn type_tokens(limits: ShapeLimits, params: Vec<Ident>) -> BoxedStrategy<TokenStream> {
+    let mut leaves: Vec<TokenStream> = ["u8", "i32", "u64", "usize", "bool", "char", "f64"]
+        .iter()
+        .map(|name| Ident::new(name, Span::call_site()).into_token_stream())
+        .collect();
+    leaves.push(quote!(String));
+    leaves.push(quote!(()));
+    leaves.extend(params.iter().map(|param| param.to_token_stream()));
+    select(leaves)
+        .prop_recursive(limits.type_depth, 32, 2, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|ty| quote!(Vec<#ty>)),
+                inner.clone().prop_map(|ty| quote!(Option<#ty>)),
+                inner.clone().prop_map(|ty| quote!(Box<#ty>)),
+                inner.clone().prop_map(|ty| quote!(&'static #ty)),
+                (inner.clone(), 0..8usize).prop_map(|(ty, len)| {
+                    let len = Literal::usize_unsuffixed(len);
+                    quote!([#ty; #len])
+                }),
+                vec(inner.clone(), 2..4).prop_map(|types| quote!((#(#types,)*))),
+                (inner.clone(), inner)
+                    .prop_map(|(k, v)| quote!(::std::collections::HashMap<#k, #v>)),
+            ]
+        })
+        .boxed()
+}
+
+/// An attribute: `#[name]`, `#[name = lit]` or `#[name(a, b = lit, c(...))]`
+pub(crate) fn arb_attribute() -> impl Strategy<Value = syn::Attribute> {
+    meta_tokens(2).prop_map(|meta| syn::parse_quote!(#[#meta]))
+}
+
+/// Up to `limits.attrs` attributes
+pub(crate) fn arb_attributes(limits: ShapeLimits) -> impl Strategy<Value = Vec<syn::Attribute>> {
+    vec(arb_attribute(), 0..=limits.attrs)
+}
+
+fn meta_tokens(depth: u32) -> BoxedStrategy<TokenStream> {
+    let leaf = prop_oneof![
+        arb_ident().prop_map(|name| quote!(#name)),
+        (arb_ident(), arb_literal()).prop_map(|(name, value)| quote!(#name = #value)),
+    ];
+    leaf.prop_recursive(depth, 16, 4, |inner| {
+        (arb_ident(), vec(inner, 0..4)).prop_map(|(name, items)| quote!(#name(#(#items),*)))
+    })
+    .boxed()
+}
+
+/// Any token stream, including ones that are not valid Rust, for fuzzing parsers
+pub(crate) fn arb_token_stream(limits: ShapeLimits) -> BoxedStrategy<TokenStream> {
+    let puncts = select("+-*/%^!&|=<>@.,;:#$?~".chars().collect::<Vec<_>>());
+    let leaf = prop_oneof![
+        arb_ident().prop_map(TokenTree::Ident),
+        arb_type_ident().prop_map(TokenTree::Ident),
+        arb_literal().prop_map(TokenTree::Literal),
+        (puncts, any::<bool>()).prop_map(|(c, joint)| {
+            let spacing = if joint {
+                Spacing::Joint
+            } else {
+                Spacing::Alone
+            };
+            TokenTree::Punct(Punct::new(c, spacing))
+        }),
+    ];
+    let delimiters = select(vec![
+        Delimiter::Parenthesis,
+        Delimiter::Brace,
+        Delimiter::Bracket,
+    ]);
+    let tree = leaf.prop_recursive(limits.group_depth, 64, limits.tokens as u32, move |inner| {
+        (delimiters.clone(), vec(inner, 0..limits.tokens)).prop_map(|(delimiter, trees)| {
+            TokenTree::Group(Group::new(delimiter, trees.into_iter().collect()))
+        })
+    });
+    vec(tree, 0..limits.tokens)
+        .prop_map(|trees| trees.into_iter().collect())
+        .boxed()
+}
+
+/// A struct or enum with attributes, generics and fields of `arb_type`s. Every generated input is
+/// valid for `syn` (names are unique), but not necessarily for rustc (e.g. unused parameters).
+///
+/// `syn::DeriveInput` only implements `Debug` (which proptest needs) with `syn`'s `extra-traits`
+/// feature.
+/// ```ignore
+/// proptest! {
+///     #[test]
+///     fn derive_never_panics(input in arb_derive_input(ShapeLimits::default())) {
+///         let _ = derive_getters(input.into_token_stream());
+///     }
+/// }
+/// ```
+pub(crate) fn arb_derive_input(limits: ShapeLimits) -> BoxedStrategy<syn::DeriveInput> {
+    let vis = select(vec![quote!(), quote!(pub), quote!(pub(crate))]);
+    let attrs = attrs_tokens(limits);
+    (
+        attrs,
+        vis,
+        arb_type_ident(),
+        vec(arb_type_ident(), 0..=limits.generics),
+    )
+        .prop_flat_map(move |(attrs, vis, name, params)| {
+            let params = unique(params, &[&name]);
+            let ty = type_tokens(limits, params.clone());
+            let fields = fields_tokens(limits, ty);
+            let variant = (attrs_tokens(limits), arb_type_ident(), fields.clone());
+            let body = prop_oneof![
+                fields.prop_map(|fields| (false, vec![(TokenStream::new(), None, fields)])),
+                vec(variant, 0..=limits.variants).prop_map(|variants| {
+                    let variants = variants
+                        .into_iter()
+                        .map(|(attrs, name, fields)| (attrs, Some(name), fields));
+                    (true, unique_by_name(variants.collect()))
+                }),
+            ];
+            (Just((attrs, vis, name, params)), body)
+        })
+        .prop_map(|((attrs, vis, name, params), (is_enum, parts))| {
+            let generics = (!params.is_empty()).then(|| quote!(<#(#params),*>));
+            let tokens = if is_enum {
+                let variants = parts.iter().map(|(attrs, name, fields)| {
+                    let fields = fields.variant();
+                    quote!(#attrs #name #fields)
+                });
+                quote!(#attrs #vis enum #name #generics { #(#variants,)* })
+            } else {
+                let fields = parts[0].2.item();
+                quote!(#attrs #vis struct #name #generics #fields)
+            };
+            syn::parse2(tokens).expect("generated an invalid item")
+        })
+        .boxed()
+}
+
+fn attrs_tokens(limits: ShapeLimits) -> impl Strategy<Value = TokenStream> {
+    vec(meta_tokens(2), 0..=limits.attrs).prop_map(|metas| quote!(#(#[#metas])*))
+}
+
+#[derive(Clone, Debug)]
+enum FieldsTokens {
+    Named(Vec<(TokenStream, Ident, TokenStream)>),
+    Unnamed(Vec<(TokenStream, TokenStream)>),
+    Unit,
+}
+
+impl FieldsTokens {
+    /// The fields of a struct, including the `;` if needed
+    fn item(&self) -> TokenStream {
+        match self {
+            Self::Named(_) => self.variant(),
+            Self::Unnamed(_) | Self::Unit => {
+                let fields = self.variant();
+                quote!(#fields;)
+            }
+        }
+    }
+    /// The fields of an enum variant
+    fn variant(&self) -> TokenStream {
+        match self {
+            Self::Named(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(attrs, name, ty)| quote!(#attrs #name: #ty));
+                quote!({ #(#fields,)* })
+            }
+            Self::Unnamed(fields) => {
+                let fields = fields.iter().map(|(attrs, ty)| quote!(#attrs #ty));
+                quote!((#(#fields,)*))
+            }
+            Self::Unit => TokenStream::new(),
+        }
+    }
+}
+
+fn fields_tokens(
+    limits: ShapeLimits,
+    ty: BoxedStrategy<TokenStream>,
+) -> BoxedStrategy<FieldsTokens> {
+    let named = vec(
+        (attrs_tokens(limits), arb_ident(), ty.clone()),
+        0..=limits.fields,
+    )
+    .prop_map(|fields| {
+        let mut seen: Vec<Ident> = vec![];
+        let fields = fields
+            .into_iter()
+            .filter(|(_, name, _)| {
+                let new = !seen.contains(name);
+                seen.push(name.clone());
+                new
+            })
+            .collect();
+        FieldsTokens::Named(fields)
+    });
+    let unnamed =
+        vec((attrs_tokens(limits), ty), 0..=limits.fields).prop_map(FieldsTokens::Unnamed);
+    prop_oneof![named, unnamed, Just(FieldsTokens::Unit)].boxed()
+}
+
+/// The names without duplicates and without the excluded names
+fn unique(names: Vec<Ident>, excluded: &[&Ident]) -> Vec<Ident> {
+    let mut output: Vec<Ident> = vec![];
+    for name in names {
+        if !output.contains(&name) && !excluded.contains(&&name) {
+            output.push(name);
+        }
+    }
+    output
+}
+
+type Part = (TokenStream, Option<Ident>, FieldsTokens);
+
+fn unique_by_name(parts: Vec<Part>) -> Vec<Part> {
+    let mut output: Vec<Part> = vec![];
+    for part in parts {
+        if !output.iter().any(|other| other.1 == part.1) {
+            output.push(part);
+        }
+    }
+    output
+}
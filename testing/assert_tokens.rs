@@ -0,0 +1,144 @@
+#![allow(dead_code)]
+
+use super::super::tokens;
+use proc_macro2::TokenStream;
+
+/// Assert that two token streams are structurally equal (see `tokens::eq_transparent`), ignoring
+/// spans and formatting. A failure shows the first difference and a side-by-side diff of the two
+/// streams, formatted with `tokens::format_generated`.
+///
+/// Accepts anything that implements `ToTokens`, and an optional message like `assert_eq!`. The
+/// items of this module have to be in scope (`use utils::testing::*;`).
+/// ```ignore
+/// assert_tokens_eq!(builder.build(), quote!(fn foo() {}));
+/// assert_tokens_eq!(output, expected, "for input `{}`", input);
+/// ```
+#[allow(unused_macros)]
+macro_rules! assert_tokens_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        assert_tokens_eq_impl(
+            ::quote::ToTokens::to_token_stream(&$actual),
+            ::quote::ToTokens::to_token_stream(&$expected),
+            ::core::option::Option::None,
+        )
+    };
+    ($actual:expr, $expected:expr, $($message:tt)+) => {
+        assert_tokens_eq_impl(
+            ::quote::ToTokens::to_token_stream(&$actual),
+            ::quote::ToTokens::to_token_stream(&$expected),
+            ::core::option::Option::Some(::std::format!($($message)+)),
+        )
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use assert_tokens_eq;
+
+/// The implementation of `assert_tokens_eq!`
+#[track_caller]
+pub(crate) fn assert_tokens_eq_impl(
+    actual: TokenStream,
+    expected: TokenStream,
+    message: Option<String>,
+) {
+    if let Err(report) = compare_tokens(actual, expected) {
+        match message {
+            Some(message) => panic!("{}\n{}", message, report),
+            None => panic!("{}", report),
+        }
+    }
+}
+
+/// Compare two streams like `assert_tokens_eq!`, returning the failure report instead of
+/// panicking
+pub(crate) fn compare_tokens(
+    actual: TokenStream,
+    expected: TokenStream,
+) -> std::result::Result<(), String> {
+    let actual = tokens::flatten_none_groups(actual);
+    let expected = tokens::flatten_none_groups(expected);
+    match tokens::diff(expected.clone(), actual.clone()) {
+        None => Ok(()),
+        Some(diff) => Err(format!(
+            "{}\n\n{}",
+            diff,
+            side_by_side(
+                "expected",
+                &tokens::format_generated(expected),
+                "actual",
+                &tokens::format_generated(actual)
+            )
+        )),
+    }
+}
+
+/// A line diff of two texts in two columns. Lines that only exist on one side are marked with `-`
+/// (left) or `+` (right), changed lines with `~`.
+pub(crate) fn side_by_side(left_title: &str, left: &str, right_title: &str, right: &str) -> String {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+
+    // longest common subsequence, lengths[i][j] is for left[i..] and right[j..]
+    let mut lengths = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lengths[i][j] = if left[i] == right[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut rows: Vec<Row> = vec![];
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added) = (vec![], vec![]);
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            flush_changes(&mut rows, &mut removed, &mut added);
+            rows.push((' ', left[i], right[j]));
+            i += 1;
+            j += 1;
+        } else if j == right.len() || (i < left.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            removed.push(left[i]);
+            i += 1;
+        } else {
+            added.push(right[j]);
+            j += 1;
+        }
+    }
+    flush_changes(&mut rows, &mut removed, &mut added);
+
+    let width = rows
+        .iter()
+        .map(|(_, l, _)| l.chars().count())
+        .chain([left_title.chars().count()])
+        .max()
+        .unwrap_or(0);
+    let mut output = format!("  {:width$} | {}\n", left_title, right_title, width = width);
+    for (marker, l, r) in rows {
+        let line = format!("{} {:width$} | {}", marker, l, r, width = width);
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// A row of `side_by_side`: the marker, the left line and the right line
+type Row<'a> = (char, &'a str, &'a str);
+
+/// Pair up the lines of a changed section
+fn flush_changes<'a>(
+    rows: &mut Vec<Row<'a>>,
+    removed: &mut Vec<&'a str>,
+    added: &mut Vec<&'a str>,
+) {
+    for k in 0..removed.len().max(added.len()) {
+        match (removed.get(k), added.get(k)) {
+            (Some(l), Some(r)) => rows.push(('~', l, r)),
+            (Some(l), None) => rows.push(('-', l, "")),
+            (None, Some(r)) => rows.push(('+', "", r)),
+            (None, None) => {}
+        }
+    }
+    removed.clear();
+    added.clear();
+}
@@ -0,0 +1,257 @@
+#![allow(dead_code)]
+
+use super::super::tokens;
+use super::{Expansion, UPDATE_SNAPSHOTS_VAR};
+use proc_macro2::TokenStream;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Time differences below this are noise and never count as a regression
+const MIN_TIME_REGRESSION: Duration = Duration::from_micros(50);
+
+/// The measurements of one input, from `ExpansionBench::run`
+#[derive(Clone, Debug)]
+pub(crate) struct BenchResult {
+    /// The name of the input, which can't contain whitespace
+    pub name: String,
+    /// Median wall time of one expansion
+    pub time: Duration,
+    /// Number of token trees in the output, see `tokens::stats`
+    pub tokens: usize,
+}
+
+/// The results of `ExpansionBench::run`, displayed as a table
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BenchReport {
+    /// The measurements, in the order of the inputs
+    pub results: Vec<BenchResult>,
+    /// Descriptions of the inputs that got slower or bigger than the baseline allows
+    pub regressions: Vec<String>,
+}
+
+impl BenchReport {
+    /// Panic with the report if there are regressions
+    #[track_caller]
+    pub fn assert_no_regressions(&self) {
+        if !self.regressions.is_empty() {
+            panic!(
+                "expansion regressions:\n  {}\n\n{}",
+                self.regressions.join("\n  "),
+                self
+            );
+        }
+    }
+}
+
+impl Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .results
+            .iter()
+            .map(|result| result.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("input".len());
+        writeln!(
+            f,
+            "{:width$} {:>12} {:>8}",
+            "input",
+            "time",
+            "tokens",
+            width = width
+        )?;
+        for result in &self.results {
+            writeln!(
+                f,
+                "{:width$} {:>12} {:>8}",
+                result.name,
+                format!("{:?}", result.time),
+                result.tokens,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Measures how long a macro implementation takes for a set of inputs and how much code it
+/// produces, and compares that to a baseline file.
+///
+/// The baseline is a text file with one `name tokens nanoseconds` line per input. A missing
+/// baseline is created, and `UPDATE_EXPANSIONS=1` overwrites it. Token counts are exact, so by
+/// default every increase is a regression. Times depend on the machine and the build profile, so
+/// they only count if they are more than `time_tolerance` (default 100%) slower. Run the
+/// benchmarks with `--release` for meaningful times.
+/// ```ignore
+/// #[test]
+/// #[ignore = "benchmark"]
+/// fn expansion_overhead() {
+///     let inputs = (0..100).map(|i| (format!("struct_{}", i), make_struct(i)));
+///     ExpansionBench::new()
+///         .iterations(20)
+///         .run(inputs, derive_getters)
+///         .assert_no_regressions();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct ExpansionBench {
+    baseline: Option<PathBuf>,
+    iterations: usize,
+    time_tolerance: f64,
+    token_tolerance: f64,
+}
+
+impl Default for ExpansionBench {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExpansionBench {
+    /// 10 iterations with the baseline `tests/expansion_bench.txt` of `CARGO_MANIFEST_DIR`
+    pub fn new() -> Self {
+        let root = std::env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from);
+        Self {
+            baseline: Some(root.join("tests").join("expansion_bench.txt")),
+            iterations: 10,
+            time_tolerance: 1.0,
+            token_tolerance: 0.0,
+        }
+    }
+
+    /// Set the baseline file, or `None` to only measure
+    pub fn baseline(mut self, path: Option<PathBuf>) -> Self {
+        self.baseline = path;
+        self
+    }
+    /// Set how often every input is expanded. The median time is reported.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+    /// Set how much slower than the baseline an input may get, as a fraction (`0.5` = 50%)
+    pub fn time_tolerance(mut self, tolerance: f64) -> Self {
+        self.time_tolerance = tolerance;
+        self
+    }
+    /// Set how much bigger than the baseline the output may get, as a fraction
+    pub fn token_tolerance(mut self, tolerance: f64) -> Self {
+        self.token_tolerance = tolerance;
+        self
+    }
+
+    /// Expand every input and compare the results to the baseline. Panics if the baseline file
+    /// can't be written.
+    pub fn run<N, E>(
+        &self,
+        inputs: impl IntoIterator<Item = (N, TokenStream)>,
+        mut macro_fn: impl FnMut(TokenStream) -> E,
+    ) -> BenchReport
+    where
+        N: Into<String>,
+        E: Expansion,
+    {
+        let mut report = BenchReport::default();
+        for (name, input) in inputs {
+            let mut times = vec![];
+            let mut tokens = 0;
+            for _ in 0..self.iterations {
+                let input = input.clone();
+                let start = Instant::now();
+                let output = macro_fn(input).into_expansion();
+                times.push(start.elapsed());
+                tokens = tokens::stats(&output).tokens;
+            }
+            times.sort();
+            report.results.push(BenchResult {
+                name: name.into(),
+                time: times[times.len() / 2],
+                tokens,
+            });
+        }
+
+        let Some(path) = &self.baseline else {
+            return report;
+        };
+        let update = std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some_and(|value| value != "0");
+        match std::fs::read_to_string(path) {
+            Ok(baseline) if !update => {
+                report.regressions = self.regressions(&parse_baseline(&baseline), &report.results);
+            }
+            _ => {
+                let mut text = String::new();
+                for result in &report.results {
+                    text.push_str(&format!(
+                        "{} {} {}\n",
+                        result.name,
+                        result.tokens,
+                        result.time.as_nanos()
+                    ));
+                }
+                if let Err(e) = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| std::fs::write(path, text))
+                {
+                    panic!("failed to write baseline {}: {}", path.display(), e);
+                }
+            }
+        }
+        report
+    }
+
+    fn regressions(
+        &self,
+        baseline: &BTreeMap<String, (usize, Duration)>,
+        results: &[BenchResult],
+    ) -> Vec<String> {
+        let mut regressions = vec![];
+        for result in results {
+            let Some((tokens, time)) = baseline.get(&result.name) else {
+                continue;
+            };
+            if result.tokens as f64 > *tokens as f64 * (1.0 + self.token_tolerance) {
+                regressions.push(format!(
+                    "{}: {} tokens instead of {}",
+                    result.name, result.tokens, tokens
+                ));
+            }
+            let slower = result.time.saturating_sub(*time);
+            if result.time.as_secs_f64() > time.as_secs_f64() * (1.0 + self.time_tolerance)
+                && slower > MIN_TIME_REGRESSION
+            {
+                regressions.push(format!(
+                    "{}: took {:?} instead of {:?}",
+                    result.name, result.time, time
+                ));
+            }
+        }
+        regressions
+    }
+}
+
+/// Measure the inputs with the default `ExpansionBench`
+pub(crate) fn bench_expansion<N, E>(
+    inputs: impl IntoIterator<Item = (N, TokenStream)>,
+    macro_fn: impl FnMut(TokenStream) -> E,
+) -> BenchReport
+where
+    N: Into<String>,
+    E: Expansion,
+{
+    ExpansionBench::new().run(inputs, macro_fn)
+}
+
+fn parse_baseline(text: &str) -> BTreeMap<String, (usize, Duration)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?.to_string();
+            let tokens = parts.next()?.parse().ok()?;
+            let nanos = parts.next()?.parse().ok()?;
+            Some((name, (tokens, Duration::from_nanos(nanos))))
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+#![allow(dead_code)]
+
+use super::super::{tokens, Edition};
+use super::Expansion;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Compiles generated code with `rustc --emit=metadata` (type checking, no codegen) to check that
+/// it actually compiles, which snapshots can't tell. The expansion is formatted and comes first in
+/// the checked file, so the line numbers in rustc's diagnostics are the lines of the expansion.
+/// The prelude is appended after it and can only contain items, e.g. `use`s or the types that the
+/// macro was invoked on.
+///
+/// Only available with the `compile-check` feature, since it needs a toolchain at test time. Uses
+/// `$RUSTC` or the `rustc` on the `PATH`, and a directory in `std::env::temp_dir`. Unused
+/// code is allowed, since expansions in tests are rarely used.
+/// ```ignore
+/// let input = quote!(struct Foo { a: u8 });
+/// CompileCheck::new()
+///     .prelude(&input.to_string())
+///     .assert(derive_getters(input));
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct CompileCheck {
+    prelude: String,
+    edition: Edition,
+    args: Vec<String>,
+}
+
+impl Default for CompileCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompileCheck {
+    /// No prelude, with `Edition::current`
+    pub fn new() -> Self {
+        Self {
+            prelude: String::new(),
+            edition: Edition::current(),
+            args: vec![],
+        }
+    }
+
+    /// Set the code that is appended to the expansion
+    pub fn prelude(mut self, prelude: &str) -> Self {
+        self.prelude = prelude.to_string();
+        self
+    }
+    /// Set the edition to compile with
+    pub fn edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+    /// Add an argument for rustc, e.g. `--extern` or `-D warnings`
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Compile the expansion, returning rustc's diagnostics if it fails. Panics if rustc can't be
+    /// run.
+    pub fn check(&self, expansion: impl Expansion) -> Result<(), String> {
+        let code = tokens::format_generated(expansion.into_expansion());
+        let dir = std::env::temp_dir().join(format!(
+            "compile_check_{}_{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = dir.join("expansion.rs");
+        let result = self.compile(&dir, &file, &code);
+        let _ = std::fs::remove_dir_all(&dir);
+        result.map_err(|stderr| {
+            let stderr = stderr.replace(&file.display().to_string(), "expansion.rs");
+            format!("{}\n\nexpansion:\n{}", stderr.trim_end(), numbered(&code))
+        })
+    }
+
+    /// Panic with rustc's diagnostics if the expansion doesn't compile
+    #[track_caller]
+    pub fn assert(&self, expansion: impl Expansion) {
+        if let Err(diagnostics) = self.check(expansion) {
+            panic!("the generated code doesn't compile:\n{}", diagnostics);
+        }
+    }
+
+    /// rustc's stderr if it failed
+    fn compile(&self, dir: &Path, file: &Path, code: &str) -> Result<(), String> {
+        let source = format!("{}\n{}\n", code.trim_end(), self.prelude);
+        if let Err(e) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(file, source)) {
+            panic!("failed to write {}: {}", file.display(), e);
+        }
+        let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(&rustc)
+            .arg("--crate-type=lib")
+            .arg("--crate-name=compile_check")
+            .arg("--emit=metadata")
+            .arg(format!("--edition={}", self.edition.year()))
+            .arg("-Aunused")
+            .args(&self.args)
+            .arg("--out-dir")
+            .arg(dir)
+            .arg(file)
+            .output()
+            .unwrap_or_else(|e| panic!("failed to run {:?}: {}", rustc, e));
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+    }
+}
+
+/// Assert that the expansion compiles with the default `CompileCheck`
+#[track_caller]
+pub(crate) fn assert_compiles(expansion: impl Expansion) {
+    CompileCheck::new().assert(expansion)
+}
+
+fn numbered(code: &str) -> String {
+    let lines: Vec<&str> = code.lines().collect();
+    let width = lines.len().to_string().len();
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$} | {}\n", i + 1, line, width = width))
+        .collect()
+}
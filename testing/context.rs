@@ -0,0 +1,97 @@
+#![allow(dead_code)]
+
+use super::super::codegen::{LintPolicy, StdPolicy};
+use super::super::{ident, Edition, Toolchain};
+
+/// The settings that a real macro expansion gets from its environment, for running macro logic
+/// in unit tests.
+///
+/// Outside of rustc, `Edition::current` reads the manifest of the macro crate instead of the
+/// crate using the macro, so the output of a test can change with the macro crate's edition.
+/// This sets the edition and the policies to fixed values, so that a test exercises the same
+/// code paths as an expansion in a crate with those settings. `ident::gensym` names are
+/// reproducible inside of it, see `ident::deterministic`.
+///
+/// Span positions are only available if the `span-locations` feature (which has to enable
+/// `proc-macro2/span-locations`) is enabled for the tests, see `span_locations`.
+/// ```ignore
+/// let output = FakeMacroContext::new()
+///     .edition(Edition::E2021)
+///     .std_policy(StdPolicy::CoreAlloc)
+///     .run(|| derive_getters(input));
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct FakeMacroContext {
+    edition: Edition,
+    std_policy: StdPolicy,
+    lints: LintPolicy,
+    toolchain: Option<Toolchain>,
+}
+
+impl Default for FakeMacroContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeMacroContext {
+    /// `Edition::LATEST` with the default policies, without forcing a `Toolchain`
+    pub fn new() -> Self {
+        Self {
+            edition: Edition::LATEST,
+            std_policy: StdPolicy::default(),
+            lints: LintPolicy::default(),
+            toolchain: None,
+        }
+    }
+
+    /// Set the edition of the crate that the macro is pretending to be used in
+    pub fn edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+    /// Set `StdPolicy::current`
+    pub fn std_policy(mut self, policy: StdPolicy) -> Self {
+        self.std_policy = policy;
+        self
+    }
+    /// Set the default `LintPolicy`
+    pub fn lints(mut self, policy: LintPolicy) -> Self {
+        self.lints = policy;
+        self
+    }
+
+    /// Force the code paths for compilers without the nightly-only span APIs, see `Toolchain`
+    pub fn toolchain(mut self, toolchain: Toolchain) -> Self {
+        self.toolchain = Some(toolchain);
+        self
+    }
+
+    /// Run `f` with the settings. The previous settings are restored afterwards, even if `f`
+    /// panics.
+    pub fn run<R>(self, f: impl FnOnce() -> R) -> R {
+        let Self {
+            edition,
+            std_policy,
+            lints,
+            toolchain,
+        } = self;
+        let f =
+            || edition.scoped(|| std_policy.scoped(|| lints.scoped(|| ident::deterministic(f))));
+        match toolchain {
+            Some(toolchain) => toolchain.scoped(f),
+            None => f(),
+        }
+    }
+}
+
+/// Run `f` in the default `FakeMacroContext`
+pub(crate) fn in_fake_macro_context<R>(f: impl FnOnce() -> R) -> R {
+    FakeMacroContext::new().run(f)
+}
+
+/// Check if spans have line and column information in this build, i.e. if the `span-locations`
+/// feature is enabled. Tests that check positions can return early if it isn't.
+pub(crate) fn span_locations() -> bool {
+    cfg!(feature = "span-locations")
+}
@@ -0,0 +1,275 @@
+#![allow(dead_code)]
+
+use super::super::{codegen, tokens};
+use super::{error_messages, Expansion};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::VecDeque;
+use syn::punctuated::Punctuated;
+
+/// A code block from documentation
+#[derive(Clone, Debug)]
+pub(crate) struct DocExample {
+    /// The 1-based line of the opening fence in the docs
+    pub line: usize,
+    /// The code, including hidden `# ` lines (without the `# `)
+    pub code: String,
+}
+
+/// The Rust code blocks of documentation, for checking that the macro invocations in them still
+/// expand without errors. Blocks without a language or with `rust`, `ignore`, `no_run`,
+/// `should_panic` or `editionXXXX` are Rust; `compile_fail` blocks and other languages are skipped.
+/// ```ignore
+/// #[test]
+/// fn doc_examples_expand() {
+///     DocExamples::from_source(include_str!("../src/lib.rs"), "getters")
+///         .check_derive("Getters", derive_getters);
+///     DocExamples::from_source(include_str!("../src/lib.rs"), "my_attr")
+///         .check_attribute("my_attr", my_attr_impl);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DocExamples {
+    examples: Vec<DocExample>,
+}
+
+impl DocExamples {
+    /// The examples in the text of doc comments
+    pub fn from_docs(docs: &str) -> Self {
+        let mut examples = vec![];
+        let mut current: Option<(usize, bool, Vec<String>)> = None;
+        for (i, line) in docs.lines().enumerate() {
+            let trimmed = line.trim_start();
+            if let Some(info) = trimmed.strip_prefix("```") {
+                match current.take() {
+                    Some((line, true, code)) => examples.push(DocExample {
+                        line,
+                        code: code.join("\n"),
+                    }),
+                    Some(_) => {}
+                    None => current = Some((i + 1, is_rust(info), vec![])),
+                }
+            } else if let Some((_, _, code)) = &mut current {
+                code.push(unhide(trimmed).to_string());
+            }
+        }
+        Self { examples }
+    }
+    /// The examples in the doc comments of the attributes
+    pub fn from_attrs(attrs: &[syn::Attribute]) -> Self {
+        Self::from_docs(&codegen::original_docs(attrs))
+    }
+    /// The examples in the doc comments of the function or macro with the given name in a source
+    /// file, e.g. the entry point of a proc macro from `include_str!("../src/lib.rs")`. Panics if
+    /// the source can't be parsed or doesn't contain the function.
+    #[track_caller]
+    pub fn from_source(source: &str, name: &str) -> Self {
+        let file = syn::parse_file(source)
+            .unwrap_or_else(|e| panic!("failed to parse the source file: {}", e));
+        let attrs = file.items.iter().find_map(|item| match item {
+            syn::Item::Fn(f) if f.sig.ident == name => Some(&f.attrs),
+            syn::Item::Macro(m) if m.ident.as_ref().is_some_and(|ident| ident == name) => {
+                Some(&m.attrs)
+            }
+            _ => None,
+        });
+        match attrs {
+            Some(attrs) => Self::from_attrs(attrs),
+            None => panic!("the source file doesn't contain a function `{}`", name),
+        }
+    }
+
+    /// The Rust code blocks, in order
+    pub fn examples(&self) -> &[DocExample] {
+        &self.examples
+    }
+
+    /// Expand every `name!(...)` in the examples with `macro_fn`. Panics if an expansion fails
+    /// or if no example invokes the macro.
+    #[track_caller]
+    pub fn check_bang<E: Expansion>(&self, name: &str, mut macro_fn: impl FnMut(TokenStream) -> E) {
+        self.check(name, |code, invocations| {
+            find_bang_invocations(code, name, &mut |input| {
+                invocations.push(macro_fn(input).into_expansion());
+            });
+        });
+    }
+
+    /// Expand every item with `#[name]` or `#[name(...)]` in the examples with `macro_fn`, which
+    /// gets the arguments and the item without the attribute. Panics if an expansion fails or if
+    /// no example uses the attribute.
+    #[track_caller]
+    pub fn check_attribute<E: Expansion>(
+        &self,
+        name: &str,
+        mut macro_fn: impl FnMut(TokenStream, TokenStream) -> E,
+    ) {
+        self.check_items(name, |item, invocations| {
+            let Some(attrs) = item_attrs(item) else {
+                return;
+            };
+            let Some(index) = attrs.iter().position(|attr| path_is(attr.path(), name)) else {
+                return;
+            };
+            let attr = attrs.remove(index);
+            let args = match attr.meta {
+                syn::Meta::List(list) => list.tokens,
+                _ => TokenStream::new(),
+            };
+            invocations.push(macro_fn(args, item.to_token_stream()).into_expansion());
+        });
+    }
+
+    /// Expand every item with `#[derive(name)]` in the examples with `macro_fn`. Panics if an
+    /// expansion fails or if no example derives the macro.
+    #[track_caller]
+    pub fn check_derive<E: Expansion>(
+        &self,
+        name: &str,
+        mut macro_fn: impl FnMut(TokenStream) -> E,
+    ) {
+        self.check_items(name, |item, invocations| {
+            let Some(attrs) = item_attrs(item) else {
+                return;
+            };
+            let derives = attrs.iter().any(|attr| {
+                attr.path().is_ident("derive")
+                    && attr
+                        .parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+                        .is_ok_and(|paths| paths.iter().any(|path| path_is(path, name)))
+            });
+            if derives {
+                invocations.push(macro_fn(item.to_token_stream()).into_expansion());
+            }
+        });
+    }
+
+    #[track_caller]
+    fn check_items(
+        &self,
+        name: &str,
+        mut check: impl FnMut(&mut syn::Item, &mut Vec<TokenStream>),
+    ) {
+        self.check(name, |code, invocations| {
+            let Some(file) = parse_example(code) else {
+                invocations.push(quote::quote!(compile_error!("the example is not valid Rust");));
+                return;
+            };
+            let mut items = file.items;
+            let mut pending: VecDeque<&mut syn::Item> = items.iter_mut().collect();
+            while let Some(item) = pending.pop_front() {
+                check(item, invocations);
+                match item {
+                    syn::Item::Fn(f) => {
+                        pending.extend(f.block.stmts.iter_mut().filter_map(|stmt| match stmt {
+                            syn::Stmt::Item(item) => Some(item),
+                            _ => None,
+                        }))
+                    }
+                    syn::Item::Mod(m) => {
+                        pending.extend(m.content.iter_mut().flat_map(|(_, items)| items))
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    #[track_caller]
+    fn check(&self, name: &str, mut expand: impl FnMut(TokenStream, &mut Vec<TokenStream>)) {
+        let mut failures = vec![];
+        let mut used = false;
+        for example in &self.examples {
+            let Ok(code) = example.code.parse::<TokenStream>() else {
+                failures.push(format!("line {}: the example can't be lexed", example.line));
+                continue;
+            };
+            let mut invocations = vec![];
+            expand(code, &mut invocations);
+            used |= !invocations.is_empty();
+            for output in invocations {
+                for message in error_messages(&output) {
+                    failures.push(format!("line {}: {}", example.line, message));
+                }
+            }
+        }
+        if !used {
+            panic!("no doc example uses `{}`", name);
+        }
+        if !failures.is_empty() {
+            panic!(
+                "{} doc example errors for `{}`:\n  {}",
+                failures.len(),
+                name,
+                failures.join("\n  ")
+            );
+        }
+    }
+}
+
+fn is_rust(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attr| !attr.is_empty())
+        .all(|attr| {
+            matches!(attr, "rust" | "ignore" | "no_run" | "should_panic")
+                || attr.starts_with("edition")
+        })
+}
+
+/// Remove the `# ` of a line that rustdoc hides
+fn unhide(line: &str) -> &str {
+    if line == "#" {
+        ""
+    } else if line.starts_with("##") {
+        &line[1..]
+    } else {
+        line.strip_prefix("# ").unwrap_or(line)
+    }
+}
+
+/// Parse as items, or as the body of `fn main` like rustdoc does
+fn parse_example(code: TokenStream) -> Option<syn::File> {
+    syn::parse2(code.clone())
+        .or_else(|_| syn::parse2(quote::quote!(fn main() { #code })))
+        .ok()
+}
+
+fn find_bang_invocations(stream: TokenStream, name: &str, found: &mut impl FnMut(TokenStream)) {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(ident) if ident == name => {
+                if !tokens::is_punct_seq(&tokens[i + 1..], "!") {
+                    continue;
+                }
+                if let Some(TokenTree::Group(group)) = tokens.get(i + 2) {
+                    found(group.stream());
+                }
+            }
+            TokenTree::Group(group) => find_bang_invocations(group.stream(), name, found),
+            _ => {}
+        }
+    }
+}
+
+fn path_is(path: &syn::Path, name: &str) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+fn item_attrs(item: &mut syn::Item) -> Option<&mut Vec<syn::Attribute>> {
+    match item {
+        syn::Item::Const(i) => Some(&mut i.attrs),
+        syn::Item::Enum(i) => Some(&mut i.attrs),
+        syn::Item::Fn(i) => Some(&mut i.attrs),
+        syn::Item::Impl(i) => Some(&mut i.attrs),
+        syn::Item::Mod(i) => Some(&mut i.attrs),
+        syn::Item::Static(i) => Some(&mut i.attrs),
+        syn::Item::Struct(i) => Some(&mut i.attrs),
+        syn::Item::Trait(i) => Some(&mut i.attrs),
+        syn::Item::Type(i) => Some(&mut i.attrs),
+        syn::Item::Union(i) => Some(&mut i.attrs),
+        _ => None,
+    }
+}
@@ -0,0 +1,78 @@
+#![allow(dead_code)]
+
+use super::super::{lit, tokens, Error};
+use proc_macro2::{TokenStream, TokenTree};
+
+/// The messages of the `compile_error!` invocations in the tokens, in order
+pub(crate) fn error_messages(stream: &TokenStream) -> Vec<String> {
+    let mut messages = vec![];
+    collect_messages(stream.clone(), &mut messages);
+    messages
+}
+
+fn collect_messages(stream: TokenStream, messages: &mut Vec<String>) {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(ident) if ident == "compile_error" => {
+                if !tokens::is_punct_seq(&tokens[i + 1..], "!") {
+                    continue;
+                }
+                if let Some(TokenTree::Group(group)) = tokens.get(i + 2) {
+                    let message = group.stream().into_iter().find_map(|token| match token {
+                        TokenTree::Literal(literal) => lit::parse_str(&literal).ok(),
+                        _ => None,
+                    });
+                    messages.extend(message);
+                }
+            }
+            TokenTree::Group(group) => collect_messages(group.stream(), messages),
+            _ => {}
+        }
+    }
+}
+
+/// Assert that a macro implementation failed with the expected errors. Every entry of `expected`
+/// has to be a substring of exactly one of the error messages. Fails with a list of all messages
+/// if an entry matches none or several messages, or if the result is `Ok`.
+/// ```ignore
+/// let input: syn::DeriveInput = syn::parse_quote!(#[config(key, key = 1)] struct Foo;);
+/// expect_error(derive_config(input), &["expected `=`", "duplicate key"]);
+/// ```
+#[track_caller]
+pub(crate) fn expect_error<T, E: Into<Error>>(
+    result: std::result::Result<T, E>,
+    expected: &[&str],
+) {
+    let error = match result {
+        Ok(_) => panic!(
+            "expected the macro to fail with {:?}, but it succeeded",
+            expected
+        ),
+        Err(error) => TokenStream::from(error.into()),
+    };
+    let messages = error_messages(&error);
+    let mut problems = vec![];
+    for pattern in expected {
+        let count = messages
+            .iter()
+            .filter(|message| message.contains(pattern))
+            .count();
+        match count {
+            1 => {}
+            0 => problems.push(format!("no error contains {:?}", pattern)),
+            n => problems.push(format!("{} errors contain {:?}", n, pattern)),
+        }
+    }
+    if problems.is_empty() {
+        return;
+    }
+    let mut emitted = String::new();
+    for message in &messages {
+        emitted.push_str(&format!("\n  - {:?}", message));
+    }
+    if messages.is_empty() {
+        emitted = " none".to_string();
+    }
+    panic!("{}\nemitted errors:{}", problems.join("\n"), emitted);
+}
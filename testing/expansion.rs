@@ -0,0 +1,130 @@
+#![allow(dead_code)]
+
+use super::super::{tokens, Error};
+use super::compare_tokens;
+use proc_macro2::TokenStream;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that makes `assert_snapshot` overwrite existing snapshots
+pub(crate) const UPDATE_SNAPSHOTS_VAR: &str = "UPDATE_EXPANSIONS";
+
+/// Assert that a macro implementation expands the input to the expected tokens, or to the
+/// contents of a snapshot file.
+///
+/// The first argument is the implementation (a function or closure taking a `TokenStream`,
+/// returning a `TokenStream` or a `Result<TokenStream>`). The input and expected output are
+/// written as tokens in braces. The tokens are compared like `assert_tokens_eq!`.
+///
+/// The items of this module have to be in scope (`use utils::testing::*;`).
+/// ```ignore
+/// assert_expansion!(derive_getters, { struct Foo { a: u8 } }, {
+///     impl Foo { pub fn a(&self) -> &u8 { &self.a } }
+/// });
+/// // compares to tests/expansions/getters_basic.expanded.rs, see `assert_snapshot`
+/// assert_expansion!(derive_getters, { struct Foo { a: u8 } }, snapshot = "getters_basic");
+/// ```
+#[allow(unused_macros)]
+macro_rules! assert_expansion {
+    ($implementation:expr, { $($input:tt)* }, snapshot = $name:expr $(,)?) => {
+        assert_snapshot($name, ($implementation)(expansion_tokens(stringify!($($input)*))))
+    };
+    ($implementation:expr, { $($input:tt)* }, { $($expected:tt)* } $(,)?) => {
+        assert_expansion_eq(
+            ($implementation)(expansion_tokens(stringify!($($input)*))),
+            expansion_tokens(stringify!($($expected)*)),
+        )
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use assert_expansion;
+
+/// The output of a macro implementation, for `assert_expansion!`
+pub(crate) trait Expansion {
+    /// The output tokens, with errors turned into `compile_error!` invocations
+    fn into_expansion(self) -> TokenStream;
+}
+
+impl Expansion for TokenStream {
+    fn into_expansion(self) -> TokenStream {
+        self
+    }
+}
+
+impl<E: Into<Error>> Expansion for std::result::Result<TokenStream, E> {
+    fn into_expansion(self) -> TokenStream {
+        self.unwrap_or_else(|e| e.into().into())
+    }
+}
+
+/// Parse the tokens passed to `assert_expansion!`
+pub(crate) fn expansion_tokens(source: &str) -> TokenStream {
+    source
+        .parse()
+        .expect("assert_expansion! contains invalid tokens")
+}
+
+/// Compare an expansion to the expected tokens like `assert_tokens_eq!`, returning a description
+/// of the difference
+pub(crate) fn check_expansion(
+    actual: impl Expansion,
+    expected: TokenStream,
+) -> std::result::Result<(), String> {
+    compare_tokens(actual.into_expansion(), expected)
+}
+
+/// Panic with the output of `check_expansion` if the expansion doesn't match
+#[track_caller]
+pub(crate) fn assert_expansion_eq(actual: impl Expansion, expected: TokenStream) {
+    if let Err(message) = check_expansion(actual, expected) {
+        panic!("{}", message);
+    }
+}
+
+/// Compare the formatted expansion to the snapshot `<name>.expanded.rs` in `tests/expansions`
+/// of the crate being tested, see `assert_snapshot_in`.
+#[track_caller]
+pub(crate) fn assert_snapshot(name: &str, actual: impl Expansion) {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from);
+    assert_snapshot_in(&root.join("tests").join("expansions"), name, actual);
+}
+
+/// Compare the formatted expansion to the snapshot `<name>.expanded.rs` in `dir`.
+///
+/// Missing snapshots are created, unless the `CI` environment variable is set, in which case
+/// they are an error. Setting `UPDATE_EXPANSIONS=1` overwrites snapshots that don't match
+/// instead of failing.
+#[track_caller]
+pub(crate) fn assert_snapshot_in(dir: &Path, name: &str, actual: impl Expansion) {
+    let path = dir.join(format!("{}.expanded.rs", name));
+    let actual = tokens::format_generated(actual.into_expansion());
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some_and(|value| value != "0");
+    let write = |reason: &str| {
+        if let Err(e) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, &actual)) {
+            panic!("failed to write snapshot {}: {}", path.display(), e);
+        }
+        eprintln!("{} snapshot {}", reason, path.display());
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if normalize(&expected) == normalize(&actual) => {}
+        Ok(_) if update => write("updated"),
+        Ok(expected) => panic!(
+            "expansion doesn't match snapshot {} (set {}=1 to update it)\n\nexpected:\n{}\nactual:\n{}",
+            path.display(),
+            UPDATE_SNAPSHOTS_VAR,
+            expected,
+            actual
+        ),
+        Err(_) if std::env::var_os("CI").is_some() && !update => panic!(
+            "snapshot {} is missing. Run the tests locally to create it, or set {}=1",
+            path.display(),
+            UPDATE_SNAPSHOTS_VAR
+        ),
+        Err(_) => write("created"),
+    }
+}
+
+/// Ignore line endings and trailing whitespace, which editors and git like to change
+fn normalize(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_end().to_string()
+}
@@ -0,0 +1,107 @@
+#![allow(dead_code)]
+
+use super::super::tokens;
+use proc_macro2::TokenStream;
+use syn::parse::{Parse, Parser};
+
+/// Parse a fixture for a unit test, panicking with the parse error shown in the numbered source.
+///
+/// With the `span-locations` feature (which has to enable `proc-macro2/span-locations`), the
+/// errors are marked at their line and column. Without it, only the message and the source are
+/// shown, except for unbalanced delimiters.
+/// ```ignore
+/// let input: syn::DeriveInput = parse_fixture(r#"
+///     #[config(key = 1)]
+///     struct Foo { a: u8 }
+/// "#);
+/// ```
+#[track_caller]
+pub(crate) fn parse_fixture<T: Parse>(source: &str) -> T {
+    parse_fixture_with(T::parse, source)
+}
+
+/// `parse_fixture` with a custom parser, e.g. `syn::Attribute::parse_outer` or
+/// `Punctuated::parse_terminated`
+#[track_caller]
+pub(crate) fn parse_fixture_with<P: Parser>(parser: P, source: &str) -> P::Output {
+    match parser.parse_str(source) {
+        Ok(value) => value,
+        Err(error) => panic!(
+            "failed to parse fixture as `{}`\n{}",
+            std::any::type_name::<P::Output>(),
+            render_parse_error(source, &error)
+        ),
+    }
+}
+
+/// The error messages and the source with line numbers, with markers under the lines of the
+/// errors if the `span-locations` feature is enabled. Unbalanced delimiters are always marked,
+/// using `tokens::check_delimiters`.
+pub(crate) fn render_parse_error(source: &str, error: &syn::Error) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let width = lines.len().max(1).to_string().len();
+    let mut markers: Vec<Vec<(usize, String)>> = vec![vec![]; lines.len()];
+    let mut unplaced = vec![];
+    let mut place = |line: usize, column: usize, message: String| {
+        if line >= 1 && line <= lines.len() {
+            markers[line - 1].push((column, message));
+        } else {
+            unplaced.push(message);
+        }
+    };
+    let delimiter_error = source
+        .parse::<TokenStream>()
+        .err()
+        .and_then(|_| tokens::check_delimiters(source).err());
+    if let Some(delimiter_error) = delimiter_error {
+        place(
+            delimiter_error.line,
+            delimiter_error.column - 1,
+            delimiter_error.message,
+        );
+    } else {
+        for error in error.clone() {
+            let message = error.to_string();
+            if message.starts_with("unexpected end of input") {
+                // the span of this error is the call site, not the end
+                let column = lines.last().map_or(0, |line| line.chars().count());
+                place(lines.len(), column, message);
+            } else if let Some((line, column)) = location(&error) {
+                place(line, column, message);
+            } else {
+                place(0, 0, message);
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for message in &unplaced {
+        output.push_str(&format!("error: {}\n", message));
+    }
+    for (i, line) in lines.iter().enumerate() {
+        output.push_str(&format!("{:>width$} | {}\n", i + 1, line, width = width));
+        for (column, message) in &markers[i] {
+            output.push_str(&format!(
+                "{:>width$} | {:column$}^ {}\n",
+                "",
+                "",
+                message,
+                width = width,
+                column = column
+            ));
+        }
+    }
+    output
+}
+
+/// 1-based line and 0-based column of the start of the error
+#[cfg(feature = "span-locations")]
+fn location(error: &syn::Error) -> Option<(usize, usize)> {
+    let start = error.span().start();
+    Some((start.line, start.column))
+}
+
+#[cfg(not(feature = "span-locations"))]
+fn location(_: &syn::Error) -> Option<(usize, usize)> {
+    None
+}
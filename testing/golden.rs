@@ -0,0 +1,193 @@
+#![allow(dead_code)]
+
+use super::super::tokens;
+use super::{compare_tokens, Expansion, UPDATE_SNAPSHOTS_VAR};
+use proc_macro2::TokenStream;
+use std::path::{Path, PathBuf};
+
+/// Compares expansions against committed golden files `<name>.expanded.rs`, by default in
+/// `tests/expand` of the crate being tested.
+///
+/// Unlike `assert_snapshot`, a missing golden is a failure, and all checks of a `GoldenFiles`
+/// are reported together when it is dropped (or `finish`ed). Goldens are compared as tokens, so a
+/// different formatter version doesn't break them, and every expansion is checked to survive
+/// formatting and parsing again unchanged. With `UPDATE_EXPANSIONS=1`, goldens are (re)written
+/// instead, including ones that only differ in formatting.
+///
+/// `sorted(true)` sorts the top-level items with `tokens::sort_items_by_name` first, so that
+/// expansions built from unordered data produce the same golden every time.
+/// ```ignore
+/// let mut goldens = GoldenFiles::new().sorted(true);
+/// for (name, input) in [("unit", quote!(struct A;)), ("named", quote!(struct B { x: u8 }))] {
+///     goldens.check(name, derive_getters(input));
+/// }
+/// goldens.finish();
+/// ```
+pub(crate) struct GoldenFiles {
+    dir: PathBuf,
+    sorted: bool,
+    checked: Vec<String>,
+    failures: Vec<String>,
+    finished: bool,
+}
+
+impl Default for GoldenFiles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GoldenFiles {
+    /// Goldens in `tests/expand` of `CARGO_MANIFEST_DIR`
+    pub fn new() -> Self {
+        let root = std::env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from);
+        Self::in_dir(root.join("tests").join("expand"))
+    }
+    /// Goldens in another directory
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            sorted: false,
+            checked: vec![],
+            failures: vec![],
+            finished: false,
+        }
+    }
+    /// Sort the top-level items of every expansion by name before comparing them
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// The path of the golden with the given name
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.expanded.rs", name))
+    }
+
+    /// Compare an expansion to its golden. Failures are collected and reported by `finish`.
+    pub fn check(&mut self, name: &str, expansion: impl Expansion) -> &mut Self {
+        let path = self.path(name);
+        if self.checked.iter().any(|checked| checked == name) {
+            self.failures
+                .push(format!("{}: checked more than once", path.display()));
+            return self;
+        }
+        self.checked.push(name.to_string());
+        if let Err(failure) = self.compare(&path, expansion.into_expansion()) {
+            self.failures
+                .push(format!("{}: {}", path.display(), failure));
+        }
+        self
+    }
+
+    fn compare(&self, path: &Path, mut expansion: TokenStream) -> std::result::Result<(), String> {
+        if self.sorted {
+            expansion = tokens::sort_items_by_name(expansion);
+        }
+        let formatted = tokens::format_generated(expansion.clone());
+        match formatted.parse() {
+            Ok(reparsed) => compare_tokens(reparsed, expansion.clone())
+                .map_err(|report| format!("the expansion changes when formatted\n{}", report))?,
+            Err(_) => return Err("the formatted expansion can't be parsed".to_string()),
+        }
+
+        let update = std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some_and(|value| value != "0");
+        let write = || {
+            std::fs::create_dir_all(&self.dir)
+                .and_then(|_| std::fs::write(path, &formatted))
+                .map_err(|e| format!("failed to write golden: {}", e))
+        };
+        let Ok(golden) = std::fs::read_to_string(path) else {
+            if update {
+                return write();
+            }
+            return Err(format!(
+                "golden is missing, run with {}=1 to create it",
+                UPDATE_SNAPSHOTS_VAR
+            ));
+        };
+        let Ok(golden_tokens) = golden.parse::<TokenStream>() else {
+            if update {
+                return write();
+            }
+            return Err("golden can't be parsed".to_string());
+        };
+        match compare_tokens(expansion, golden_tokens) {
+            Ok(()) if update && golden != formatted => write(),
+            Ok(()) => Ok(()),
+            Err(_) if update => write(),
+            Err(report) => Err(format!(
+                "expansion differs from the golden (run with {}=1 to update it)\n{}",
+                UPDATE_SNAPSHOTS_VAR, report
+            )),
+        }
+    }
+
+    /// Panic with all failures, if there are any
+    #[track_caller]
+    pub fn finish(&mut self) {
+        self.finished = true;
+        self.report();
+    }
+
+    #[track_caller]
+    fn report(&self) {
+        if !self.failures.is_empty() {
+            panic!(
+                "{} golden checks failed\n\n{}",
+                self.failures.len(),
+                self.failures.join("\n\n")
+            );
+        }
+    }
+}
+
+impl Drop for GoldenFiles {
+    fn drop(&mut self) {
+        if !self.finished && !std::thread::panicking() {
+            self.finished = true;
+            self.report();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn sorted_with_where_clauses_and_generic_defaults() {
+        let dir = std::env::temp_dir().join(format!("golden_sorted_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let golden = quote! {
+            struct B<T = u8>(T);
+            fn a() where u8: Into<u16>, {}
+            fn c() {}
+            impl<T> Foo for T where T: Iterator<Item = u8>, {}
+        };
+        // the formatter ends where clauses with a `,`, so expansions without it don't survive
+        // formatting unchanged
+        std::fs::write(dir.join("items.expanded.rs"), golden.to_string()).unwrap();
+
+        let forwards = quote! {
+            impl<T> Foo for T where T: Iterator<Item = u8>, {}
+            fn c() {}
+            struct B<T = u8>(T);
+            fn a() where u8: Into<u16>, {}
+        };
+        let backwards = quote! {
+            struct B<T = u8>(T);
+            fn a() where u8: Into<u16>, {}
+            impl<T> Foo for T where T: Iterator<Item = u8>, {}
+            fn c() {}
+        };
+        for expansion in [forwards, backwards] {
+            GoldenFiles::in_dir(&dir)
+                .sorted(true)
+                .check("items", expansion)
+                .finish();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+#![allow(dead_code)]
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
+mod assert_tokens;
+mod bench;
+#[cfg(feature = "compile-check")]
+mod compile_check;
+mod context;
+mod doc_examples;
+mod errors;
+mod expansion;
+mod fixture;
+mod golden;
+mod schema_coverage;
+#[cfg(feature = "span-locations")]
+mod source;
+mod structure;
+mod ui;
+#[cfg(feature = "proptest")]
+pub(crate) use arbitrary::*;
+pub(crate) use assert_tokens::*;
+pub(crate) use bench::*;
+#[cfg(feature = "compile-check")]
+pub(crate) use compile_check::*;
+pub(crate) use context::*;
+pub(crate) use doc_examples::*;
+pub(crate) use errors::*;
+pub(crate) use expansion::*;
+pub(crate) use fixture::*;
+pub(crate) use golden::*;
+pub(crate) use schema_coverage::*;
+#[cfg(feature = "span-locations")]
+pub(crate) use source::*;
+pub(crate) use structure::*;
+pub(crate) use ui::*;
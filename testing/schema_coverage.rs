@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use super::super::{AttrSchema, AttrValues};
+use std::collections::BTreeSet;
+
+/// Checks that the tests parse every key of an `AttrSchema`, including every alias, so that an
+/// option that silently stopped working (or was never tested) is noticed.
+/// ```ignore
+/// #[test]
+/// fn all_options_are_tested() {
+///     let mut coverage = SchemaCoverage::new(&field_schema());
+///     for fixture in FIELD_FIXTURES {
+///         let field: syn::Field = parse_fixture_with(syn::Field::parse_named, fixture);
+///         coverage.record(&field_schema().parse(&field.attrs).unwrap());
+///     }
+///     coverage.assert_covered();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct SchemaCoverage {
+    schema: AttrSchema,
+    exercised: BTreeSet<String>,
+}
+
+impl SchemaCoverage {
+    /// Start with no exercised keys
+    pub fn new(schema: &AttrSchema) -> Self {
+        Self {
+            schema: schema.clone(),
+            exercised: BTreeSet::new(),
+        }
+    }
+
+    /// Mark the keys of parsed values as exercised, with the spelling they were written in
+    pub fn record(&mut self, values: &AttrValues) -> &mut Self {
+        for key in values.written_keys() {
+            self.exercised.insert(key.to_string());
+        }
+        self
+    }
+    /// Mark a key or alias as exercised, e.g. from a list kept next to the tests
+    pub fn exercise(&mut self, key: &str) -> &mut Self {
+        self.exercised.insert(key.to_string());
+        self
+    }
+
+    /// The keys and aliases of the schema that were never exercised, in schema order
+    pub fn missing(&self) -> Vec<&str> {
+        self.schema
+            .keys()
+            .iter()
+            .flat_map(|key| key.spellings())
+            .filter(|spelling| !self.exercised.contains(*spelling))
+            .collect()
+    }
+    /// The exercised keys that are not part of the schema, e.g. after a key was renamed
+    pub fn unknown(&self) -> Vec<&str> {
+        self.exercised
+            .iter()
+            .map(String::as_str)
+            .filter(|key| self.schema.find(key).is_none())
+            .collect()
+    }
+
+    /// Panic with the missing and unknown keys, if there are any
+    #[track_caller]
+    pub fn assert_covered(&self) {
+        let (missing, unknown) = (self.missing(), self.unknown());
+        if missing.is_empty() && unknown.is_empty() {
+            return;
+        }
+        let mut message = format!("`#[{}]` is not fully tested", self.schema.attr_name());
+        if !missing.is_empty() {
+            message.push_str(&format!("\nnever parsed: {}", missing.join(", ")));
+        }
+        if !unknown.is_empty() {
+            message.push_str(&format!("\nnot in the schema: {}", unknown.join(", ")));
+        }
+        panic!("{}", message);
+    }
+}
+
+/// Check that `exercised` contains every key and alias of the schema, and nothing else
+#[track_caller]
+pub(crate) fn assert_schema_coverage(schema: &AttrSchema, exercised: &[&str]) {
+    let mut coverage = SchemaCoverage::new(schema);
+    for key in exercised {
+        coverage.exercise(key);
+    }
+    coverage.assert_covered();
+}
@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+
+use super::super::join_spans;
+use proc_macro2::{Span, TokenStream, TokenTree};
+
+/// Tokens lexed from a string, whose spans have real source text, lines and columns, for testing
+/// code that looks at `Span::source_text` or `Span::start`. Only available with the
+/// `span-locations` feature (which has to enable `proc-macro2/span-locations`), since spans
+/// outside of rustc have neither otherwise.
+/// ```ignore
+/// let source = FakeSource::at(10, 4, "let x = foo(1, 2);");
+/// let call = source.span_of("foo(1, 2)");
+/// assert_eq!(call.source_text().as_deref(), Some("foo(1, 2)"));
+/// assert_eq!(call.start().line, 10);
+/// let output = my_macro_impl(source.tokens());
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct FakeSource {
+    text: String,
+    padding: usize,
+    tokens: TokenStream,
+}
+
+impl FakeSource {
+    /// Lex the text, starting at line 1, column 0. Panics if it can't be lexed.
+    pub fn new(text: &str) -> Self {
+        Self::at(1, 0, text)
+    }
+    /// Lex the text as if it started at the given 1-based line and 0-based column (the
+    /// convention of `LineColumn`). Panics if it can't be lexed.
+    pub fn at(line: usize, column: usize, text: &str) -> Self {
+        let padded = format!(
+            "{}{}{}",
+            "\n".repeat(line.saturating_sub(1)),
+            " ".repeat(column),
+            text
+        );
+        let tokens = padded
+            .parse()
+            .unwrap_or_else(|e| panic!("FakeSource: can't lex {:?}: {}", text, e));
+        Self {
+            text: text.to_string(),
+            padding: padded.len() - text.len(),
+            tokens,
+        }
+    }
+
+    /// The lexed tokens
+    pub fn tokens(&self) -> TokenStream {
+        self.tokens.clone()
+    }
+    /// The text, without the padding added by `at`
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The span of the first occurrence of `needle` in the text, joined from the tokens it
+    /// covers, or only the span of the first token if spans can't be joined (see `join_spans`).
+    /// Panics if `needle` is not in the text or doesn't start and end at token boundaries.
+    pub fn span_of(&self, needle: &str) -> Span {
+        let start = self
+            .text
+            .find(needle)
+            .unwrap_or_else(|| panic!("FakeSource: {:?} is not in the source", needle))
+            + self.padding;
+        let end = start + needle.len();
+        let mut covered = vec![];
+        collect_spans(self.tokens.clone(), start..end, &mut covered);
+        let (Some(first), Some(last)) = (covered.first(), covered.last()) else {
+            panic!("FakeSource: {:?} doesn't contain whole tokens", needle);
+        };
+        // like the compiler on stable, e.g. with `Toolchain::Stable`
+        let Some(span) = join_spans(*first, *last) else {
+            return *first;
+        };
+        if span.byte_range() != (start..end) {
+            panic!(
+                "FakeSource: {:?} doesn't start and end at token boundaries",
+                needle
+            );
+        }
+        span
+    }
+}
+
+/// The spans of the outermost tokens inside of `range`, in order
+fn collect_spans(stream: TokenStream, range: std::ops::Range<usize>, spans: &mut Vec<Span>) {
+    for token in stream {
+        let token_range = token.span().byte_range();
+        if token_range.start >= range.start && token_range.end <= range.end {
+            spans.push(token.span());
+        } else if let TokenTree::Group(group) = token {
+            if token_range.start < range.end && range.start < token_range.end {
+                collect_spans(group.stream(), range.clone(), spans);
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+#![allow(dead_code)]
+
+use super::super::tokens;
+use super::Expansion;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::ToTokens;
+
+/// Assert that the expansion contains an `impl` of the trait for the type, anywhere in the
+/// output (including modules, function bodies and `const _: () = { ... };` blocks).
+///
+/// Both names are compared without whitespace. Without `::`, only the last segment of the trait
+/// path has to match, so `"Display"` matches `impl ::core::fmt::Display for Foo`. Without `<`, the
+/// generic arguments of the type are ignored, so `"Foo"` matches `impl<T> Display for Foo<T>`.
+/// ```ignore
+/// let output = derive_display(input);
+/// assert_contains_impl(output.clone(), "Display", "Foo");
+/// assert_contains_impl(output, "From<Foo>", "String");
+/// ```
+#[track_caller]
+pub(crate) fn assert_contains_impl(expansion: impl Expansion, trait_name: &str, type_name: &str) {
+    let file = parse_expansion(expansion.into_expansion());
+    let mut impls = vec![];
+    walk_items(&file.items, &mut |item| {
+        if let syn::Item::Impl(item) = item {
+            impls.push(item.clone());
+        }
+    });
+    let found = impls.iter().any(|item| {
+        let Some((_, path, _)) = &item.trait_ else {
+            return false;
+        };
+        path_matches(path, trait_name) && type_matches(&item.self_ty, type_name)
+    });
+    if !found {
+        let existing: Vec<String> = impls.iter().map(impl_header).collect();
+        panic!(
+            "the expansion has no `impl {} for {}`, only:\n  {}",
+            trait_name,
+            type_name,
+            existing.join("\n  ")
+        );
+    }
+}
+
+/// Assert that the expansion contains a function (free, in an `impl` or in a trait) whose
+/// signature matches the pattern. The pattern is compared token by token, where `_` matches any
+/// single token or group and `..` matches any number of tokens. A pattern without a visibility
+/// matches functions with any visibility.
+/// ```ignore
+/// assert_contains_fn(output.clone(), "pub fn name(&self) -> &str");
+/// assert_contains_fn(output.clone(), "fn with_name(..) -> Self");
+/// assert_contains_fn(output, "fn from(value: Foo) -> _");
+/// ```
+#[track_caller]
+pub(crate) fn assert_contains_fn(expansion: impl Expansion, signature_pattern: &str) {
+    let pattern: TokenStream = signature_pattern
+        .parse()
+        .unwrap_or_else(|e| panic!("invalid signature pattern {:?}: {}", signature_pattern, e));
+    let pattern: Vec<TokenTree> = pattern.into_iter().collect();
+    let file = parse_expansion(expansion.into_expansion());
+    let mut signatures = vec![];
+    walk_items(&file.items, &mut |item| match item {
+        syn::Item::Fn(f) => signatures.push((f.vis.clone(), f.sig.clone())),
+        syn::Item::Impl(item) => {
+            for impl_item in &item.items {
+                if let syn::ImplItem::Fn(f) = impl_item {
+                    signatures.push((f.vis.clone(), f.sig.clone()));
+                }
+            }
+        }
+        syn::Item::Trait(item) => {
+            for trait_item in &item.items {
+                if let syn::TraitItem::Fn(f) = trait_item {
+                    signatures.push((syn::Visibility::Inherited, f.sig.clone()));
+                }
+            }
+        }
+        _ => {}
+    });
+    let found = signatures.iter().any(|(vis, sig)| {
+        let with_vis: Vec<TokenTree> = quote::quote!(#vis #sig).into_iter().collect();
+        let without_vis: Vec<TokenTree> = sig.to_token_stream().into_iter().collect();
+        matches_pattern(&pattern, &with_vis) || matches_pattern(&pattern, &without_vis)
+    });
+    if !found {
+        let existing: Vec<String> = signatures
+            .iter()
+            .map(|(vis, sig)| header(quote::quote!(#vis #sig {})))
+            .collect();
+        panic!(
+            "the expansion has no function matching `{}`, only:\n  {}",
+            signature_pattern,
+            existing.join("\n  ")
+        );
+    }
+}
+
+#[track_caller]
+fn parse_expansion(expansion: TokenStream) -> syn::File {
+    syn::parse2(expansion.clone()).unwrap_or_else(|e| {
+        panic!(
+            "the expansion is not a list of items: {}\n{}",
+            e,
+            tokens::format_tokens(expansion)
+        )
+    })
+}
+
+/// Call `f` for all items, including the ones nested in modules and blocks
+fn walk_items<'a>(items: &'a [syn::Item], f: &mut impl FnMut(&'a syn::Item)) {
+    for item in items {
+        f(item);
+        match item {
+            syn::Item::Mod(item) => {
+                if let Some((_, items)) = &item.content {
+                    walk_items(items, f);
+                }
+            }
+            syn::Item::Fn(item) => walk_block(&item.block, f),
+            syn::Item::Const(item) => walk_expr(&item.expr, f),
+            syn::Item::Static(item) => walk_expr(&item.expr, f),
+            syn::Item::Impl(item) => {
+                for impl_item in &item.items {
+                    if let syn::ImplItem::Fn(method) = impl_item {
+                        walk_block(&method.block, f);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn walk_block<'a>(block: &'a syn::Block, f: &mut impl FnMut(&'a syn::Item)) {
+    for stmt in &block.stmts {
+        match stmt {
+            syn::Stmt::Item(item) => walk_items(std::slice::from_ref(item), f),
+            syn::Stmt::Expr(expr, _) => walk_expr(expr, f),
+            _ => {}
+        }
+    }
+}
+
+fn walk_expr<'a>(expr: &'a syn::Expr, f: &mut impl FnMut(&'a syn::Item)) {
+    match expr {
+        syn::Expr::Block(expr) => walk_block(&expr.block, f),
+        syn::Expr::Unsafe(expr) => walk_block(&expr.block, f),
+        _ => {}
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn path_matches(path: &syn::Path, pattern: &str) -> bool {
+    let pattern = normalize(pattern);
+    if pattern.contains("::") {
+        let path = normalize(&path.to_token_stream().to_string());
+        let path = path.strip_prefix("::").unwrap_or(&path);
+        return path == pattern.strip_prefix("::").unwrap_or(&pattern);
+    }
+    let Some(last) = path.segments.last() else {
+        return false;
+    };
+    if pattern.contains('<') {
+        normalize(&last.to_token_stream().to_string()) == pattern
+    } else {
+        last.ident == pattern
+    }
+}
+
+fn type_matches(ty: &syn::Type, pattern: &str) -> bool {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path_matches(&path.path, pattern),
+        _ => normalize(&ty.to_token_stream().to_string()) == normalize(pattern),
+    }
+}
+
+fn impl_header(item: &syn::ItemImpl) -> String {
+    let ty = &item.self_ty;
+    let header = match &item.trait_ {
+        Some((_, path, _)) => quote::quote!(impl #path for #ty),
+        None => quote::quote!(impl #ty),
+    };
+    self::header(quote::quote!(#header {}))
+}
+
+/// Format an item with an empty body, without the body
+fn header(item: TokenStream) -> String {
+    let text = tokens::format_generated(item);
+    let text = text.trim();
+    text.strip_suffix("{}").unwrap_or(text).trim().to_string()
+}
+
+fn is_wildcard(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Ident(ident) if ident == "_")
+}
+
+fn is_rest(tokens: &[TokenTree]) -> bool {
+    tokens::is_punct_seq(tokens, "..")
+}
+
+/// Match a pattern with `_` and `..` wildcards against tokens
+fn matches_pattern(pattern: &[TokenTree], tokens: &[TokenTree]) -> bool {
+    if is_rest(pattern) {
+        let rest = &pattern[2..];
+        return (0..=tokens.len()).any(|skip| matches_pattern(rest, &tokens[skip..]));
+    }
+    let (Some(expected), Some(actual)) = (pattern.first(), tokens.first()) else {
+        return pattern.is_empty() && tokens.is_empty();
+    };
+    let same = match (expected, actual) {
+        _ if is_wildcard(expected) => true,
+        (TokenTree::Group(expected), TokenTree::Group(actual)) => {
+            let expected_delimiter = expected.delimiter();
+            (expected_delimiter == actual.delimiter() || expected_delimiter == Delimiter::None)
+                && matches_pattern(
+                    &expected.stream().into_iter().collect::<Vec<_>>(),
+                    &actual.stream().into_iter().collect::<Vec<_>>(),
+                )
+        }
+        (TokenTree::Ident(expected), TokenTree::Ident(actual)) => expected == actual,
+        (TokenTree::Punct(expected), TokenTree::Punct(actual)) => {
+            expected.as_char() == actual.as_char()
+        }
+        (TokenTree::Literal(expected), TokenTree::Literal(actual)) => {
+            expected.to_string() == actual.to_string()
+        }
+        _ => false,
+    };
+    same && matches_pattern(&pattern[1..], &tokens[1..])
+}
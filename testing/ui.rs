@@ -0,0 +1,217 @@
+#![allow(dead_code)]
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What a UI test case is expected to do, from its header comment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum UiExpectation {
+    /// `// pass`: the case compiles
+    Pass,
+    /// `// error: <message>` lines: compiling the case fails, and the output contains every
+    /// message as `error: <message>`
+    Fail(Vec<String>),
+}
+
+/// A case from `UiCases::scan`
+#[derive(Clone, Debug)]
+pub(crate) struct UiCase {
+    /// The file name without `.rs`
+    pub name: String,
+    /// The annotated source file
+    pub path: PathBuf,
+    /// The contents of the source file
+    pub source: String,
+    /// The expectation from the header
+    pub expectation: UiExpectation,
+}
+
+impl UiCase {
+    /// The expected messages that are missing from trybuild's `.stderr` output
+    pub fn missing_messages<'a>(&'a self, stderr: &str) -> Vec<&'a str> {
+        let UiExpectation::Fail(messages) = &self.expectation else {
+            return vec![];
+        };
+        messages
+            .iter()
+            .filter(|message| {
+                !stderr
+                    .lines()
+                    .any(|line| line.trim_start() == format!("error: {}", message))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// The changes made by `UiCases::sync`
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UiSync {
+    /// Case files that were created or changed
+    pub written: Vec<PathBuf>,
+    /// Files of cases that no longer exist (or moved between `pass` and `fail`)
+    pub removed: Vec<PathBuf>,
+    /// `.stderr` files that were removed because they lacked expected messages. trybuild records
+    /// them again on the next run with `TRYBUILD=overwrite`.
+    pub stale: Vec<PathBuf>,
+}
+
+impl UiSync {
+    /// Check if nothing had to be changed
+    pub fn is_clean(&self) -> bool {
+        self.written.is_empty() && self.removed.is_empty() && self.stale.is_empty()
+    }
+}
+
+/// Generates trybuild UI tests from a directory of annotated examples.
+///
+/// Every `.rs` file in the directory starts with a header comment that says whether it should
+/// compile (`// pass`) or which errors it should produce (one `// error: <message>` line per
+/// error). `sync` copies the cases to `pass` and `fail` directories for trybuild's globs and
+/// checks the recorded `.stderr` files against the headers, so that adding a diagnostic only
+/// means adding an example.
+///
+/// `sync` doesn't run the compiler, so it never writes `.stderr` files. New cases, and cases
+/// whose `.stderr` was removed as stale, are recorded by trybuild itself on a run with
+/// `TRYBUILD=overwrite`. Run `sync` again afterwards to check that the recorded output contains
+/// the expected messages.
+/// ```ignore
+/// // tests/examples/duplicate_key.rs:
+/// // error: duplicate key `a`
+/// #[derive(Config)]
+/// #[config(a = 1, a = 2)]
+/// struct Foo;
+///
+/// #[test]
+/// fn ui() {
+///     UiCases::scan("tests/examples").unwrap().sync("tests/ui").unwrap();
+///     let t = trybuild::TestCases::new();
+///     t.pass("tests/ui/pass/*.rs");
+///     t.compile_fail("tests/ui/fail/*.rs");
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UiCases {
+    cases: Vec<UiCase>,
+}
+
+impl UiCases {
+    /// Read all `.rs` files in `dir`, sorted by name. Fails if a file has no header.
+    pub fn scan(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut paths = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let cases = paths
+            .into_iter()
+            .map(|path| {
+                let source = std::fs::read_to_string(&path)?;
+                let expectation = parse_header(&source).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "{} has no `// pass` or `// error: ...` header",
+                            path.display()
+                        ),
+                    )
+                })?;
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                Ok(UiCase {
+                    name,
+                    path,
+                    source,
+                    expectation,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { cases })
+    }
+
+    /// The cases, sorted by name
+    pub fn cases(&self) -> &[UiCase] {
+        &self.cases
+    }
+
+    /// Write the cases to `ui_dir/pass` and `ui_dir/fail`, and remove the files of cases that
+    /// don't exist anymore, as well as `.stderr` files that are missing an expected message.
+    /// Only the `.rs` files are written: `.stderr` files are only ever removed, recording them is
+    /// left to trybuild.
+    ///
+    /// The `pass` and `fail` directories are owned by this function, so any other files in them
+    /// are removed.
+    pub fn sync(&self, ui_dir: impl AsRef<Path>) -> io::Result<UiSync> {
+        let mut report = UiSync::default();
+        for (kind, is_fail) in [("pass", false), ("fail", true)] {
+            let dir = ui_dir.as_ref().join(kind);
+            std::fs::create_dir_all(&dir)?;
+            let cases: Vec<&UiCase> = self
+                .cases
+                .iter()
+                .filter(|case| matches!(case.expectation, UiExpectation::Fail(_)) == is_fail)
+                .collect();
+
+            let mut files = vec![];
+            for entry in std::fs::read_dir(&dir)? {
+                files.push(entry?.path());
+            }
+            files.sort();
+            for file in files {
+                let known = file
+                    .file_stem()
+                    .is_some_and(|stem| cases.iter().any(|case| *stem == *case.name))
+                    && file
+                        .extension()
+                        .is_some_and(|ext| ext == "rs" || (is_fail && ext == "stderr"));
+                if !known && file.is_file() {
+                    std::fs::remove_file(&file)?;
+                    report.removed.push(file);
+                }
+            }
+
+            for case in cases {
+                let target = dir.join(format!("{}.rs", case.name));
+                if std::fs::read_to_string(&target).ok().as_ref() != Some(&case.source) {
+                    std::fs::write(&target, &case.source)?;
+                    report.written.push(target);
+                }
+                let stderr = dir.join(format!("{}.stderr", case.name));
+                if let Ok(output) = std::fs::read_to_string(&stderr) {
+                    if !case.missing_messages(&output).is_empty() {
+                        std::fs::remove_file(&stderr)?;
+                        report.stale.push(stderr);
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// The expectation from the leading comment lines of a case
+fn parse_header(source: &str) -> Option<UiExpectation> {
+    let mut pass = false;
+    let mut errors = vec![];
+    for line in source.lines().map(str::trim) {
+        let Some(comment) = line.strip_prefix("//") else {
+            break;
+        };
+        let comment = comment.trim();
+        if comment == "pass" {
+            pass = true;
+        } else if let Some(message) = comment.strip_prefix("error:") {
+            errors.push(message.trim().to_string());
+        }
+    }
+    match (pass, errors.is_empty()) {
+        (true, true) => Some(UiExpectation::Pass),
+        (false, false) => Some(UiExpectation::Fail(errors)),
+        _ => None,
+    }
+}
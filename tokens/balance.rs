@@ -0,0 +1,247 @@
+#![allow(dead_code)]
+
+use std::fmt::Display;
+
+/// An unbalanced delimiter found by `check_delimiters`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct DelimiterError {
+    /// Byte offset of the offending delimiter in the source
+    pub offset: usize,
+    /// 1-based line of the offending delimiter
+    pub line: usize,
+    /// 1-based column (in characters) of the offending delimiter
+    pub column: usize,
+    /// What is wrong, e.g. "unclosed `(`"
+    pub message: String,
+}
+
+impl Display for DelimiterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}:{}", self.message, self.line, self.column)
+    }
+}
+
+/// Check that all `(`, `[` and `{` in a piece of source code are closed in the right order.
+///
+/// Token streams are always balanced, so this is only needed for code from strings (attribute
+/// values, files), where the lexer fails with an error that doesn't say where the problem is.
+/// Delimiters inside of string and char literals and comments are ignored.
+pub(crate) fn check_delimiters(source: &str) -> std::result::Result<(), DelimiterError> {
+    if let Some((open, offset)) = scan(source)?.pop() {
+        return Err(error_at(
+            source,
+            offset,
+            &format!("unclosed `{}`", open as char),
+        ));
+    }
+    Ok(())
+}
+
+/// The closing delimiters that `source` is missing, innermost first. Fails for the same errors
+/// as `check_delimiters`, except unclosed delimiters, e.g. if `source` ends inside of a string.
+pub(crate) fn missing_delimiters(source: &str) -> std::result::Result<String, DelimiterError> {
+    let stack = scan(source)?;
+    Ok(stack
+        .iter()
+        .rev()
+        .map(|(open, _)| matching_close(*open) as char)
+        .collect())
+}
+
+/// Check everything but unclosed delimiters, returning the open delimiters with their offsets
+fn scan(source: &str) -> std::result::Result<Vec<(u8, usize)>, DelimiterError> {
+    let bytes = source.as_bytes();
+    let mut stack: Vec<(u8, usize)> = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let start = i;
+                let mut depth = 0;
+                loop {
+                    if i + 1 >= bytes.len() {
+                        return Err(error_at(source, start, "unterminated block comment"));
+                    }
+                    if bytes[i] == b'/' && bytes[i + 1] == b'*' {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i] == b'*' && bytes[i + 1] == b'/' {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            b'"' => i = skip_string(source, i, false, 0)?,
+            b'r' if is_raw_string_start(bytes, i) => {
+                let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+                i = skip_string(source, i + 1 + hashes, true, hashes)?;
+            }
+            b'\'' => {
+                // char literal or lifetime
+                if let Some(end) = char_literal_end(source, i) {
+                    i = end;
+                }
+            }
+            open @ (b'(' | b'[' | b'{') => stack.push((open, i)),
+            close @ (b')' | b']' | b'}') => match stack.pop() {
+                Some((open, _)) if matching_close(open) == close => {}
+                Some((open, open_offset)) => {
+                    let (line, column) = line_column(source, open_offset);
+                    let message = format!(
+                        "mismatched closing delimiter `{}`, the `{}` at {}:{} is not closed",
+                        close as char, open as char, line, column
+                    );
+                    return Err(error_at(source, i, &message));
+                }
+                None => {
+                    let message = format!("unexpected closing delimiter `{}`", close as char);
+                    return Err(error_at(source, i, &message));
+                }
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(stack)
+}
+
+/// Check the delimiters in the value of a string literal. The error points at the offending
+/// delimiter inside the literal if the compiler supports it (`Span::subspan` is nightly-only),
+/// otherwise at the whole literal with the position in the message.
+#[cfg(not(feature = "no-syn"))]
+pub(crate) fn validate_delimiters(lit: &syn::LitStr) -> super::super::Result<()> {
+    let value = lit.value();
+    let Err(error) = check_delimiters(&value) else {
+        return Ok(());
+    };
+    let span = super::super::lit::value_span(lit, error.offset..error.offset + 1);
+    super::super::Error::err(span, error)
+}
+
+fn matching_close(open: u8) -> u8 {
+    match open {
+        b'(' => b')',
+        b'[' => b']',
+        _ => b'}',
+    }
+}
+
+fn is_raw_string_start(bytes: &[u8], i: usize) -> bool {
+    // `r"`, `r#"` or `br"`, but not the end of an identifier like `bar"`
+    let is_ident_char = |i: usize| bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_';
+    let standalone = i == 0
+        || !is_ident_char(i - 1)
+        || (matches!(bytes[i - 1], b'b' | b'c') && (i == 1 || !is_ident_char(i - 2)));
+    let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+    standalone && bytes.get(i + 1 + hashes) == Some(&b'"')
+}
+
+/// Skip a string starting at the `"` at `start`, returning the index of its last byte. Raw strings
+/// have no escapes, so `r"\"` is complete.
+fn skip_string(
+    source: &str,
+    start: usize,
+    raw: bool,
+    hashes: usize,
+) -> std::result::Result<usize, DelimiterError> {
+    let bytes = source.as_bytes();
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if !raw => i += 2,
+            b'"' if bytes[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|b| **b == b'#')
+                .count()
+                == hashes =>
+            {
+                return Ok(i + hashes);
+            }
+            _ => i += 1,
+        }
+    }
+    Err(error_at(source, start, "unterminated string literal"))
+}
+
+/// If the `'` at `start` begins a char literal, return the index of its closing `'`
+fn char_literal_end(source: &str, start: usize) -> Option<usize> {
+    let rest = &source[start + 1..];
+    if let Some(escaped) = rest.strip_prefix('\\') {
+        // the escaped character can be a `'` or `\\` itself
+        let skipped = escaped.chars().next()?.len_utf8();
+        let end = escaped[skipped..].find('\'')?;
+        return Some(start + 2 + skipped + end);
+    }
+    // a single character followed by `'`, otherwise it's a lifetime
+    let mut chars = rest.chars();
+    let c = chars.next()?;
+    (chars.next() == Some('\'')).then_some(start + 1 + c.len_utf8())
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+fn error_at(source: &str, offset: usize, message: &str) -> DelimiterError {
+    let (line, column) = line_column(source, offset);
+    DelimiterError {
+        offset,
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_char_literals() {
+        assert_eq!(check_delimiters(r"f('\'', '{')"), Ok(()));
+        assert_eq!(check_delimiters(r"f('\\', '}')"), Ok(()));
+        assert_eq!(check_delimiters(r"['\u{7b}', '\n', ']']"), Ok(()));
+        assert_eq!(check_delimiters(r#"f(r"\")"#), Ok(()));
+        assert_eq!(check_delimiters(r#"f(br"\", ")")"#), Ok(()));
+        assert_eq!(check_delimiters(r##"f(r#"\"#, "\")")"##), Ok(()));
+        let error = check_delimiters(r"f('\'') }").unwrap_err();
+        assert_eq!(error.message, "unexpected closing delimiter `}`");
+        assert_eq!(error.offset, 8);
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(missing_delimiters("f(a, [b, {").as_deref(), Ok("}])"));
+        assert_eq!(missing_delimiters("f(a)").as_deref(), Ok(""));
+        assert!(missing_delimiters("f(\"a").is_err());
+    }
+
+    #[test]
+    fn lifetimes_are_not_char_literals() {
+        assert_eq!(
+            check_delimiters("fn f<'a>(x: &'a str) -> &'a str { x }"),
+            Ok(())
+        );
+    }
+}
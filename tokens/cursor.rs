@@ -0,0 +1,216 @@
+#![allow(dead_code)]
+
+use super::super::{Error, Result};
+use proc_macro2::{Delimiter, Ident, Literal, Punct, Span, TokenStream, TokenTree};
+
+/// A position in a `Cursor` that can be returned to with `Cursor::rollback`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Checkpoint(usize);
+
+/// A cursor over the tokens of one level of a `TokenStream`, for small hand-written parsers
+/// (e.g. for DSL macros) that don't need all of `syn`. Groups are entered with `expect_group`,
+/// which returns a new cursor over their contents.
+///
+/// `None`-delimited groups (created by `macro_rules!` for fragments like `$e:expr`) are
+/// transparent: the cursor sees their contents as if they were written directly, so parsing works
+/// the same when the macro is called from a declarative macro.
+///
+/// All `expect_*` methods return an error at the current token if it doesn't match, and leave the
+/// cursor unchanged in that case. `peek_*` and `eat_*` are the non-failing variants.
+/// ```ignore
+/// // parse `name as Type => { ... }`
+/// let mut cursor = Cursor::new(input);
+/// let name = cursor.expect_any_ident()?;
+/// cursor.expect_ident("as")?;
+/// let ty = cursor.expect_any_ident()?;
+/// cursor.expect_punct("=>")?;
+/// let (mut body, _) = cursor.expect_group(Delimiter::Brace)?;
+/// cursor.expect_end()?;
+/// ```
+#[derive(Clone)]
+pub(crate) struct Cursor {
+    tokens: Vec<TokenTree>,
+    pos: usize,
+    end_span: Span,
+}
+
+impl Cursor {
+    /// Create a cursor at the start of a stream. Errors at the end of the stream are reported at
+    /// `Span::call_site()`, see `with_end_span`.
+    pub fn new(stream: TokenStream) -> Self {
+        Self {
+            tokens: super::flatten_none_groups_shallow(stream),
+            pos: 0,
+            end_span: Span::call_site(),
+        }
+    }
+    /// Set the span for errors at the end of the stream, e.g. the closing delimiter of the group
+    pub fn with_end_span(mut self, span: Span) -> Self {
+        self.end_span = span;
+        self
+    }
+
+    /// The next token, without advancing
+    pub fn peek(&self) -> Option<&TokenTree> {
+        self.peek_nth(0)
+    }
+    /// The token `n` positions ahead (0 is the next token), without advancing
+    pub fn peek_nth(&self, n: usize) -> Option<&TokenTree> {
+        self.tokens.get(self.pos + n)
+    }
+    /// Return the next token and advance past it
+    pub fn advance(&mut self) -> Option<TokenTree> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        Some(token)
+    }
+    /// Check if there are no more tokens
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+    /// The span of the next token, or the end span if there are no more tokens
+    pub fn span(&self) -> Span {
+        self.peek().map_or(self.end_span, TokenTree::span)
+    }
+
+    /// Remember the current position
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.pos)
+    }
+    /// Go back to a position returned by `checkpoint`
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
+    }
+
+    /// Check if the next token is the identifier `name`
+    pub fn peek_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(TokenTree::Ident(ident)) if ident == name)
+    }
+    /// Advance past the identifier `name` if it is next
+    pub fn eat_ident(&mut self, name: &str) -> Option<Ident> {
+        if !self.peek_ident(name) {
+            return None;
+        }
+        match self.advance() {
+            Some(TokenTree::Ident(ident)) => Some(ident),
+            _ => None,
+        }
+    }
+    /// Expect the identifier `name`
+    pub fn expect_ident(&mut self, name: &str) -> Result<Ident> {
+        self.eat_ident(name)
+            .ok_or_else(|| self.error_expected(&format!("`{}`", name)))
+    }
+    /// Expect any identifier
+    pub fn expect_any_ident(&mut self) -> Result<Ident> {
+        match self.peek() {
+            Some(TokenTree::Ident(ident)) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => Err(self.error_expected("an identifier")),
+        }
+    }
+
+    /// Check if the next tokens are the punctuation `puncts`, e.g. `"=>"`. Multi-character
+    /// punctuation has to be joined, so `"->"` doesn't match `- >`, and it must not be the start
+    /// of longer punctuation, so `"="` doesn't match `==`.
+    pub fn peek_punct(&self, puncts: &str) -> bool {
+        self.punct_len(puncts).is_some()
+    }
+    /// Advance past the punctuation `puncts` if it is next, returning the span of its first
+    /// character
+    pub fn eat_punct(&mut self, puncts: &str) -> Option<Span> {
+        let span = self.span();
+        self.match_puncts(puncts).map(|_| span)
+    }
+    /// Advance past the punctuation `puncts` if it is next, returning the individual characters.
+    /// Uses the same rules as `peek_punct`, see `tokens::is_punct_seq`.
+    pub fn match_puncts(&mut self, puncts: &str) -> Option<Vec<Punct>> {
+        let len = self.punct_len(puncts)?;
+        let matched = self.tokens[self.pos..self.pos + len]
+            .iter()
+            .filter_map(|token| match token {
+                TokenTree::Punct(punct) => Some(punct.clone()),
+                _ => None,
+            })
+            .collect();
+        self.pos += len;
+        Some(matched)
+    }
+    /// Expect the punctuation `puncts`, returning the span of its first character
+    pub fn expect_punct(&mut self, puncts: &str) -> Result<Span> {
+        self.eat_punct(puncts)
+            .ok_or_else(|| self.error_expected(&format!("`{}`", puncts)))
+    }
+
+    /// Expect any literal
+    pub fn expect_literal(&mut self) -> Result<Literal> {
+        match self.peek() {
+            Some(TokenTree::Literal(literal)) => {
+                let literal = literal.clone();
+                self.pos += 1;
+                Ok(literal)
+            }
+            _ => Err(self.error_expected("a literal")),
+        }
+    }
+
+    /// Expect a group with the given delimiter, returning a cursor over its contents and the
+    /// span of the group. `Delimiter::None` never matches, since those groups are transparent.
+    pub fn expect_group(&mut self, delimiter: Delimiter) -> Result<(Cursor, Span)> {
+        match self.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == delimiter => {
+                let inner = Cursor::new(group.stream()).with_end_span(group.span_close());
+                let span = group.span();
+                self.pos += 1;
+                Ok((inner, span))
+            }
+            _ => {
+                let expected = match delimiter {
+                    Delimiter::Parenthesis => "`(`",
+                    Delimiter::Brace => "`{`",
+                    Delimiter::Bracket => "`[`",
+                    Delimiter::None => "a `None`-delimited group",
+                };
+                Err(self.error_expected(expected))
+            }
+        }
+    }
+
+    /// Expect the end of the stream
+    pub fn expect_end(&self) -> Result<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Error::err(token.span(), format!("unexpected token `{}`", token)),
+        }
+    }
+
+    /// Take all remaining tokens
+    pub fn rest(&mut self) -> TokenStream {
+        let rest = self.tokens[self.pos.min(self.tokens.len())..]
+            .iter()
+            .cloned()
+            .collect();
+        self.pos = self.tokens.len();
+        rest
+    }
+
+    /// Create an error at the current token
+    pub fn error(&self, message: impl std::fmt::Display) -> Error {
+        Error::new(self.span(), message)
+    }
+
+    fn error_expected(&self, expected: &str) -> Error {
+        match self.peek() {
+            Some(token) => self.error(format!("expected {}, found `{}`", expected, token)),
+            None => self.error(format!("expected {}, found end of input", expected)),
+        }
+    }
+
+    /// The number of tokens that make up `puncts` if they are next
+    fn punct_len(&self, puncts: &str) -> Option<usize> {
+        super::punct_seq_len(&self.tokens[self.pos.min(self.tokens.len())..], puncts)
+    }
+}
@@ -0,0 +1,232 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use std::fmt::Display;
+
+/// The first point where two token streams differ, as found by `diff`
+#[derive(Clone, Debug)]
+pub(crate) struct Diff {
+    /// Where the difference is, e.g. `item[2] > group({) > token[5]`
+    pub path: String,
+    /// The differing token in the expected stream, or `None` if the expected stream ended
+    pub expected: Option<String>,
+    /// The differing token in the actual stream, or `None` if the actual stream ended
+    pub actual: Option<String>,
+    /// The spacing of the expected and the actual punct, if that is the only difference, as in
+    /// `- >` vs `->`
+    pub spacing: Option<(Spacing, Spacing)>,
+    /// A few tokens around the difference in the expected stream, with the differing token
+    /// marked as `>>token<<`
+    pub expected_context: String,
+    /// Same as `expected_context` for the actual stream
+    pub actual_context: String,
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let describe = |token: &Option<String>| match token {
+            Some(token) => format!("`{}`", token),
+            None => "end of stream".to_string(),
+        };
+        writeln!(f, "token streams differ at {}", self.path)?;
+        let (expected, actual) = (describe(&self.expected), describe(&self.actual));
+        match self.spacing {
+            Some((e_spacing, a_spacing)) => writeln!(
+                f,
+                "expected {} {}, found {} {}",
+                spacing_name(e_spacing),
+                expected,
+                spacing_name(a_spacing),
+                actual
+            )?,
+            None => writeln!(f, "expected {}, found {}", expected, actual)?,
+        }
+        writeln!(f, "  expected: {}", self.expected_context)?;
+        write!(f, "  actual:   {}", self.actual_context)
+    }
+}
+
+/// Number of tokens shown before and after the difference in `Diff`'s context
+const CONTEXT: usize = 4;
+
+/// Compare two token streams structurally and return the first difference, or `None` if they
+/// are equal. Spans are ignored, and the spacing of punctuation only matters if it is followed
+/// by punctuation that it could be joined with (`- >` vs `->`, but not `> ,` vs `>,`).
+///
+/// Much more useful in test failures than comparing the stringified streams:
+/// ```ignore
+/// if let Some(diff) = tokens::diff(expected, actual) {
+///     panic!("{}", diff);
+/// }
+/// ```
+pub(crate) fn diff(expected: TokenStream, actual: TokenStream) -> Option<Diff> {
+    diff_level(expected, actual, &mut vec![])
+}
+
+fn diff_level(expected: TokenStream, actual: TokenStream, path: &mut Vec<String>) -> Option<Diff> {
+    let expected: Vec<TokenTree> = expected.into_iter().collect();
+    let actual: Vec<TokenTree> = actual.into_iter().collect();
+    let label = if path.is_empty() { "item" } else { "token" };
+    for i in 0..expected.len().max(actual.len()) {
+        let (e, a) = (expected.get(i), actual.get(i));
+        let position = format!("{}[{}]", label, i);
+        let (e_token, a_token) = match (e, a) {
+            (Some(TokenTree::Group(e_group)), Some(TokenTree::Group(a_group)))
+                if e_group.delimiter() == a_group.delimiter() =>
+            {
+                path.push(position);
+                path.push(format!("group({})", open_delimiter(e_group.delimiter())));
+                let result = diff_level(e_group.stream(), a_group.stream(), path);
+                path.truncate(path.len() - 2);
+                if result.is_some() {
+                    return result;
+                }
+                continue;
+            }
+            (Some(e_token), Some(a_token))
+                if leaf_eq(e_token, expected.get(i + 1), a_token, actual.get(i + 1)) =>
+            {
+                continue;
+            }
+            pair => pair,
+        };
+        path.push(position);
+        let spacing = match (e_token, a_token) {
+            (Some(TokenTree::Punct(e)), Some(TokenTree::Punct(a)))
+                if e.as_char() == a.as_char() =>
+            {
+                Some((e.spacing(), a.spacing()))
+            }
+            _ => None,
+        };
+        let diff = Diff {
+            path: path.join(" > "),
+            expected: e_token.map(short_token),
+            actual: a_token.map(short_token),
+            spacing,
+            expected_context: context(&expected, i),
+            actual_context: context(&actual, i),
+        };
+        path.pop();
+        return Some(diff);
+    }
+    None
+}
+
+/// Compare two non-group tokens (or mismatched groups). `a_next` and `b_next` are the tokens
+/// following each one, which decide whether the spacing of a punct matters.
+pub(super) fn leaf_eq(
+    a: &TokenTree,
+    a_next: Option<&TokenTree>,
+    b: &TokenTree,
+    b_next: Option<&TokenTree>,
+) -> bool {
+    match (a, b) {
+        (TokenTree::Ident(a), TokenTree::Ident(b)) => a == b,
+        (TokenTree::Literal(a), TokenTree::Literal(b)) => a.to_string() == b.to_string(),
+        (TokenTree::Punct(a), TokenTree::Punct(b)) => {
+            let spacing_matters = can_join(a.as_char(), a_next) || can_join(b.as_char(), b_next);
+            a.as_char() == b.as_char()
+                && (!spacing_matters
+                    || (a.spacing() == Spacing::Joint) == (b.spacing() == Spacing::Joint))
+        }
+        _ => false,
+    }
+}
+
+/// The first two characters of all multi-character operators, e.g. `>=` of `>>=`, plus `.=`
+/// for the end of `..=`
+const JOINABLE: &[&str] = &[
+    "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=", "%=", "^=", "&=", "|=", "==", "!=", ">=", "<=",
+    "->", "=>", "<-", "::", "..", ".=",
+];
+
+/// Check if `c` followed by `next` could be part of a multi-character operator
+fn can_join(c: char, next: Option<&TokenTree>) -> bool {
+    let Some(TokenTree::Punct(next)) = next else {
+        return false;
+    };
+    let mut pair = String::from(c);
+    pair.push(next.as_char());
+    JOINABLE.contains(&pair.as_str())
+}
+
+fn spacing_name(spacing: Spacing) -> &'static str {
+    match spacing {
+        Spacing::Joint => "joint",
+        Spacing::Alone => "alone",
+    }
+}
+
+fn open_delimiter(delimiter: Delimiter) -> &'static str {
+    match delimiter {
+        Delimiter::Parenthesis => "(",
+        Delimiter::Brace => "{",
+        Delimiter::Bracket => "[",
+        Delimiter::None => "none",
+    }
+}
+
+/// A token as a short string, with the contents of groups elided
+fn short_token(token: &TokenTree) -> String {
+    match token {
+        TokenTree::Group(group) => {
+            let (open, close) = match group.delimiter() {
+                Delimiter::Parenthesis => ("(", ")"),
+                Delimiter::Brace => ("{", "}"),
+                Delimiter::Bracket => ("[", "]"),
+                Delimiter::None => ("«", "»"),
+            };
+            if group.stream().is_empty() {
+                format!("{}{}", open, close)
+            } else {
+                format!("{} ... {}", open, close)
+            }
+        }
+        token => token.to_string(),
+    }
+}
+
+fn context(tokens: &[TokenTree], index: usize) -> String {
+    let start = index.saturating_sub(CONTEXT);
+    let end = (index + CONTEXT + 1).min(tokens.len());
+    let mut parts: Vec<String> = vec![];
+    if start > 0 {
+        parts.push("...".to_string());
+    }
+    for (i, token) in tokens.iter().enumerate().take(end).skip(start) {
+        if i == index {
+            parts.push(format!(">>{}<<", short_token(token)));
+        } else {
+            parts.push(short_token(token));
+        }
+    }
+    if index >= tokens.len() {
+        parts.push(">><<".to_string());
+    }
+    if end < tokens.len() {
+        parts.push("...".to_string());
+    }
+    format!("`{}`", parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn spacing_differences_are_named() {
+        let arrow = diff(quote!(fn f() -> u8), quote!(fn f() - > u8)).unwrap();
+        assert_eq!(arrow.path, "item[3]");
+        let message = arrow.to_string();
+        assert!(
+            message.contains("expected joint `-`, found alone `-`"),
+            "{}",
+            message
+        );
+        let operator = diff(quote!(a + b), quote!(a - b)).unwrap();
+        assert!(operator.spacing.is_none());
+        assert!(operator.to_string().contains("expected `+`, found `-`"));
+    }
+}
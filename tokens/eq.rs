@@ -0,0 +1,25 @@
+#![allow(dead_code)]
+
+use super::flatten_none_groups;
+use proc_macro2::TokenStream;
+
+/// Check if two token streams have the same content, ignoring spans and hygiene. Uses the same
+/// rules as `tokens::diff`: the spacing of punctuation only matters if it is followed by
+/// punctuation that it could be joined with, and `None`-delimited groups have to match exactly
+/// (see `eq_transparent`).
+/// ```ignore
+/// assert!(tokens::eq(&output, &quote!(impl Foo for Bar {})));
+/// ```
+pub(crate) fn eq(a: &TokenStream, b: &TokenStream) -> bool {
+    super::diff(a.clone(), b.clone()).is_none()
+}
+
+/// Same as `eq`, but `None`-delimited groups are treated as if their contents were written
+/// directly. Such groups are invisible in source code, but are created when a `macro_rules!`
+/// fragment like `$e:expr` is passed on, so this is usually what tests of macro output want.
+pub(crate) fn eq_transparent(a: &TokenStream, b: &TokenStream) -> bool {
+    eq(
+        &flatten_none_groups(a.clone()),
+        &flatten_none_groups(b.clone()),
+    )
+}
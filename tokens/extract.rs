@@ -0,0 +1,123 @@
+#![allow(dead_code)]
+
+use super::super::{Error, ErrorBuilder, Result};
+use proc_macro2::{Span, TokenStream, TokenTree};
+
+/// A region found by `extract_between`
+#[derive(Clone)]
+pub(crate) struct Region {
+    /// The tokens between the markers, without the markers themselves
+    pub tokens: TokenStream,
+    /// The span of the first token of the start marker
+    pub start: Span,
+    /// The span of the first token of the end marker
+    pub end: Span,
+    /// The nesting depth of the region, 0 if it is not inside of any group
+    pub depth: usize,
+}
+
+/// Find all regions between `start_marker` and `end_marker`, at any nesting level. Both markers
+/// of a region have to be in the same group. Regions are not nested: a start marker inside of a
+/// region is part of the region's tokens.
+///
+/// Returns an error at every start marker without an end marker and every end marker without a
+/// start marker.
+/// ```ignore
+/// // my_macro! { fn a() { @begin x + 1 @end } }
+/// let regions = tokens::extract_between(input, &quote!(@begin), &quote!(@end))?;
+/// assert_eq!(regions[0].tokens.to_string(), "x + 1");
+/// ```
+pub(crate) fn extract_between(
+    stream: TokenStream,
+    start_marker: &TokenStream,
+    end_marker: &TokenStream,
+) -> Result<Vec<Region>> {
+    let start: Vec<TokenTree> = start_marker.clone().into_iter().collect();
+    let end: Vec<TokenTree> = end_marker.clone().into_iter().collect();
+    if start.is_empty() || end.is_empty() {
+        return Error::err(
+            Span::call_site(),
+            "extract_between: markers must not be empty",
+        );
+    }
+    let mut extractor = Extractor {
+        start,
+        end,
+        regions: vec![],
+        error: Error::builder(),
+    };
+    extractor.extract_level(stream, 0);
+    extractor.error.ok_or_build()?;
+    Ok(extractor.regions)
+}
+
+struct Extractor {
+    start: Vec<TokenTree>,
+    end: Vec<TokenTree>,
+    regions: Vec<Region>,
+    error: ErrorBuilder,
+}
+
+impl Extractor {
+    fn extract_level(&mut self, stream: TokenStream, depth: usize) {
+        let tokens: Vec<TokenTree> = stream.into_iter().collect();
+        let mut i = 0;
+        while i < tokens.len() {
+            if is_marker_at(&tokens, i, &self.end) {
+                self.error.with(
+                    tokens[i].span(),
+                    format!(
+                        "`{}` without a preceding `{}`",
+                        marker_string(&self.end),
+                        marker_string(&self.start)
+                    ),
+                );
+                i += self.end.len();
+                continue;
+            }
+            if is_marker_at(&tokens, i, &self.start) {
+                let inner_start = i + self.start.len();
+                let Some(end) =
+                    (inner_start..tokens.len()).find(|&j| is_marker_at(&tokens, j, &self.end))
+                else {
+                    self.error.with(
+                        tokens[i].span(),
+                        format!(
+                            "`{}` without a matching `{}`",
+                            marker_string(&self.start),
+                            marker_string(&self.end)
+                        ),
+                    );
+                    return;
+                };
+                self.regions.push(Region {
+                    tokens: tokens[inner_start..end].iter().cloned().collect(),
+                    start: tokens[i].span(),
+                    end: tokens[end].span(),
+                    depth,
+                });
+                i = end + self.end.len();
+                continue;
+            }
+            if let TokenTree::Group(group) = &tokens[i] {
+                self.extract_level(group.stream(), depth + 1);
+            }
+            i += 1;
+        }
+    }
+}
+
+fn is_marker_at(tokens: &[TokenTree], i: usize, marker: &[TokenTree]) -> bool {
+    let Some(window) = tokens.get(i..i + marker.len()) else {
+        return false;
+    };
+    super::diff(
+        marker.iter().cloned().collect(),
+        window.iter().cloned().collect(),
+    )
+    .is_none()
+}
+
+fn marker_string(marker: &[TokenTree]) -> String {
+    marker.iter().cloned().collect::<TokenStream>().to_string()
+}
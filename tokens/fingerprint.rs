@@ -0,0 +1,89 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Hash the content of a token stream, ignoring spans and hygiene.
+///
+/// Streams that compare equal with `tokens::diff` have the same fingerprint, so the spacing of
+/// punctuation only matters if it is followed by another punctuation. The value is stable across
+/// builds, machines and compiler versions, so it can be used for memoization keys, change
+/// detection or unique suffixes of generated names (see `ident::mangle`).
+/// ```ignore
+/// let key = tokens::fingerprint(&input);
+/// if let Some(cached) = cache.get(&key) { ... }
+/// ```
+pub(crate) fn fingerprint(stream: &TokenStream) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write_tokens(stream.clone());
+    hasher.finish()
+}
+
+/// `fingerprint` as 16 hex digits, for use in identifiers and file names
+pub(crate) fn fingerprint_hex(stream: &TokenStream) -> String {
+    format!("{:016x}", fingerprint(stream))
+}
+
+/// FNV-1a hasher for combining several token streams and strings into one fingerprint.
+/// `std`'s `DefaultHasher` is not guaranteed to produce the same values across Rust versions,
+/// which would change generated names when the compiler is updated.
+///
+/// Not a cryptographic hash, so it must not be used where collisions could be provoked.
+#[derive(Clone)]
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    /// Create a new hasher
+    pub fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+    /// Add raw bytes
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    /// Add a string, followed by a separator so that ("ab", "c") and ("a", "bc") hash differently
+    pub fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+        self.write(&[0xff]);
+    }
+    /// Add the content of a token stream, ignoring spans
+    pub fn write_tokens(&mut self, tokens: TokenStream) {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.write_str(open);
+                    self.write_tokens(group.stream());
+                    self.write_str(close);
+                }
+                TokenTree::Ident(ident) => self.write_str(&ident.to_string()),
+                TokenTree::Punct(punct) => {
+                    let mut buf = [0; 4];
+                    self.write(punct.as_char().encode_utf8(&mut buf).as_bytes());
+                    let joint = punct.spacing() == Spacing::Joint
+                        && matches!(tokens.peek(), Some(TokenTree::Punct(_)));
+                    self.write(&[joint as u8]);
+                }
+                TokenTree::Literal(literal) => self.write_str(&literal.to_string()),
+            }
+        }
+    }
+    /// The hash of everything written so far
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
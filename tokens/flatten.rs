@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use super::TokenRope;
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+
+/// Replace all `None`-delimited groups with their contents, at any nesting level.
+///
+/// `macro_rules!` wraps interpolated fragments like `$e:expr` or `$t:ty` in such invisible
+/// groups, so a proc macro called from a declarative macro sees `Group(None, [a + b])` where the
+/// user wrote `a + b`. This breaks matching on individual tokens, and removing the groups is
+/// usually the simplest fix. Note that the groups preserve precedence (`$e * 2` with `$e = a + b`
+/// is `(a + b) * 2`), which is lost by flattening.
+pub(crate) fn flatten_none_groups(stream: TokenStream) -> TokenStream {
+    let mut out = TokenRope::new();
+    for token in stream {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                out.push(flatten_none_groups(group.stream()));
+            }
+            TokenTree::Group(group) => {
+                let mut new_group =
+                    Group::new(group.delimiter(), flatten_none_groups(group.stream()));
+                new_group.set_span(group.span());
+                out.push_tree(TokenTree::Group(new_group));
+            }
+            token => {
+                out.push_tree(token);
+            }
+        }
+    }
+    out.finish()
+}
+
+/// Same as `flatten_none_groups`, but only for the top level of the stream (including `None`
+/// groups nested directly in `None` groups). Other groups are left unchanged.
+pub(crate) fn flatten_none_groups_shallow(stream: TokenStream) -> Vec<TokenTree> {
+    let mut out = vec![];
+    for token in stream {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                out.extend(flatten_none_groups_shallow(group.stream()));
+            }
+            token => out.push(token),
+        }
+    }
+    out
+}
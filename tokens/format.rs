@@ -0,0 +1,347 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Format generated code for humans, e.g. for snapshot tests, debug output or logging.
+///
+/// With the `prettyplease` feature, anything that parses as a sequence of items is formatted like
+/// `rustfmt` would. Everything else (and everything without the feature) goes through
+/// `format_tokens`, which only looks at the tokens and produces readable but less polished output.
+/// `prettyplease` needs `syn`, so it is not used with the `no-syn` feature.
+pub(crate) fn format_generated(tokens: TokenStream) -> String {
+    #[cfg(all(feature = "prettyplease", not(feature = "no-syn")))]
+    if let Ok(file) = syn::parse2::<syn::File>(tokens.clone()) {
+        return prettyplease::unparse(&file);
+    }
+    format_tokens(tokens)
+}
+
+/// Format tokens without parsing them: one statement or item per line, indented blocks and the
+/// usual spacing around punctuation. Works for any input, including expressions and fragments.
+/// Generics and closure parameters are written like `rustfmt` does (`collect::<Vec<_>>()`,
+/// `|a| a + 1`); a `<` after a name counts as generics if a `>` closes it in the same statement.
+pub(crate) fn format_tokens(tokens: TokenStream) -> String {
+    let mut formatter = Formatter {
+        out: String::new(),
+        indent: 0,
+        line_start: true,
+        space: false,
+    };
+    formatter.stream(tokens, Delimiter::Brace);
+    if !formatter.line_start {
+        formatter.out.push('\n');
+    }
+    formatter.out
+}
+
+struct Formatter {
+    out: String,
+    indent: usize,
+    line_start: bool,
+    /// whether the next token should be preceded by a space
+    space: bool,
+}
+
+/// What the previous token was, to decide on spacing
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Previous {
+    Start,
+    Word,
+    Keyword,
+    Punct(char, Spacing),
+    /// The `>` that closes generics
+    Generic,
+    Group,
+    Block,
+}
+
+/// Keywords that are followed by a space before a group, unlike function calls
+const SPACED_KEYWORDS: &[&str] = &[
+    "if", "while", "match", "for", "in", "return", "let", "as", "mut", "move", "else", "where",
+    "impl", "dyn", "break", "yield", "unsafe", "async", "await", "loop", "static", "const",
+];
+
+impl Formatter {
+    fn write(&mut self, text: &str) {
+        if self.line_start {
+            for _ in 0..self.indent {
+                self.out.push_str("    ");
+            }
+            self.line_start = false;
+        } else if self.space {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.space = true;
+    }
+    fn newline(&mut self) {
+        if !self.line_start {
+            self.out.push('\n');
+            self.line_start = true;
+        }
+    }
+
+    fn stream(&mut self, tokens: TokenStream, context: Delimiter) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let statements = context == Delimiter::Brace;
+        let mut previous = Previous::Start;
+        // depth of generics `<...>`, and whether the parameters of a closure `|...|` are open
+        let mut angle_depth = 0;
+        let mut closure_params = false;
+        // whether the previous punct was unary, which the rest of a joint operator inherits
+        let mut unary = false;
+        for (i, token) in tokens.iter().enumerate() {
+            if previous == Previous::Block && statements && !continues_after_block(token) {
+                self.newline();
+            }
+            match token {
+                TokenTree::Ident(ident) => {
+                    let text = ident.to_string();
+                    self.write(&text);
+                    previous = if SPACED_KEYWORDS.contains(&text.as_str()) {
+                        Previous::Keyword
+                    } else {
+                        Previous::Word
+                    };
+                }
+                TokenTree::Literal(literal) => {
+                    self.write(&literal.to_string());
+                    previous = Previous::Word;
+                }
+                TokenTree::Punct(punct)
+                    if punct.as_char() == '<' && is_generic_open(&tokens, i) =>
+                {
+                    // `Vec<T>`, `collect::<T>`, `impl<T>`
+                    self.space = false;
+                    self.write("<");
+                    self.space = false;
+                    angle_depth += 1;
+                    previous = Previous::Punct('<', punct.spacing());
+                }
+                TokenTree::Punct(punct)
+                    if punct.as_char() == '>' && angle_depth > 0 && !is_arrow(previous) =>
+                {
+                    self.space = false;
+                    self.write(">");
+                    self.space = punct.spacing() == Spacing::Alone;
+                    angle_depth -= 1;
+                    previous = Previous::Generic;
+                }
+                TokenTree::Punct(punct)
+                    if punct.as_char() == '|'
+                        && (closure_params
+                            || punct.spacing() == Spacing::Alone
+                                && matches!(
+                                    previous,
+                                    Previous::Start
+                                        | Previous::Keyword
+                                        | Previous::Punct(_, Spacing::Alone)
+                                )) =>
+                {
+                    // the pipes around closure parameters: `|a, b| a + b`
+                    if closure_params {
+                        self.space = false;
+                    }
+                    self.write("|");
+                    self.space = closure_params;
+                    closure_params = !closure_params;
+                    previous = Previous::Punct('|', Spacing::Alone);
+                }
+                TokenTree::Punct(punct) => {
+                    let c = punct.as_char();
+                    if matches!(c, ',' | ';' | '.' | '?' | ':')
+                        || matches!(previous, Previous::Punct(_, Spacing::Joint))
+                    {
+                        self.space = false;
+                    }
+                    if c == '!' && previous == Previous::Word {
+                        // macro invocation
+                        self.space = false;
+                    }
+                    self.write(&c.to_string());
+                    unary = match previous {
+                        Previous::Punct(_, Spacing::Joint) => unary,
+                        _ => {
+                            matches!(c, '&' | '*' | '-' | '!')
+                                && !matches!(
+                                    previous,
+                                    Previous::Word | Previous::Group | Previous::Generic
+                                )
+                        }
+                    };
+                    if punct.spacing() == Spacing::Joint
+                        || unary
+                        || matches!(c, '.' | '#' | '$')
+                        || (c == '!' && previous == Previous::Word)
+                        || (c == ':' && matches!(previous, Previous::Punct(':', Spacing::Joint)))
+                    {
+                        self.space = false;
+                    }
+                    previous = Previous::Punct(c, punct.spacing());
+                    if c == ';' && statements {
+                        self.newline();
+                    }
+                    if c == ',' && statements && angle_depth == 0 {
+                        self.newline();
+                    }
+                }
+                TokenTree::Group(group) => {
+                    let is_attribute = matches!(previous, Previous::Punct('#', _))
+                        || matches!(previous, Previous::Punct('!', _))
+                            && i >= 2
+                            && matches!(&tokens[i - 2], TokenTree::Punct(p) if p.as_char() == '#');
+                    match group.delimiter() {
+                        Delimiter::Brace => {
+                            if group.stream().is_empty() {
+                                self.write("{}");
+                            } else {
+                                self.write("{");
+                                self.indent += 1;
+                                self.newline();
+                                self.stream(group.stream(), Delimiter::Brace);
+                                self.indent -= 1;
+                                self.newline();
+                                self.write("}");
+                            }
+                            previous = Previous::Block;
+                            continue;
+                        }
+                        Delimiter::Parenthesis | Delimiter::Bracket => {
+                            let (open, close) = if group.delimiter() == Delimiter::Parenthesis {
+                                ("(", ")")
+                            } else {
+                                ("[", "]")
+                            };
+                            // function calls and indexing
+                            if matches!(
+                                previous,
+                                Previous::Word | Previous::Group | Previous::Generic
+                            ) {
+                                self.space = false;
+                            }
+                            self.write(open);
+                            self.space = false;
+                            self.stream(group.stream(), group.delimiter());
+                            self.space = false;
+                            self.write(close);
+                        }
+                        Delimiter::None => self.stream(group.stream(), context),
+                    }
+                    previous = Previous::Group;
+                    // attributes get their own line
+                    if is_attribute && statements {
+                        self.newline();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Check if the `<` at `index` opens generics rather than being a comparison: it follows a
+/// path segment, `::` or `impl`, and is closed by a `>` before the end of the statement
+fn is_generic_open(tokens: &[TokenTree], index: usize) -> bool {
+    let punct_at = |i: usize| match tokens.get(i) {
+        Some(TokenTree::Punct(punct)) => Some((punct.as_char(), punct.spacing())),
+        _ => None,
+    };
+    if matches!(punct_at(index), Some(('<', Spacing::Joint))) {
+        return false; // `<<` or `<=`
+    }
+    let after_path = match index.checked_sub(1).map(|i| &tokens[i]) {
+        Some(TokenTree::Ident(ident)) => {
+            ident == "impl" || !SPACED_KEYWORDS.iter().any(|keyword| ident == keyword)
+        }
+        Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => {
+            index >= 2 && matches!(punct_at(index - 2), Some((':', Spacing::Joint)))
+        }
+        _ => false,
+    };
+    if !after_path {
+        return false;
+    }
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(index) {
+        match (punct_at(i), i.checked_sub(1).and_then(punct_at)) {
+            // `->` and `=>`
+            (Some(('>', _)), Some(('-' | '=', Spacing::Joint))) => {}
+            (Some(('<', _)), _) => depth += 1,
+            (Some(('>', _)), _) => {
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            (Some((';', _)), _) => return false,
+            (Some(('&', _)), Some(('&', Spacing::Joint)))
+            | (Some(('|', _)), Some(('|', Spacing::Joint))) => return false,
+            _ if matches!(token, TokenTree::Group(g) if g.delimiter() == Delimiter::Brace) => {
+                return false;
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Check if a `>` after `previous` is part of `->` or `=>`
+fn is_arrow(previous: Previous) -> bool {
+    matches!(previous, Previous::Punct('-' | '=', Spacing::Joint))
+}
+
+/// Tokens that continue an expression after a `{}` block instead of starting a new statement
+fn continues_after_block(token: &TokenTree) -> bool {
+    match token {
+        TokenTree::Punct(punct) => matches!(punct.as_char(), ',' | ';' | '.' | '?' | ')'),
+        TokenTree::Ident(ident) => ident == "else",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn generics() {
+        let tokens = quote!(let v = iter.collect::<Vec<_>>(););
+        assert_eq!(format_tokens(tokens), "let v = iter.collect::<Vec<_>>();\n");
+        let tokens = quote!(
+            impl<T: Clone> Foo<T> for Bar<'a, Option<&'a T>> {}
+        );
+        assert_eq!(
+            format_tokens(tokens),
+            "impl<T: Clone> Foo<T> for Bar<'a, Option<&'a T>> {}\n"
+        );
+        let tokens = quote!(
+            fn f<F: Fn() -> u8>(f: F) -> Vec<u8> {}
+        );
+        assert_eq!(
+            format_tokens(tokens),
+            "fn f<F: Fn() -> u8>(f: F) -> Vec<u8> {}\n"
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        let tokens = quote!(let b = a < c; if a < b && c > d {});
+        assert_eq!(
+            format_tokens(tokens),
+            "let b = a < c;\nif a < b && c > d {}\n"
+        );
+        let tokens = quote!(let x = 1 << 2;);
+        assert_eq!(format_tokens(tokens), "let x = 1 << 2;\n");
+    }
+
+    #[test]
+    fn closures() {
+        let tokens = quote!(let f = v.iter().map(|a| a + 1).filter(move |&a, b: u8| a | b););
+        assert_eq!(
+            format_tokens(tokens),
+            "let f = v.iter().map(|a| a + 1).filter(move |&a, b: u8| a | b);\n"
+        );
+        let tokens = quote!(thread::spawn(|| run()););
+        assert_eq!(format_tokens(tokens), "thread::spawn(|| run());\n");
+    }
+}
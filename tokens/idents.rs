@@ -0,0 +1,230 @@
+#![allow(dead_code)]
+
+use super::super::Edition;
+use super::{walk, TokenVisitor};
+use proc_macro2::{Delimiter, Ident, Spacing, TokenStream, TokenTree};
+use std::collections::HashSet;
+
+/// Collect all identifiers in a stream, at any nesting level
+pub(crate) fn collect_idents(stream: &TokenStream) -> HashSet<Ident> {
+    struct Collect(HashSet<Ident>);
+    impl TokenVisitor for Collect {
+        fn visit_ident(&mut self, ident: &Ident) {
+            self.0.insert(ident.clone());
+        }
+    }
+    let mut collect = Collect(HashSet::new());
+    walk(stream, &mut collect);
+    collect.0
+}
+
+/// Collect the identifiers in an expression or block that look like references to variables
+/// from the surrounding scope, e.g. to generate the captures of a closure or to check which
+/// fields a user-provided expression uses.
+///
+/// This is a heuristic on tokens, not name resolution. Excluded are:
+/// - keywords (except `self`), primitive types and identifiers starting with an uppercase letter
+///   (types, variants and constants)
+/// - fields and methods (`a.b`), path segments (`a::b`), macro names (`a!`), lifetimes and
+///   field names in struct expressions (`Foo { a: 1 }`)
+/// - names bound inside of the stream by `let`, `if let`, `while let`, `for`, closure parameters
+///   and `match` arms. Scopes are ignored, so a name that is bound anywhere in the stream is
+///   excluded everywhere.
+/// ```ignore
+/// // `value + offset * 2` -> {value, offset}
+/// let captures = tokens::collect_free_idents(&expr);
+/// ```
+pub(crate) fn collect_free_idents(stream: &TokenStream) -> HashSet<Ident> {
+    let mut binders = HashSet::new();
+    collect_binders(stream, &mut binders);
+    let mut found = HashSet::new();
+    collect_references(stream, &mut found);
+    found.retain(|ident| !binders.contains(ident));
+    found
+}
+
+/// Primitive type names, which are not keywords but are rarely variables
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64",
+];
+
+fn is_variable_like(ident: &Ident) -> bool {
+    let name = ident.to_string();
+    let name = name.strip_prefix("r#").unwrap_or(&name);
+    let starts_lowercase = name.starts_with(|c: char| c == '_' || c.is_lowercase());
+    name != "_"
+        && (name == "self"
+            || (starts_lowercase
+                && !Edition::LATEST.is_keyword(name)
+                && !PRIMITIVES.contains(&name)))
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == c)
+}
+
+/// Check if the tokens at `i` are `::`
+fn is_path_sep(tokens: &[TokenTree], i: usize) -> bool {
+    matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ':' && p.spacing() == Spacing::Joint)
+        && is_punct(tokens.get(i + 1), ':')
+}
+
+/// Check if the tokens at `i` are a single `:`, not part of `::`
+fn is_single_colon(tokens: &[TokenTree], i: usize) -> bool {
+    is_punct(tokens.get(i), ':')
+        && !is_path_sep(tokens, i)
+        && !(i > 0 && is_path_sep(tokens, i - 1))
+}
+
+fn collect_references(stream: &TokenStream, found: &mut HashSet<Ident>) {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        let ident = match token {
+            TokenTree::Group(group) => {
+                collect_references(&group.stream(), found);
+                continue;
+            }
+            TokenTree::Ident(ident) if is_variable_like(ident) => ident,
+            _ => continue,
+        };
+        let prev = i.checked_sub(1).and_then(|i| tokens.get(i));
+        let excluded = is_punct(prev, '.')
+            || is_punct(prev, '\'')
+            || (i >= 2 && is_path_sep(&tokens, i - 2))
+            || is_path_sep(&tokens, i + 1)
+            || is_punct(tokens.get(i + 1), '!')
+            // field name in `Foo { a: 1, b: 2 }`
+            || ((prev.is_none() || is_punct(prev, ',')) && is_single_colon(&tokens, i + 1));
+        if !excluded {
+            found.insert(ident.clone());
+        }
+    }
+}
+
+fn collect_binders(stream: &TokenStream, binders: &mut HashSet<Ident>) {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Ident(ident) if ident == "let" => {
+                // the pattern ends at the type, value or end of the statement
+                let end = (i + 1..tokens.len())
+                    .find(|&j| {
+                        is_punct(tokens.get(j), '=')
+                            || is_punct(tokens.get(j), ';')
+                            || is_single_colon(&tokens, j)
+                    })
+                    .unwrap_or(tokens.len());
+                pattern_binders(&tokens[i + 1..end], binders);
+                i = end;
+                continue;
+            }
+            TokenTree::Ident(ident) if ident == "for" => {
+                let end = (i + 1..tokens.len())
+                    .find(|&j| matches!(&tokens[j], TokenTree::Ident(ident) if ident == "in"));
+                if let Some(end) = end {
+                    pattern_binders(&tokens[i + 1..end], binders);
+                    i = end;
+                    continue;
+                }
+            }
+            TokenTree::Ident(ident) if ident == "match" => {
+                let arms = tokens[i + 1..].iter().find_map(|token| match token {
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                        Some(group.stream())
+                    }
+                    _ => None,
+                });
+                if let Some(arms) = arms {
+                    match_arm_binders(&arms, binders);
+                }
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '|' && starts_closure(&tokens, i) => {
+                let end = (i + 1..tokens.len()).find(|&j| is_punct(tokens.get(j), '|'));
+                if let Some(end) = end {
+                    // parameter types are written with a single `:`
+                    let params: Vec<TokenTree> = tokens[i + 1..end].to_vec();
+                    for param in super::split_top_level(params.into_iter().collect(), ',') {
+                        let param: Vec<TokenTree> = param.0.into_iter().collect();
+                        let end = (0..param.len())
+                            .find(|&j| is_single_colon(&param, j))
+                            .unwrap_or(param.len());
+                        pattern_binders(&param[..end], binders);
+                    }
+                    i = end + 1;
+                    continue;
+                }
+            }
+            TokenTree::Group(group) => collect_binders(&group.stream(), binders),
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Check if the `|` at `i` starts the parameters of a closure rather than being a binary or
+fn starts_closure(tokens: &[TokenTree], i: usize) -> bool {
+    match i.checked_sub(1).map(|i| &tokens[i]) {
+        None => true,
+        Some(TokenTree::Ident(ident)) => ident == "move" || ident == "return",
+        // `a | b` has a value before the `|`, `= |x|` or `, |x|` don't
+        Some(TokenTree::Punct(punct)) => matches!(punct.as_char(), '=' | ',' | ';' | '&'),
+        Some(_) => false,
+    }
+}
+
+/// Binders in the `match` arms of a brace group: everything before a top-level `=>`
+fn match_arm_binders(arms: &TokenStream, binders: &mut HashSet<Ident>) {
+    let tokens: Vec<TokenTree> = arms.clone().into_iter().collect();
+    let mut start = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_punct(tokens.get(i), '=') && is_punct(tokens.get(i + 1), '>') {
+            // a guard `if ...` is not part of the pattern
+            let end = (start..i)
+                .find(|&j| matches!(&tokens[j], TokenTree::Ident(ident) if ident == "if"))
+                .unwrap_or(i);
+            pattern_binders(&tokens[start..end], binders);
+            // skip the body: a block, optionally followed by `,`, or an expression up to `,`
+            i += 2;
+            if matches!(tokens.get(i), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace)
+            {
+                i += 1;
+                if is_punct(tokens.get(i), ',') {
+                    i += 1;
+                }
+            } else {
+                while i < tokens.len() && !is_punct(tokens.get(i), ',') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            start = i;
+            continue;
+        }
+        i += 1;
+    }
+}
+
+/// The names bound by a pattern: lowercase identifiers that are not paths, struct or tuple
+/// struct names, or field names in struct patterns
+fn pattern_binders(tokens: &[TokenTree], binders: &mut HashSet<Ident>) {
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => {
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                pattern_binders(&inner, binders);
+            }
+            TokenTree::Ident(ident) if is_variable_like(ident) => {
+                let is_path = (i >= 2 && is_path_sep(tokens, i - 2)) || is_path_sep(tokens, i + 1);
+                let is_name = matches!(tokens.get(i + 1), Some(TokenTree::Group(g)) if g.delimiter() != Delimiter::Bracket);
+                let is_field = is_single_colon(tokens, i + 1);
+                if !is_path && !is_name && !is_field {
+                    binders.insert(ident.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
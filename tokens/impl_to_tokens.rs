@@ -0,0 +1,72 @@
+#![allow(dead_code)]
+
+/// Implement `ToTokens` for the intermediate representation types of a macro, with one `quote!`
+/// template per type (or per enum variant). The listed fields are bound by reference, so the
+/// template can use them as `#field`, including in repetitions.
+///
+/// Structs list the named fields that the template uses in braces, or the first positional
+/// fields in parentheses, or nothing if the template doesn't need any. Enum variants are matched
+/// like patterns, so tuple variants can skip fields with `_` or `..`. Types can have lifetime
+/// parameters, but no type parameters.
+/// ```ignore
+/// impl_to_tokens! {
+///     struct Getter { vis, name, ty } => {
+///         #vis fn #name(&self) -> &#ty { &self.#name }
+///     }
+///     struct Getters<'a>(ty, getters) => {
+///         impl #ty { #(#getters)* }
+///     }
+///     enum Access {
+///         Shared => { & },
+///         Unique { lifetime } => { & #lifetime mut },
+///         Owned(..) => {},
+///     }
+/// }
+/// ```
+#[allow(unused_macros)]
+macro_rules! impl_to_tokens {
+    () => {};
+    (
+        struct $name:ident $(<$($lifetime:lifetime),+>)?
+            $({ $($field:ident),* $(,)? })?
+            $(( $($position:tt),* $(,)? ))?
+            => { $($template:tt)* }
+        $($rest:tt)*
+    ) => {
+        impl<$($($lifetime),+)?> ::quote::ToTokens for $name<$($($lifetime),+)?> {
+            fn to_tokens(&self, tokens: &mut ::proc_macro2::TokenStream) {
+                $(let Self { $($field,)* .. } = self;)?
+                $(let Self($($position,)* ..) = self;)?
+                tokens.extend(::quote::quote!($($template)*));
+            }
+        }
+        impl_to_tokens!($($rest)*);
+    };
+    (
+        enum $name:ident $(<$($lifetime:lifetime),+>)? {
+            $(
+                $variant:ident
+                    $({ $($field:ident),* $(,)? })?
+                    $(( $($position:tt),* $(,)? ))?
+                    => { $($template:tt)* }
+            ),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        impl<$($($lifetime),+)?> ::quote::ToTokens for $name<$($($lifetime),+)?> {
+            fn to_tokens(&self, tokens: &mut ::proc_macro2::TokenStream) {
+                match self {
+                    $(
+                        Self::$variant
+                            $({ $($field,)* .. })?
+                            $(($($position),*))?
+                            => tokens.extend(::quote::quote!($($template)*)),
+                    )*
+                }
+            }
+        }
+        impl_to_tokens!($($rest)*);
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use impl_to_tokens;
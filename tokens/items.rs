@@ -0,0 +1,344 @@
+#![allow(dead_code)]
+
+use super::{eq, fingerprint, TokenRope};
+use proc_macro2::{Delimiter, Ident, Spacing, TokenStream, TokenTree};
+use std::collections::HashMap;
+
+/// An item found by `split_items`. Can be serialized with the `serde` feature, without spans.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ItemRef {
+    /// All tokens of the item, including attributes
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_tokens"))]
+    pub tokens: TokenStream,
+    /// The keyword that determines what kind of item this is (`fn`, `struct`, `impl`,
+    /// `macro_rules`, ...), if one was found
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_option_ident"))]
+    pub kind: Option<Ident>,
+    /// The name of the item, if it has one (`impl` blocks don't)
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_option_ident"))]
+    pub name: Option<Ident>,
+}
+
+impl ItemRef {
+    /// The item as a string, e.g. for sorting items without a name
+    pub fn text(&self) -> String {
+        self.tokens.to_string()
+    }
+}
+
+/// Keywords that start an item and are followed by its name
+const NAMED_ITEMS: &[&str] = &[
+    "fn",
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "type",
+    "mod",
+    "const",
+    "static",
+    "macro_rules",
+];
+/// Keywords that start an item without a name
+const UNNAMED_ITEMS: &[&str] = &["impl", "use"];
+
+/// Item keywords after which a `=` at the top level starts an initializer or the aliased type
+const ASSIGNED_ITEMS: &[&str] = &["const", "static", "type", "let"];
+
+/// Split a stream into top-level items without parsing them. An item ends at a `;` or at a
+/// `{ ... }` block, unless it is a `const`, `static`, `type` or `let` with a `=` before the
+/// block, as in `static X: Foo = Foo { .. };`. A `=` inside of `<...>` (generic defaults and
+/// associated type bindings like `Iterator<Item = u8>`) doesn't count. Attributes belong to the
+/// item that follows them.
+///
+/// This is only meant for code generated by the macro itself, where the items are known to be
+/// well-formed. Use `syn::File` for everything else.
+pub(crate) fn split_items(stream: TokenStream) -> Vec<ItemRef> {
+    let mut items = vec![];
+    let mut current = vec![];
+    let mut state = SplitState::default();
+    let mut tokens = stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let ends_item = match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ';' => true,
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                // `const _: () = { ... };` continues until the `;`
+                !state.has_assignment
+                    && !matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == ';')
+            }
+            token => {
+                state.visit(token);
+                false
+            }
+        };
+        current.push(token);
+        if ends_item {
+            items.push(item_ref(std::mem::take(&mut current)));
+            state = SplitState::default();
+        }
+    }
+    if !current.is_empty() {
+        items.push(item_ref(current));
+    }
+    items
+}
+
+/// What `split_items` knows about the current item
+#[derive(Default)]
+struct SplitState {
+    /// The first item keyword, with `const fn` counting as `fn`
+    kind: Option<String>,
+    /// The previous token was the `const` keyword of the item
+    after_const: bool,
+    /// Depth of `<...>`
+    angle_depth: usize,
+    /// The previous token was a joint `-` or `=`, so a `>` is part of `->` or `=>`
+    arrow: bool,
+    has_assignment: bool,
+}
+
+impl SplitState {
+    fn visit(&mut self, token: &TokenTree) {
+        let arrow = std::mem::take(&mut self.arrow);
+        match token {
+            TokenTree::Ident(ident) => {
+                let after_const = std::mem::take(&mut self.after_const);
+                let name = ident.to_string();
+                if self.kind.is_none()
+                    && (NAMED_ITEMS.contains(&name.as_str())
+                        || UNNAMED_ITEMS.contains(&name.as_str())
+                        || name == "let")
+                {
+                    self.after_const = name == "const";
+                    self.kind = Some(name);
+                } else if after_const
+                    && matches!(name.as_str(), "fn" | "unsafe" | "async" | "extern")
+                {
+                    self.kind = Some("fn".to_string());
+                }
+            }
+            TokenTree::Punct(punct) => {
+                self.after_const = false;
+                match punct.as_char() {
+                    '<' => self.angle_depth += 1,
+                    '>' if !arrow => self.angle_depth = self.angle_depth.saturating_sub(1),
+                    '=' if self.angle_depth == 0
+                        && self
+                            .kind
+                            .as_deref()
+                            .is_some_and(|kind| ASSIGNED_ITEMS.contains(&kind)) =>
+                    {
+                        self.has_assignment = true;
+                    }
+                    _ => {}
+                }
+                self.arrow =
+                    matches!(punct.as_char(), '-' | '=') && punct.spacing() == Spacing::Joint;
+            }
+            _ => self.after_const = false,
+        }
+    }
+}
+
+/// Sort the top-level items of a stream by a key, keeping items with equal keys in their original
+/// order. Useful when the items are generated from data with unstable order (like a `HashMap`),
+/// to get the same output in every build:
+/// ```ignore
+/// let output = tokens::sort_items(output, |item| (item.kind.as_ref().map(Ident::to_string), item.text()));
+/// ```
+pub(crate) fn sort_items<K: Ord>(
+    stream: TokenStream,
+    mut key: impl FnMut(&ItemRef) -> K,
+) -> TokenStream {
+    let mut items: Vec<(K, ItemRef)> = split_items(stream)
+        .into_iter()
+        .map(|item| (key(&item), item))
+        .collect();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut out = TokenRope::new();
+    for (_, item) in items {
+        out.push(item.tokens);
+    }
+    out.finish()
+}
+
+/// Remove top-level items that are identical (ignoring spans) to an earlier item, keeping the
+/// first one. Useful when several parts of a macro (or several fields) independently emit the same
+/// helper function or impl, which would otherwise fail with "defined multiple times" (E0428).
+///
+/// Items with the same name but different content are all kept, since removing them would hide a
+/// real conflict.
+pub(crate) fn dedup_items(stream: TokenStream) -> TokenStream {
+    let mut seen: HashMap<u64, Vec<TokenStream>> = HashMap::new();
+    let mut out = TokenRope::new();
+    for item in split_items(stream) {
+        let candidates = seen.entry(fingerprint(&item.tokens)).or_default();
+        // the fingerprint only narrows it down, hash collisions are possible
+        if candidates.iter().any(|other| eq(other, &item.tokens)) {
+            continue;
+        }
+        candidates.push(item.tokens.clone());
+        out.push(item.tokens);
+    }
+    out.finish()
+}
+
+/// Sort the top-level items by name, with unnamed items (`impl`, `use`, ...) sorted by their
+/// text after all named ones
+pub(crate) fn sort_items_by_name(stream: TokenStream) -> TokenStream {
+    sort_items(stream, |item| match &item.name {
+        Some(name) => (0, name.to_string()),
+        None => (1, item.text()),
+    })
+}
+
+fn item_ref(tokens: Vec<TokenTree>) -> ItemRef {
+    let mut kind = None;
+    let mut name = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        // skips attributes, visibility, `unsafe`, `extern "C"`, ...
+        let TokenTree::Ident(ident) = &tokens[i] else {
+            i += 1;
+            continue;
+        };
+        let keyword = ident.to_string();
+        if UNNAMED_ITEMS.contains(&keyword.as_str()) {
+            kind = Some(ident.clone());
+            break;
+        }
+        if !NAMED_ITEMS.contains(&keyword.as_str()) {
+            i += 1;
+            continue;
+        }
+        kind = Some(ident.clone());
+        let mut next = i + 1;
+        if keyword == "macro_rules" {
+            next += 1; // the `!`
+        }
+        if matches!(tokens.get(next), Some(TokenTree::Ident(ident)) if ident == "mut") {
+            next += 1;
+        }
+        match tokens.get(next) {
+            // `const fn`, `const unsafe fn`, ...
+            Some(TokenTree::Ident(ident))
+                if ident == "unsafe" || NAMED_ITEMS.iter().any(|k| ident == k) =>
+            {
+                i = next;
+                continue;
+            }
+            Some(TokenTree::Ident(ident)) if ident != "_" => name = Some(ident.clone()),
+            _ => {}
+        }
+        break;
+    }
+    ItemRef {
+        tokens: tokens.into_iter().collect(),
+        kind,
+        name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn names(stream: TokenStream) -> Vec<String> {
+        split_items(stream)
+            .iter()
+            .map(|item| match (&item.kind, &item.name) {
+                (Some(kind), Some(name)) => format!("{} {}", kind, name),
+                (Some(kind), None) => kind.to_string(),
+                _ => item.text(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_where_clause_and_generic_default() {
+        let stream = quote! {
+            impl<T> Foo for T where T: Iterator<Item = u8> { fn a() {} }
+            fn b() {}
+            struct S<T = u8> { x: T }
+            struct R;
+        };
+        assert_eq!(names(stream), ["impl", "fn b", "struct S", "struct R"]);
+    }
+
+    #[test]
+    fn split_associated_type_bindings() {
+        let stream = quote! {
+            fn iter() -> impl Iterator<Item = u8> { None.into_iter() }
+            trait Tr: Deref<Target = str> { type Out<T = u8>: Into<T>; }
+            fn with_arrow<F: Fn() -> u8, I: Iterator<Item = F>>(f: I) {}
+            enum E { A = 1 }
+        };
+        assert_eq!(
+            names(stream),
+            ["fn iter", "trait Tr", "fn with_arrow", "enum E"]
+        );
+    }
+
+    #[test]
+    fn split_initializers() {
+        let stream = quote! {
+            static X: Foo = Foo { a: 1 };
+            const _: () = { assert!(true) };
+            const fn f() -> u8 { 1 }
+            type A<T = u8> = Vec<T>;
+            const N: Option<u8> = match 1 { _ => None };
+            #[attr] pub(crate) struct Last {}
+        };
+        assert_eq!(
+            names(stream),
+            [
+                "static X",
+                "const",
+                "fn f",
+                "type A",
+                "const N",
+                "struct Last"
+            ]
+        );
+    }
+
+    fn where_clause_fixture() -> TokenStream {
+        quote! {
+            struct Z<T = u8>(T);
+            impl<T> Foo for T where T: Iterator<Item = u8> { fn a() {} }
+            fn b() where u8: Into<Option<u8>> {}
+            fn a() {}
+            struct Z<T = u8>(T);
+            impl<T> Foo for T where T: Iterator<Item = u8> { fn a() {} }
+        }
+    }
+
+    #[test]
+    fn dedup_with_where_clauses() {
+        let expected = quote! {
+            struct Z<T = u8>(T);
+            impl<T> Foo for T where T: Iterator<Item = u8> { fn a() {} }
+            fn b() where u8: Into<Option<u8>> {}
+            fn a() {}
+        };
+        assert!(eq(&dedup_items(where_clause_fixture()), &expected));
+    }
+
+    #[test]
+    fn sort_with_where_clauses() {
+        let sorted = sort_items_by_name(where_clause_fixture());
+        assert_eq!(
+            names(sorted),
+            ["struct Z", "struct Z", "fn a", "fn b", "impl", "impl"]
+        );
+        let by_kind = sort_items(where_clause_fixture(), |item| {
+            item.kind.as_ref().map(Ident::to_string)
+        });
+        assert_eq!(
+            names(by_kind),
+            ["fn b", "fn a", "impl", "impl", "struct Z", "struct Z"]
+        );
+    }
+}
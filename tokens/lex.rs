@@ -0,0 +1,105 @@
+#![allow(dead_code)]
+
+use super::super::{Error, Result};
+use proc_macro2::{Span, TokenStream};
+
+/// Lex source code from a string (e.g. an attribute value or a file) into tokens that all have
+/// the span `span`, usually the span of the literal the string came from.
+///
+/// Lexer errors are reported at `span`, with their line and column in `source` in the message,
+/// since the span can't point inside of it. Delimiter and literal errors are located exactly;
+/// for other errors, the position is the first word after which `source` no longer lexes.
+/// ```ignore
+/// // #[my_attr(where_clause = "T: Clone, U: Default")]
+/// let bounds = tokens::lex_at(&lit.value(), lit.span())?;
+/// ```
+pub(crate) fn lex_at(source: &str, span: Span) -> Result<TokenStream> {
+    if let Err(error) = super::check_delimiters(source) {
+        let message = format!("invalid tokens: {}", error);
+        return Error::err(span, with_line(message, source, error.line));
+    }
+    match source.parse::<TokenStream>() {
+        Ok(tokens) => Ok(super::respan(tokens, span)),
+        Err(e) => {
+            let Some((line, column)) = locate_error(source) else {
+                return Error::err(span, format!("invalid tokens: {}", e));
+            };
+            let message = format!("invalid tokens: {} at {}:{}", e, line, column);
+            Error::err(span, with_line(message, source, line))
+        }
+    }
+}
+
+/// Add the offending line to the message if `source` has more than one
+fn with_line(mut message: String, source: &str, line: usize) -> String {
+    if source.contains('\n') {
+        if let Some(text) = source.lines().nth(line - 1) {
+            message += &format!("\n --> line {}: {}", line, text.trim());
+        }
+    }
+    message
+}
+
+/// The 1-based line and column of a lexer error in `source`, whose delimiters are balanced.
+///
+/// A prefix of `source` lexes (after closing its open delimiters) unless the error is in it, so
+/// the error is on the first line whose prefix doesn't lex, at the first word of that line after
+/// which it doesn't. Prefixes that end inside of a literal or comment are skipped.
+fn locate_error(source: &str) -> Option<(usize, usize)> {
+    let lexes = |end: usize| {
+        let prefix = &source[..end];
+        let closing = super::missing_delimiters(prefix).ok()?;
+        Some(
+            format!("{}{}", prefix, closing)
+                .parse::<TokenStream>()
+                .is_ok(),
+        )
+    };
+    let mut line_start = 0;
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        if lexes(line_start + line.len()) != Some(false) {
+            line_start += line.len();
+            continue;
+        }
+        let mut word_end = 0;
+        for word in line.split_inclusive(char::is_whitespace) {
+            let word_start = word_end;
+            word_end += word.len();
+            if !word.trim().is_empty() && lexes(line_start + word_end) == Some(false) {
+                return Some((index + 1, line[..word_start].chars().count() + 1));
+            }
+        }
+        return Some((index + 1, 1));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        let Err(error) = lex_at(source, Span::call_site()) else {
+            panic!("{:?} should not lex", source);
+        };
+        TokenStream::from(error).to_string()
+    }
+
+    #[test]
+    fn lexer_errors_have_a_position() {
+        assert!(error("a + 1 ¤ 2").contains(" at 1:7"));
+        let message = error("fn a() {\n    let s = \"(\";\n    let x = 1 ¤ 2;\n}");
+        assert!(message.contains(" at 3:15"), "{}", message);
+        assert!(
+            message.contains("--> line 3: let x = 1 ¤ 2;"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn delimiter_errors_have_a_position() {
+        assert!(error("f(a]").contains("mismatched closing delimiter `]`"));
+        assert!(error("f(a]").contains(" at 1:4"));
+    }
+}
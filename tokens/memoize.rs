@@ -0,0 +1,99 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Cached outputs by fingerprint. Token streams can't be kept between invocations (or sent
+/// between threads), so the outputs are stored without spans.
+static CACHE: Mutex<Option<HashMap<u64, Vec<CachedTree>>>> = Mutex::new(None);
+
+enum CachedTree {
+    Group(Delimiter, Vec<CachedTree>),
+    Ident(String),
+    Punct(char, Spacing),
+    Literal(String),
+}
+
+/// Run `expand`, or return its output from an earlier call with the same `fingerprint` in this
+/// compiler process. Meant for derives that are applied to many structurally identical types,
+/// e.g. ones generated by another macro, where every expansion would be the same work.
+///
+/// The fingerprint has to cover everything the output depends on, including the name of the
+/// macro (different macros can get the same input) and any options. Use `StableHasher` to
+/// combine them. Only successful outputs are cached, so errors keep their spans.
+///
+/// Cached outputs have `Span::call_site()` everywhere, so only use this for outputs that don't
+/// rely on input spans for hygiene or error locations. The cache is not a substitute for
+/// correctness: a fingerprint collision (unlikely, but the hash isn't cryptographic) returns the
+/// output of a different input.
+/// ```ignore
+/// let mut key = StableHasher::new();
+/// key.write_str("getters");
+/// key.write_tokens(input.clone());
+/// tokens::memoize(key.finish(), || derive_getters(input))
+/// ```
+pub(crate) fn memoize<E>(
+    fingerprint: u64,
+    expand: impl FnOnce() -> Result<TokenStream, E>,
+) -> Result<TokenStream, E> {
+    {
+        let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.as_ref().and_then(|cache| cache.get(&fingerprint)) {
+            return Ok(restore(cached, Span::call_site()));
+        }
+    }
+    // the lock is not held while expanding, so `expand` can use `memoize` itself
+    let output = expand()?;
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get_or_insert_with(HashMap::new)
+        .insert(fingerprint, store(output.clone()));
+    Ok(output)
+}
+
+/// Remove all cached outputs, e.g. between tests
+pub(crate) fn clear_memoized() {
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn store(stream: TokenStream) -> Vec<CachedTree> {
+    stream
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => CachedTree::Group(group.delimiter(), store(group.stream())),
+            TokenTree::Ident(ident) => CachedTree::Ident(ident.to_string()),
+            TokenTree::Punct(punct) => CachedTree::Punct(punct.as_char(), punct.spacing()),
+            TokenTree::Literal(literal) => CachedTree::Literal(literal.to_string()),
+        })
+        .collect()
+}
+
+fn restore(trees: &[CachedTree], span: Span) -> TokenStream {
+    trees
+        .iter()
+        .map(|tree| -> TokenTree {
+            match tree {
+                CachedTree::Group(delimiter, stream) => {
+                    let mut group = Group::new(*delimiter, restore(stream, span));
+                    group.set_span(span);
+                    group.into()
+                }
+                CachedTree::Ident(name) => match name.strip_prefix("r#") {
+                    Some(raw) => Ident::new_raw(raw, span).into(),
+                    None => Ident::new(name, span).into(),
+                },
+                CachedTree::Punct(c, spacing) => {
+                    let mut punct = Punct::new(*c, *spacing);
+                    punct.set_span(span);
+                    punct.into()
+                }
+                CachedTree::Literal(s) => {
+                    let mut literal: Literal = s.parse().expect("stored from a valid literal");
+                    literal.set_span(span);
+                    literal.into()
+                }
+            }
+        })
+        .collect()
+}
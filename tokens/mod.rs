@@ -0,0 +1,60 @@
+#![allow(dead_code)]
+
+mod balance;
+mod cursor;
+mod diff;
+mod eq;
+mod extract;
+mod fingerprint;
+mod flatten;
+mod format;
+mod idents;
+mod impl_to_tokens;
+mod items;
+mod lex;
+mod memoize;
+mod pattern;
+mod punct;
+mod rename;
+mod rope;
+mod sequence;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "serialize-tokens")]
+mod serialize;
+mod splice;
+mod split;
+mod stats;
+mod strip;
+mod template;
+mod visit;
+mod writer;
+pub(crate) use balance::*;
+pub(crate) use cursor::*;
+pub(crate) use diff::*;
+pub(crate) use eq::*;
+pub(crate) use extract::*;
+pub(crate) use fingerprint::*;
+pub(crate) use flatten::*;
+pub(crate) use format::*;
+pub(crate) use idents::*;
+pub(crate) use impl_to_tokens::*;
+pub(crate) use items::*;
+pub(crate) use lex::*;
+pub(crate) use memoize::*;
+pub(crate) use pattern::*;
+pub(crate) use punct::*;
+pub(crate) use rename::*;
+pub(crate) use rope::*;
+pub(crate) use sequence::*;
+#[cfg(feature = "serde")]
+pub(crate) use serde_support::*;
+#[cfg(feature = "serialize-tokens")]
+pub(crate) use serialize::*;
+pub(crate) use splice::*;
+pub(crate) use split::*;
+pub(crate) use stats::*;
+pub(crate) use strip::*;
+pub(crate) use template::*;
+pub(crate) use visit::*;
+pub(crate) use writer::*;
@@ -0,0 +1,332 @@
+#![allow(dead_code)]
+
+use super::super::{Error, Result};
+use super::{leaf_eq, TokenRope};
+use proc_macro2::{Delimiter, Group, Ident, Literal, Span, TokenStream, TokenTree};
+
+/// Create a pattern for `tokens::replace_all` and `tokens::find_all` from tokens, where
+/// `$name:kind` captures a single token. See `Pattern` for the available kinds.
+/// ```ignore
+/// let body = tokens::replace_all(body, pattern!(self . $field:ident), |captures| {
+///     let field = &captures["field"];
+///     quote! { this.#field }
+/// });
+/// ```
+/// Unlike `quote!`, this does not interpolate `#var`, so it can be used to match attributes.
+#[allow(unused_macros)]
+macro_rules! pattern {
+    ($($tokens:tt)*) => {
+        <::proc_macro2::TokenStream as ::core::str::FromStr>::from_str(stringify!($($tokens)*))
+            .expect("pattern! contains invalid tokens")
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use pattern;
+
+/// What a `$name:kind` capture in a pattern matches
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum CaptureKind {
+    /// `$name:ident`: an identifier
+    Ident,
+    /// `$name:lit`: a literal
+    Lit,
+    /// `$name:punct`: a single punctuation character
+    Punct,
+    /// `$name:group`: a delimited group
+    Group,
+    /// `$name:tt`: any single token tree
+    Tt,
+}
+
+impl CaptureKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ident" => Some(CaptureKind::Ident),
+            "lit" => Some(CaptureKind::Lit),
+            "punct" => Some(CaptureKind::Punct),
+            "group" => Some(CaptureKind::Group),
+            "tt" => Some(CaptureKind::Tt),
+            _ => None,
+        }
+    }
+    fn matches(self, token: &TokenTree) -> bool {
+        match self {
+            CaptureKind::Ident => matches!(token, TokenTree::Ident(_)),
+            CaptureKind::Lit => matches!(token, TokenTree::Literal(_)),
+            CaptureKind::Punct => matches!(token, TokenTree::Punct(_)),
+            CaptureKind::Group => matches!(token, TokenTree::Group(_)),
+            CaptureKind::Tt => true,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum PatternToken {
+    Exact(TokenTree),
+    Group(Delimiter, Vec<PatternToken>),
+    Capture(String, CaptureKind),
+}
+
+/// A sequence of tokens to search for in a `TokenStream`. Each element of the pattern matches
+/// exactly one token tree:
+/// - Identifiers, literals and punctuation match themselves (ignoring spans)
+/// - Groups match groups with the same delimiter whose entire contents match
+/// - `$name:ident`, `$name:lit`, `$name:punct`, `$name:group` and `$name:tt` match a single token
+///   of that kind and make it available as `captures["name"]`
+/// - `$$` matches a literal `$`
+///
+/// Create patterns with `pattern!` or `Pattern::parse`.
+#[derive(Clone)]
+pub(crate) struct Pattern(Vec<PatternToken>);
+
+impl Pattern {
+    /// Parse a pattern from tokens, returning an error for malformed captures. Use this for
+    /// patterns provided by users of the macro.
+    pub fn parse(tokens: TokenStream) -> Result<Self> {
+        let mut names = vec![];
+        let pattern = parse_level(tokens, &mut names)?;
+        if pattern.is_empty() {
+            return Error::err(Span::call_site(), "pattern must not be empty");
+        }
+        Ok(Pattern(pattern))
+    }
+    /// The number of token trees (at the top level) that the pattern matches
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Always `false`, patterns can't be empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Panics if the tokens are not a valid pattern. Use `Pattern::parse` for patterns from user
+/// input.
+impl From<TokenStream> for Pattern {
+    fn from(tokens: TokenStream) -> Self {
+        let text = tokens.to_string();
+        Pattern::parse(tokens).unwrap_or_else(|_| panic!("invalid token pattern `{}`", text))
+    }
+}
+
+impl From<&Pattern> for Pattern {
+    fn from(pattern: &Pattern) -> Self {
+        pattern.clone()
+    }
+}
+
+fn parse_level(tokens: TokenStream, names: &mut Vec<String>) -> Result<Vec<PatternToken>> {
+    let mut iter = tokens.into_iter();
+    let mut out = vec![];
+    while let Some(token) = iter.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => match iter.next() {
+                Some(TokenTree::Punct(dollar)) if dollar.as_char() == '$' => {
+                    out.push(PatternToken::Exact(TokenTree::Punct(dollar)));
+                }
+                Some(TokenTree::Ident(name)) => {
+                    let colon = iter.next();
+                    let kind = iter.next();
+                    let (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(kind))) =
+                        (&colon, &kind)
+                    else {
+                        return Error::err(
+                            name.span(),
+                            format!("expected `${}:kind` in pattern", name),
+                        );
+                    };
+                    if colon.as_char() != ':' {
+                        return Error::err(colon.span(), "expected `:`");
+                    }
+                    let Some(kind) = CaptureKind::from_name(&kind.to_string()) else {
+                        return Error::err(
+                            kind.span(),
+                            "unknown capture kind. Expected one of `ident`, `lit`, `punct`, `group` or `tt`",
+                        );
+                    };
+                    let name = name.to_string();
+                    if names.contains(&name) {
+                        return Error::err(
+                            punct.span(),
+                            format!("duplicate capture `${}` in pattern", name),
+                        );
+                    }
+                    names.push(name.clone());
+                    out.push(PatternToken::Capture(name, kind));
+                }
+                _ => return Error::err(punct.span(), "expected `$name:kind` or `$$` in pattern"),
+            },
+            TokenTree::Group(group) => {
+                let inner = parse_level(group.stream(), names)?;
+                out.push(PatternToken::Group(group.delimiter(), inner));
+            }
+            token => out.push(PatternToken::Exact(token)),
+        }
+    }
+    Ok(out)
+}
+
+/// The tokens matched by a `Pattern`
+#[derive(Clone)]
+pub(crate) struct Captures {
+    captures: Vec<(String, TokenTree)>,
+    matched: Vec<TokenTree>,
+}
+
+impl Captures {
+    /// The token captured as `$name`
+    pub fn get(&self, name: &str) -> Option<&TokenTree> {
+        self.captures
+            .iter()
+            .find(|(capture, _)| capture == name)
+            .map(|(_, token)| token)
+    }
+    /// The identifier captured as `$name:ident`
+    pub fn ident(&self, name: &str) -> Option<&Ident> {
+        match self.get(name)? {
+            TokenTree::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+    /// The literal captured as `$name:lit`
+    pub fn literal(&self, name: &str) -> Option<&Literal> {
+        match self.get(name)? {
+            TokenTree::Literal(literal) => Some(literal),
+            _ => None,
+        }
+    }
+    /// The full sequence of tokens that matched the pattern
+    pub fn matched(&self) -> TokenStream {
+        self.matched.iter().cloned().collect()
+    }
+    /// The span of the first matched token
+    pub fn span(&self) -> Span {
+        self.matched[0].span()
+    }
+}
+
+/// Panics if there is no capture with that name
+impl std::ops::Index<&str> for Captures {
+    type Output = TokenTree;
+    fn index(&self, name: &str) -> &TokenTree {
+        self.get(name)
+            .unwrap_or_else(|| panic!("pattern has no capture named `{}`", name))
+    }
+}
+
+/// Replace all non-overlapping occurrences of `pattern` in `stream` (including inside of
+/// groups) with the tokens returned by `replace`. Everything else is left unchanged. Replacement
+/// tokens are not searched again.
+pub(crate) fn replace_all(
+    stream: TokenStream,
+    pattern: impl Into<Pattern>,
+    mut replace: impl FnMut(&Captures) -> TokenStream,
+) -> TokenStream {
+    replace_level(stream, &pattern.into().0, &mut replace)
+}
+
+/// Find all non-overlapping occurrences of `pattern` in `stream`, including inside of groups,
+/// in the order they appear
+pub(crate) fn find_all(stream: TokenStream, pattern: impl Into<Pattern>) -> Vec<Captures> {
+    let mut found = vec![];
+    find_level(stream, &pattern.into().0, &mut found);
+    found
+}
+
+fn replace_level(
+    stream: TokenStream,
+    pattern: &[PatternToken],
+    replace: &mut impl FnMut(&Captures) -> TokenStream,
+) -> TokenStream {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut out = TokenRope::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(captures) = match_at(&tokens[i..], pattern) {
+            out.push(replace(&captures));
+            i += pattern.len();
+            continue;
+        }
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                let stream = replace_level(group.stream(), pattern, replace);
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                out.push_tree(TokenTree::Group(new_group));
+            }
+            token => {
+                out.push_tree(token.clone());
+            }
+        }
+        i += 1;
+    }
+    out.finish()
+}
+
+fn find_level(stream: TokenStream, pattern: &[PatternToken], found: &mut Vec<Captures>) {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(captures) = match_at(&tokens[i..], pattern) {
+            found.push(captures);
+            i += pattern.len();
+            continue;
+        }
+        if let TokenTree::Group(group) = &tokens[i] {
+            find_level(group.stream(), pattern, found);
+        }
+        i += 1;
+    }
+}
+
+/// Check if the pattern matches at the start of `tokens`
+fn match_at(tokens: &[TokenTree], pattern: &[PatternToken]) -> Option<Captures> {
+    if tokens.len() < pattern.len() {
+        return None;
+    }
+    let mut captures = vec![];
+    if !match_sequence(tokens, pattern, &mut captures) {
+        return None;
+    }
+    Some(Captures {
+        captures,
+        matched: tokens[..pattern.len()].to_vec(),
+    })
+}
+
+fn match_sequence(
+    tokens: &[TokenTree],
+    pattern: &[PatternToken],
+    captures: &mut Vec<(String, TokenTree)>,
+) -> bool {
+    for (i, expected) in pattern.iter().enumerate() {
+        let token = &tokens[i];
+        let matches = match expected {
+            PatternToken::Exact(expected) => {
+                let pattern_next = match pattern.get(i + 1) {
+                    Some(PatternToken::Exact(next)) => Some(next),
+                    _ => None,
+                };
+                leaf_eq(token, tokens.get(i + 1), expected, pattern_next)
+            }
+            PatternToken::Group(delimiter, inner) => match token {
+                TokenTree::Group(group) if group.delimiter() == *delimiter => {
+                    let contents: Vec<TokenTree> = group.stream().into_iter().collect();
+                    contents.len() == inner.len() && match_sequence(&contents, inner, captures)
+                }
+                _ => false,
+            },
+            PatternToken::Capture(name, kind) => {
+                let matches = kind.matches(token);
+                if matches {
+                    captures.push((name.clone(), token.clone()));
+                }
+                matches
+            }
+        };
+        if !matches {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,83 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Spacing, TokenTree};
+
+/// Check if `tokens` start with the (possibly multi-character) punctuation `puncts`, e.g. `"=>"`.
+///
+/// All but the last character have to be `Spacing::Joint`, so `"->"` doesn't match `- >`, and
+/// `puncts` must not be the start of a longer operator, so `"="` doesn't match `==` and `".."`
+/// doesn't match `..=`. Other punctuation may follow directly, as in `Vec::<u8>`, `&'a str` or
+/// `-> !`. Returns `false` for an empty `puncts`.
+/// ```ignore
+/// let tokens: Vec<TokenTree> = input.into_iter().collect();
+/// if tokens::is_punct_seq(&tokens[i..], "=>") { ... }
+/// ```
+pub(crate) fn is_punct_seq(tokens: &[TokenTree], puncts: &str) -> bool {
+    punct_seq_len(tokens, puncts).is_some()
+}
+
+/// Same as `is_punct_seq`, returning the number of tokens that make up `puncts`
+pub(crate) fn punct_seq_len(tokens: &[TokenTree], puncts: &str) -> Option<usize> {
+    let mut len = 0;
+    let mut last_joint = false;
+    for c in puncts.chars() {
+        // every character but the first has to be joined to the previous one
+        if len > 0 && !last_joint {
+            return None;
+        }
+        let TokenTree::Punct(punct) = tokens.get(len)? else {
+            return None;
+        };
+        if punct.as_char() != c {
+            return None;
+        }
+        last_joint = punct.spacing() == Spacing::Joint;
+        len += 1;
+    }
+    let continues = match tokens.get(len) {
+        Some(TokenTree::Punct(next)) if last_joint => {
+            let mut longer = puncts.to_string();
+            longer.push(next.as_char());
+            OPERATORS.contains(&longer.as_str())
+        }
+        _ => false,
+    };
+    (len > 0 && !continues).then_some(len)
+}
+
+/// The operators that consist of more than one punctuation character
+const OPERATORS: &[&str] = &[
+    "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=", "%=", "^=", "&=", "|=", "<<=", ">>=", "==",
+    "!=", ">=", "<=", "->", "=>", "<-", "::", "..", "...", "..=",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::TokenStream;
+
+    fn len(source: &str, puncts: &str) -> Option<usize> {
+        let tokens: Vec<TokenTree> = source.parse::<TokenStream>().unwrap().into_iter().collect();
+        punct_seq_len(&tokens, puncts)
+    }
+
+    #[test]
+    fn followed_by_other_punctuation() {
+        assert_eq!(len("::<u8>", "::"), Some(2));
+        assert_eq!(len("::*", "::"), Some(2));
+        assert_eq!(len("&'a str", "&"), Some(1));
+        assert_eq!(len("->!", "->"), Some(2));
+        assert_eq!(len("=>&x", "=>"), Some(2));
+    }
+
+    #[test]
+    fn longer_operators() {
+        assert_eq!(len("==", "="), None);
+        assert_eq!(len("..=", ".."), None);
+        assert_eq!(len("<<=", "<<"), None);
+        assert_eq!(len(":::", ":"), None);
+        assert_eq!(len("- >", "->"), None);
+        assert_eq!(len("= =", "="), Some(1));
+        assert_eq!(len("", ""), None);
+    }
+}
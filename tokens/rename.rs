@@ -0,0 +1,107 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Group, Ident, Spacing, TokenStream, TokenTree};
+use std::collections::HashMap;
+
+/// Which span a renamed identifier gets
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RenameSpan {
+    /// Keep the span of the identifier that was replaced
+    Original,
+    /// Use the span of the replacement identifier from the map
+    Replacement,
+    /// Located at the identifier that was replaced (for error messages), but resolved with the
+    /// hygiene of the replacement. This makes a placeholder in user code refer to a hygienic
+    /// (`Span::mixed_site()`) variable of the generated code.
+    Hygienic,
+}
+
+/// Options for `rename_idents_with`
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RenameOptions {
+    /// Which span the renamed identifiers get. Default: `RenameSpan::Hygienic`
+    pub span: RenameSpan,
+    /// Don't rename identifiers directly after a `.`, which are field or method names.
+    /// Default: `false`
+    pub skip_fields: bool,
+    /// Don't rename identifiers directly after a `::`, which are path segments that can't refer
+    /// to local variables. Default: `false`
+    pub skip_path_segments: bool,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self {
+            span: RenameSpan::Hygienic,
+            skip_fields: false,
+            skip_path_segments: false,
+        }
+    }
+}
+
+/// Replace all identifiers in the stream (including inside of groups) that are keys in `renames`
+/// with the corresponding values. Identifiers are matched by name, spans of the keys are ignored.
+/// ```ignore
+/// // move a method body into a free function, with `self` replaced by `this`
+/// let this = Ident::new("this", Span::mixed_site());
+/// let renames = HashMap::from([(Ident::new("self", Span::call_site()), this.clone())]);
+/// let body = tokens::rename_idents(method.block.to_token_stream(), &renames);
+/// quote! { fn helper(#this: &Foo) #body }
+/// ```
+pub(crate) fn rename_idents(stream: TokenStream, renames: &HashMap<Ident, Ident>) -> TokenStream {
+    rename_idents_with(stream, renames, RenameOptions::default())
+}
+
+/// Same as `rename_idents`, but with custom options
+pub(crate) fn rename_idents_with(
+    stream: TokenStream,
+    renames: &HashMap<Ident, Ident>,
+    options: RenameOptions,
+) -> TokenStream {
+    if renames.is_empty() {
+        return stream;
+    }
+    let mut out = Vec::new();
+    for token in stream {
+        let token = match token {
+            TokenTree::Ident(ident) if !is_skipped(&out, &options) => match renames.get(&ident) {
+                Some(replacement) => TokenTree::Ident(renamed(&ident, replacement, options)),
+                None => TokenTree::Ident(ident),
+            },
+            TokenTree::Group(group) => {
+                let stream = rename_idents_with(group.stream(), renames, options);
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group)
+            }
+            token => token,
+        };
+        out.push(token);
+    }
+    out.into_iter().collect()
+}
+
+fn is_skipped(previous: &[TokenTree], options: &RenameOptions) -> bool {
+    let is_punct = |index: usize, c: char| matches!(previous.get(index), Some(TokenTree::Punct(punct)) if punct.as_char() == c);
+    let len = previous.len();
+    if options.skip_fields && len >= 1 && is_punct(len - 1, '.') {
+        // `..x` is a range, not a field access
+        let is_range = len >= 2
+            && matches!(&previous[len - 2], TokenTree::Punct(p) if p.as_char() == '.' && p.spacing() == Spacing::Joint);
+        if !is_range {
+            return true;
+        }
+    }
+    options.skip_path_segments && len >= 2 && is_punct(len - 1, ':') && is_punct(len - 2, ':')
+}
+
+fn renamed(original: &Ident, replacement: &Ident, options: RenameOptions) -> Ident {
+    let span = match options.span {
+        RenameSpan::Original => original.span(),
+        RenameSpan::Replacement => replacement.span(),
+        RenameSpan::Hygienic => original.span().resolved_at(replacement.span()),
+    };
+    let mut ident = replacement.clone();
+    ident.set_span(span);
+    ident
+}
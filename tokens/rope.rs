@@ -0,0 +1,108 @@
+#![allow(dead_code)]
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+
+/// An accumulator for building large token streams from many pieces.
+///
+/// Every `extend` of a `proc_macro::TokenStream` is a call into the compiler that may copy the
+/// stream, so building the output of a macro token by token can become quadratic. `TokenRope`
+/// collects the pieces and concatenates them in one go when `finish` is called.
+/// ```ignore
+/// let mut out = TokenRope::new();
+/// for field in fields {
+///     out.push(generate_getter(field));
+/// }
+/// out.finish()
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct TokenRope {
+    fragments: Vec<TokenStream>,
+    /// Single trees pushed since the last fragment, collected into one fragment on the next push
+    trees: Vec<TokenTree>,
+}
+
+impl TokenRope {
+    /// Create an empty rope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a token stream
+    pub fn push(&mut self, stream: TokenStream) -> &mut Self {
+        if !stream.is_empty() {
+            self.flush_trees();
+            self.fragments.push(stream);
+        }
+        self
+    }
+    /// Append a single token tree
+    pub fn push_tree(&mut self, tree: TokenTree) -> &mut Self {
+        self.trees.push(tree);
+        self
+    }
+    /// Append anything that can be turned into tokens
+    pub fn push_tokens(&mut self, tokens: impl ToTokens) -> &mut Self {
+        self.push(tokens.into_token_stream())
+    }
+
+    /// Check if nothing was added yet (or only empty streams)
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty() && self.trees.is_empty()
+    }
+
+    /// Concatenate all pieces into a single stream
+    pub fn finish(mut self) -> TokenStream {
+        self.flush_trees();
+        match self.fragments.len() {
+            0 => TokenStream::new(),
+            1 => self.fragments.pop().unwrap_or_default(),
+            _ => self.fragments.into_iter().collect(),
+        }
+    }
+
+    fn flush_trees(&mut self) {
+        if !self.trees.is_empty() {
+            let stream = std::mem::take(&mut self.trees).into_iter().collect();
+            self.fragments.push(stream);
+        }
+    }
+}
+
+impl Extend<TokenTree> for TokenRope {
+    fn extend<I: IntoIterator<Item = TokenTree>>(&mut self, iter: I) {
+        self.trees.extend(iter);
+    }
+}
+
+impl Extend<TokenStream> for TokenRope {
+    fn extend<I: IntoIterator<Item = TokenStream>>(&mut self, iter: I) {
+        for stream in iter {
+            self.push(stream);
+        }
+    }
+}
+
+impl FromIterator<TokenStream> for TokenRope {
+    fn from_iter<I: IntoIterator<Item = TokenStream>>(iter: I) -> Self {
+        let mut rope = Self::new();
+        rope.extend(iter);
+        rope
+    }
+}
+
+impl ToTokens for TokenRope {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.fragments.iter().cloned());
+        tokens.extend(self.trees.iter().cloned());
+    }
+    fn into_token_stream(self) -> TokenStream {
+        self.finish()
+    }
+}
+
+impl From<TokenRope> for TokenStream {
+    fn from(rope: TokenRope) -> Self {
+        rope.finish()
+    }
+}
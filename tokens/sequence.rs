@@ -0,0 +1,71 @@
+#![allow(dead_code)]
+
+use super::super::ident::is_valid_ident;
+use super::super::join_spans;
+use super::punct_seq_len;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use std::ops::Range;
+
+/// A match found by `find_sequence`
+#[derive(Clone, Debug)]
+pub(crate) struct SequenceMatch {
+    /// The indices of the matched tokens in the top level of the stream, for use with
+    /// `tokens::splice`
+    pub range: Range<usize>,
+    /// The span of the matched tokens (or only the first one if spans can't be joined)
+    pub span: Span,
+}
+
+/// Find the first occurrence of a sequence of identifiers and punctuation at the top level of
+/// `stream`. Each element of `sequence` is either an identifier or (possibly multi-character)
+/// punctuation, which is matched with the rules of `tokens::is_punct_seq`:
+/// ```ignore
+/// // find `impl ... for` landmarks in code that isn't fully parsed
+/// if let Some(found) = tokens::find_sequence(&item, &["impl", "Trait", "for"]) { ... }
+/// let arrow = tokens::find_sequence(&signature, &["->"]);
+/// ```
+pub(crate) fn find_sequence(stream: &TokenStream, sequence: &[&str]) -> Option<SequenceMatch> {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    (0..tokens.len()).find_map(|i| match_at(&tokens, i, sequence))
+}
+
+/// Find all non-overlapping occurrences of a sequence at the top level of `stream`, see
+/// `find_sequence`
+pub(crate) fn find_all_sequences(stream: &TokenStream, sequence: &[&str]) -> Vec<SequenceMatch> {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    let mut found = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        match match_at(&tokens, i, sequence) {
+            Some(m) => {
+                i = m.range.end;
+                found.push(m);
+            }
+            None => i += 1,
+        }
+    }
+    found
+}
+
+fn match_at(tokens: &[TokenTree], start: usize, sequence: &[&str]) -> Option<SequenceMatch> {
+    if sequence.is_empty() {
+        return None;
+    }
+    let mut i = start;
+    for element in sequence {
+        if is_valid_ident(element) {
+            match tokens.get(i)? {
+                TokenTree::Ident(ident) if ident == element => i += 1,
+                _ => return None,
+            }
+        } else {
+            i += punct_seq_len(&tokens[i.min(tokens.len())..], element)?;
+        }
+    }
+    let first = tokens[start].span();
+    let span = join_spans(first, tokens[i - 1].span()).unwrap_or(first);
+    Some(SequenceMatch {
+        range: start..i,
+        span,
+    })
+}
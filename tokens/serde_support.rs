@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+
+use super::super::ident::{is_valid_ident, PATH_KEYWORDS};
+use proc_macro2::{Ident, Span};
+
+/// `#[serde(with = "tokens::serde_tokens")]` for `TokenStream`s, stored as their string form. Spans
+/// can't be serialized, so deserialized tokens have `Span::call_site()`.
+pub(crate) mod serde_tokens {
+    use proc_macro2::TokenStream;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        tokens: &TokenStream,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(tokens)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TokenStream, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| D::Error::custom(format!("invalid tokens `{}`", s)))
+    }
+}
+
+/// `Ident`s as their string form, with `r#` for raw identifiers. Deserialized identifiers have
+/// `Span::call_site()`.
+pub(crate) mod serde_ident {
+    use proc_macro2::Ident;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ident: &Ident, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(ident)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Ident, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::ident_from_str(&s)
+            .ok_or_else(|| D::Error::custom(format!("invalid identifier `{}`", s)))
+    }
+}
+
+/// `Option<Ident>`s, see `serde_ident`
+pub(crate) mod serde_option_ident {
+    use proc_macro2::Ident;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        ident: &Option<Ident>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match ident {
+            Some(ident) => serializer.serialize_some(&ident.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Ident>, D::Error> {
+        let Some(s) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        super::ident_from_str(&s)
+            .map(Some)
+            .ok_or_else(|| D::Error::custom(format!("invalid identifier `{}`", s)))
+    }
+}
+
+/// syn types (literals, paths, expressions, ...) as their string form, parsed again with `syn`
+/// when deserializing. Deserialized values have `Span::call_site()`.
+#[cfg(not(feature = "no-syn"))]
+pub(crate) mod serde_syn {
+    use quote::ToTokens;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: ToTokens, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&value.to_token_stream())
+    }
+    pub fn deserialize<'de, T: syn::parse::Parse, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        syn::parse_str(&s).map_err(|e| D::Error::custom(format!("invalid value `{}`: {}", s, e)))
+    }
+}
+
+fn ident_from_str(s: &str) -> Option<Ident> {
+    match s.strip_prefix("r#") {
+        Some(raw) if is_valid_ident(raw) && !PATH_KEYWORDS.contains(&raw) => {
+            Some(Ident::new_raw(raw, Span::call_site()))
+        }
+        None if is_valid_ident(s) => Some(Ident::new(s, Span::call_site())),
+        _ => None,
+    }
+}
@@ -0,0 +1,361 @@
+#![allow(dead_code)]
+
+use super::super::ident::{is_valid_ident, PATH_KEYWORDS};
+use super::super::{Error, Result};
+use super::TokenRope;
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Magic bytes and format version at the start of every serialized stream
+const HEADER: &[u8] = b"PMUT\x02";
+/// The first version, without locations, which can still be read
+const HEADER_V1: &[u8] = b"PMUT\x01";
+
+const TAG_GROUP: u8 = 0;
+const TAG_IDENT: u8 = 1;
+const TAG_PUNCT_ALONE: u8 = 2;
+const TAG_PUNCT_JOINT: u8 = 3;
+const TAG_LITERAL: u8 = 4;
+const TAG_END: u8 = 0xff;
+
+/// Where a token was when it was serialized: 1-based lines and 0-based columns, like
+/// `proc_macro2::LineColumn`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct SavedLocation {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+/// Encode a token stream into a compact byte format that can be written to a file, e.g. by a
+/// build script that precomputes code which a proc macro then embeds with `deserialize`.
+///
+/// Spans refer to a specific compiler session and can't be serialized, so `deserialize` gives
+/// all tokens the same span. With the `span-locations` feature (which has to enable
+/// `proc-macro2/span-locations`), the start and end line and column of every token are stored
+/// as well, which `deserialize_with_locations` returns. Without it, or if the spans have no
+/// location, no location is stored.
+pub(crate) fn serialize(stream: &TokenStream) -> Vec<u8> {
+    let mut out = HEADER.to_vec();
+    write_stream(&mut out, stream.clone());
+    out
+}
+
+/// Decode a token stream created by `serialize`, giving every token the span `span`. Returns an
+/// error at `span` if the bytes are not a valid serialized stream (e.g. from a different version
+/// of this module).
+pub(crate) fn deserialize(bytes: &[u8], span: Span) -> Result<TokenStream> {
+    Ok(deserialize_with_locations(bytes, span)?.0)
+}
+
+/// `deserialize`, plus the saved location of every token, in the order of a depth-first walk
+/// with every group before its contents. The locations are `None` if they weren't stored.
+pub(crate) fn deserialize_with_locations(
+    bytes: &[u8],
+    span: Span,
+) -> Result<(TokenStream, Vec<Option<SavedLocation>>)> {
+    let (mut rest, has_locations) = if let Some(rest) = bytes.strip_prefix(HEADER) {
+        (rest, true)
+    } else if let Some(rest) = bytes.strip_prefix(HEADER_V1) {
+        (rest, false)
+    } else {
+        return Error::err(span, "invalid serialized tokens: unknown format or version");
+    };
+    let mut reader = Reader {
+        span,
+        has_locations,
+        locations: vec![],
+    };
+    let stream = reader.read_stream(&mut rest, 0).map_err(|message| {
+        Error::new(
+            span,
+            format!(
+                "invalid serialized tokens at byte {}: {}",
+                bytes.len() - rest.len(),
+                message
+            ),
+        )
+    })?;
+    if !rest.is_empty() {
+        return Error::err(span, "invalid serialized tokens: unexpected trailing data");
+    }
+    Ok((stream, reader.locations))
+}
+
+fn write_stream(out: &mut Vec<u8>, stream: TokenStream) {
+    for token in stream {
+        match token {
+            TokenTree::Group(group) => {
+                out.push(TAG_GROUP);
+                out.push(match group.delimiter() {
+                    Delimiter::Parenthesis => b'(',
+                    Delimiter::Brace => b'{',
+                    Delimiter::Bracket => b'[',
+                    Delimiter::None => b' ',
+                });
+                write_location(out, group.span());
+                write_stream(out, group.stream());
+                out.push(TAG_END);
+            }
+            TokenTree::Ident(ident) => {
+                out.push(TAG_IDENT);
+                write_str(out, &ident.to_string());
+                write_location(out, ident.span());
+            }
+            TokenTree::Punct(punct) => {
+                out.push(match punct.spacing() {
+                    Spacing::Alone => TAG_PUNCT_ALONE,
+                    Spacing::Joint => TAG_PUNCT_JOINT,
+                });
+                let mut buf = [0; 4];
+                write_str(out, punct.as_char().encode_utf8(&mut buf));
+                write_location(out, punct.span());
+            }
+            TokenTree::Literal(literal) => {
+                out.push(TAG_LITERAL);
+                write_str(out, &literal.to_string());
+                write_location(out, literal.span());
+            }
+        }
+    }
+}
+
+/// Strings are stored as a LEB128 length followed by the UTF-8 bytes
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_number(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_number(out: &mut Vec<u8>, mut n: usize) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Locations are stored as the start line, start column, end line and end column. Line `0`
+/// means that there is no location, and then the other numbers are omitted.
+fn write_location(out: &mut Vec<u8>, span: Span) {
+    match location(span) {
+        Some(SavedLocation { start, end }) => {
+            for n in [start.0, start.1, end.0, end.1] {
+                write_number(out, n);
+            }
+        }
+        None => out.push(0),
+    }
+}
+
+#[cfg(feature = "span-locations")]
+fn location(span: Span) -> Option<SavedLocation> {
+    let (start, end) = (span.start(), span.end());
+    (start.line > 0).then_some(SavedLocation {
+        start: (start.line, start.column),
+        end: (end.line, end.column),
+    })
+}
+
+#[cfg(not(feature = "span-locations"))]
+fn location(_: Span) -> Option<SavedLocation> {
+    None
+}
+
+/// Maximum group nesting, to avoid stack overflows on corrupted input
+const MAX_DEPTH: usize = 256;
+
+struct Reader {
+    span: Span,
+    /// Whether the format version stores locations
+    has_locations: bool,
+    locations: Vec<Option<SavedLocation>>,
+}
+
+impl Reader {
+    fn read_stream(
+        &mut self,
+        bytes: &mut &[u8],
+        depth: usize,
+    ) -> std::result::Result<TokenStream, String> {
+        if depth > MAX_DEPTH {
+            return Err("groups are nested too deeply".to_string());
+        }
+        let span = self.span;
+        let mut out = TokenRope::new();
+        while let Some((&tag, rest)) = bytes.split_first() {
+            if tag == TAG_END {
+                if depth == 0 {
+                    return Err("unexpected end of group".to_string());
+                }
+                *bytes = rest;
+                return Ok(out.finish());
+            }
+            *bytes = rest;
+            let token = match tag {
+                TAG_GROUP => {
+                    let (&delimiter, rest) = bytes.split_first().ok_or("missing delimiter")?;
+                    *bytes = rest;
+                    let delimiter = match delimiter {
+                        b'(' => Delimiter::Parenthesis,
+                        b'{' => Delimiter::Brace,
+                        b'[' => Delimiter::Bracket,
+                        b' ' => Delimiter::None,
+                        other => return Err(format!("invalid delimiter {:#x}", other)),
+                    };
+                    self.read_location(bytes)?;
+                    let mut group = Group::new(delimiter, self.read_stream(bytes, depth + 1)?);
+                    group.set_span(span);
+                    TokenTree::Group(group)
+                }
+                TAG_IDENT => {
+                    let name = read_str(bytes)?;
+                    let ident = match name.strip_prefix("r#") {
+                        Some(raw) if is_valid_ident(raw) && !PATH_KEYWORDS.contains(&raw) => {
+                            Ident::new_raw(raw, span)
+                        }
+                        None if is_valid_ident(name) => Ident::new(name, span),
+                        _ => return Err(format!("invalid identifier `{}`", name)),
+                    };
+                    self.read_location(bytes)?;
+                    TokenTree::Ident(ident)
+                }
+                TAG_PUNCT_ALONE | TAG_PUNCT_JOINT => {
+                    let s = read_str(bytes)?;
+                    let mut chars = s.chars();
+                    let (Some(c), None) = (chars.next(), chars.next()) else {
+                        return Err(format!("invalid punctuation `{}`", s));
+                    };
+                    let spacing = if tag == TAG_PUNCT_JOINT {
+                        Spacing::Joint
+                    } else {
+                        Spacing::Alone
+                    };
+                    if !"!#$%&*+,-./:;<=>?@^|~'".contains(c) {
+                        return Err(format!("invalid punctuation `{}`", c));
+                    }
+                    let mut punct = Punct::new(c, spacing);
+                    punct.set_span(span);
+                    self.read_location(bytes)?;
+                    TokenTree::Punct(punct)
+                }
+                TAG_LITERAL => {
+                    let s = read_str(bytes)?;
+                    let mut literal: Literal =
+                        s.parse().map_err(|_| format!("invalid literal `{}`", s))?;
+                    literal.set_span(span);
+                    self.read_location(bytes)?;
+                    TokenTree::Literal(literal)
+                }
+                other => return Err(format!("invalid tag {:#x}", other)),
+            };
+            out.push_tree(token);
+        }
+        if depth > 0 {
+            return Err("unclosed group".to_string());
+        }
+        Ok(out.finish())
+    }
+
+    fn read_location(&mut self, bytes: &mut &[u8]) -> std::result::Result<(), String> {
+        let location = if !self.has_locations {
+            None
+        } else {
+            match read_number(bytes)? {
+                0 => None,
+                line => Some(SavedLocation {
+                    start: (line, read_number(bytes)?),
+                    end: (read_number(bytes)?, read_number(bytes)?),
+                }),
+            }
+        };
+        self.locations.push(location);
+        Ok(())
+    }
+}
+
+fn read_str<'a>(bytes: &mut &'a [u8]) -> std::result::Result<&'a str, String> {
+    let len = read_number(bytes)?;
+    if len > bytes.len() {
+        return Err("unexpected end of data".to_string());
+    }
+    let (s, rest) = bytes.split_at(len);
+    *bytes = rest;
+    std::str::from_utf8(s).map_err(|_| "invalid UTF-8".to_string())
+}
+
+fn read_number(bytes: &mut &[u8]) -> std::result::Result<usize, String> {
+    let mut n = 0usize;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first().ok_or("unexpected end of data")?;
+        *bytes = rest;
+        if shift >= usize::BITS {
+            return Err("invalid number".to_string());
+        }
+        n |= usize::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let stream: TokenStream = "fn f(x: &'static str) -> u8 { r#type::g!(x, 1.5e3) }"
+            .parse()
+            .unwrap();
+        let bytes = serialize(&stream);
+        let (restored, locations) = deserialize_with_locations(&bytes, Span::call_site())
+            .ok()
+            .unwrap();
+        assert_eq!(restored.to_string(), stream.to_string());
+        assert_eq!(locations.len(), 22);
+    }
+
+    #[test]
+    #[cfg(feature = "span-locations")]
+    fn locations() {
+        let stream: TokenStream = "a\n  (b c)".parse().unwrap();
+        let bytes = serialize(&stream);
+        let (_, locations) = deserialize_with_locations(&bytes, Span::call_site())
+            .ok()
+            .unwrap();
+        let locations: Vec<_> = locations.into_iter().map(Option::unwrap).collect();
+        let positions: Vec<_> = locations
+            .iter()
+            .map(|location| (location.start, location.end))
+            .collect();
+        // `a`, the group, `b` and `c`, relative to the first line of the source
+        let line = locations[0].start.0;
+        assert_eq!(
+            positions,
+            [
+                ((line, 0), (line, 1)),
+                ((line + 1, 2), (line + 1, 7)),
+                ((line + 1, 3), (line + 1, 4)),
+                ((line + 1, 5), (line + 1, 6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_version_1() {
+        // `a (b)` in the first format, without locations
+        let mut bytes = HEADER_V1.to_vec();
+        bytes.extend_from_slice(&[
+            TAG_IDENT, 1, b'a', TAG_GROUP, b'(', TAG_IDENT, 1, b'b', TAG_END,
+        ]);
+        let (stream, locations) = deserialize_with_locations(&bytes, Span::call_site())
+            .ok()
+            .unwrap();
+        assert_eq!(stream.to_string(), "a (b)");
+        assert_eq!(locations, [None, None, None]);
+    }
+}
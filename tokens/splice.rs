@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+use super::TokenRope;
+use proc_macro2::{TokenStream, TokenTree};
+use std::ops::Range;
+
+/// Replace the top-level tokens at `range` with `replacement`, keeping all other tokens
+/// (including their spans) exactly as they are. `range` usually comes from a matcher like
+/// `tokens::find_sequence`:
+/// ```ignore
+/// // turn the first `.unwrap()` into `.expect("...")`
+/// if let Some(found) = tokens::find_sequence(&body, &[".", "unwrap"]) {
+///     body = tokens::splice(body, found.range.start..found.range.end + 1, quote!(.expect("...")));
+/// }
+/// ```
+///
+/// Panics if `range` is out of bounds.
+pub(crate) fn splice(
+    stream: TokenStream,
+    range: Range<usize>,
+    replacement: TokenStream,
+) -> TokenStream {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    assert!(
+        range.start <= range.end && range.end <= tokens.len(),
+        "splice range {:?} is out of bounds for a stream of {} tokens",
+        range,
+        tokens.len()
+    );
+    let mut out = TokenRope::new();
+    out.extend(tokens[..range.start].iter().cloned());
+    out.push(replacement);
+    out.extend(tokens[range.end..].iter().cloned());
+    out.finish()
+}
+
+/// Same as `splice`, but for a range inside of a group. `path` contains the top-level indices of
+/// the groups to descend into, e.g. `&[3, 0]` for the first token of the group at index 3. The
+/// spans and delimiters of the groups are kept.
+///
+/// Panics if `path` doesn't lead to a group or `range` is out of bounds.
+pub(crate) fn splice_nested(
+    stream: TokenStream,
+    path: &[usize],
+    range: Range<usize>,
+    replacement: TokenStream,
+) -> TokenStream {
+    let Some((&first, rest)) = path.split_first() else {
+        return splice(stream, range, replacement);
+    };
+    let mut tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let Some(TokenTree::Group(group)) = tokens.get(first) else {
+        panic!("splice path index {} is not a group", first);
+    };
+    let mut new_group = proc_macro2::Group::new(
+        group.delimiter(),
+        splice_nested(group.stream(), rest, range, replacement),
+    );
+    new_group.set_span(group.span());
+    tokens[first] = TokenTree::Group(new_group);
+    tokens.into_iter().collect()
+}
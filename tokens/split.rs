@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Spacing, Span, TokenStream, TokenTree};
+
+/// Split a stream on a punctuation character, e.g. to separate the comma-separated arguments
+/// of a function-like macro without parsing them. Only separators at the top level of the stream
+/// are considered, so anything inside `()`, `[]` or `{}` stays intact. Separators that are part of
+/// a multi-character operator (`::` when splitting on `:`, `=>` when splitting on `=`) are
+/// ignored as well.
+///
+/// Each part is returned together with the span of the separator that ended it. The last part only
+/// has a separator if there is a trailing separator, in which case no empty part is added after
+/// it. Empty parts between two separators are kept.
+/// ```ignore
+/// for (arg, comma) in tokens::split_top_level(input, ',') {
+///     if arg.is_empty() {
+///         return Error::err(comma.unwrap_or_else(Span::call_site), "expected an argument");
+///     }
+///     ...
+/// }
+/// ```
+pub(crate) fn split_top_level(
+    stream: TokenStream,
+    separator: char,
+) -> Vec<(TokenStream, Option<Span>)> {
+    split(stream, separator, false)
+}
+
+/// Same as `split_top_level`, but also treats `<` and `>` as delimiters, so that generic arguments
+/// like `HashMap<K, V>` are not split. `->` and `=>` are not counted as closing brackets. This is
+/// only reliable for input that doesn't contain comparison operators, like lists of types.
+pub(crate) fn split_top_level_generics(
+    stream: TokenStream,
+    separator: char,
+) -> Vec<(TokenStream, Option<Span>)> {
+    split(stream, separator, true)
+}
+
+fn split(stream: TokenStream, separator: char, angles: bool) -> Vec<(TokenStream, Option<Span>)> {
+    let mut parts = vec![];
+    let mut current = vec![];
+    let mut angle_depth = 0usize;
+    // whether the previous token was a punct with Joint spacing, i.e. part of the same operator
+    let mut previous_joint: Option<char> = None;
+    for token in stream {
+        if let TokenTree::Punct(punct) = &token {
+            let c = punct.as_char();
+            // characters like `,` never form operators, even if they are directly followed by
+            // another punct (`a,-1`), so their spacing doesn't matter
+            let is_joint = punct.spacing() == Spacing::Joint && combines(c);
+            let part_of_operator = combines(c) && (is_joint || previous_joint.is_some());
+            if angles {
+                match c {
+                    '<' => angle_depth += 1,
+                    '>' if !matches!(previous_joint, Some('-') | Some('=')) => {
+                        angle_depth = angle_depth.saturating_sub(1);
+                    }
+                    _ => {}
+                }
+            }
+            if c == separator && !part_of_operator && angle_depth == 0 {
+                previous_joint = None;
+                parts.push((current.drain(..).collect(), Some(punct.span())));
+                continue;
+            }
+            previous_joint = if is_joint { Some(c) } else { None };
+        } else {
+            previous_joint = None;
+        }
+        current.push(token);
+    }
+    if !current.is_empty() {
+        parts.push((current.into_iter().collect(), None));
+    }
+    parts
+}
+
+/// Check if a punctuation character can be part of a multi-character operator
+fn combines(c: char) -> bool {
+    !matches!(c, ',' | ';' | '#' | '$' | '?' | '@' | '~')
+}
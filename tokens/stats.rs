@@ -0,0 +1,129 @@
+#![allow(dead_code)]
+
+use super::super::Warning;
+use super::{walk, TokenVisitor};
+use proc_macro2::{Group, Ident, Literal, Punct, Span, TokenStream};
+
+/// Size information about a `TokenStream`, see `stats`
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct TokenStats {
+    /// Number of token trees, counting each group and every token inside of it
+    pub tokens: usize,
+    /// Number of groups
+    pub groups: usize,
+    /// Deepest nesting of groups, 0 if there are no groups
+    pub max_depth: usize,
+    /// Approximate size in bytes of the stream when printed as source code
+    pub bytes: usize,
+}
+
+/// Count the tokens in a stream, including everything inside of groups
+pub(crate) fn stats(stream: &TokenStream) -> TokenStats {
+    let mut counter = StatsCounter::default();
+    walk(stream, &mut counter);
+    counter.stats
+}
+
+#[derive(Default)]
+struct StatsCounter {
+    stats: TokenStats,
+    depth: usize,
+}
+
+impl StatsCounter {
+    fn count(&mut self, bytes: usize) {
+        self.stats.tokens += 1;
+        // +1 for the separating space
+        self.stats.bytes += bytes + 1;
+    }
+}
+
+impl TokenVisitor for StatsCounter {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.count(ident.to_string().len());
+    }
+    fn visit_punct(&mut self, _punct: &Punct) {
+        self.count(1);
+    }
+    fn visit_literal(&mut self, literal: &Literal) {
+        self.count(literal.to_string().len());
+    }
+    fn enter_group(&mut self, _group: &Group) -> bool {
+        // both delimiters
+        self.count(3);
+        self.stats.groups += 1;
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+        true
+    }
+    fn leave_group(&mut self, _group: &Group) {
+        self.depth -= 1;
+    }
+}
+
+/// Limits for the size of generated code. Exceeding them doesn't break anything, but usually
+/// means that a macro generates far more code than intended, which slows down compilation for
+/// every user of the macro.
+/// ```ignore
+/// let output = generate(&input);
+/// if let Some(warning) = TokenBudget::default().check(&output, input.ident.span()) {
+///     output.extend(warning.into_token_stream());
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TokenBudget {
+    /// Maximum number of tokens. Default: 100 000
+    pub max_tokens: usize,
+    /// Maximum nesting depth. Default: 64
+    pub max_depth: usize,
+}
+
+impl Default for TokenBudget {
+    fn default() -> Self {
+        Self {
+            max_tokens: 100_000,
+            max_depth: 64,
+        }
+    }
+}
+
+impl TokenBudget {
+    /// A budget with the given maximum number of tokens and the default depth limit
+    pub fn tokens(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            ..Self::default()
+        }
+    }
+
+    /// Check the stream against the budget, returning a warning at `span` if it is exceeded
+    pub fn check(&self, stream: &TokenStream, span: Span) -> Option<Warning> {
+        self.check_stats(&stats(stream), span)
+    }
+    /// Same as `check`, for already computed stats
+    pub fn check_stats(&self, stats: &TokenStats, span: Span) -> Option<Warning> {
+        let mut problems = vec![];
+        if stats.tokens > self.max_tokens {
+            problems.push(format!(
+                "{} tokens (~{} KiB), the budget is {} tokens",
+                stats.tokens,
+                stats.bytes / 1024,
+                self.max_tokens
+            ));
+        }
+        if stats.max_depth > self.max_depth {
+            problems.push(format!(
+                "a nesting depth of {}, the budget is {}",
+                stats.max_depth, self.max_depth
+            ));
+        }
+        if problems.is_empty() {
+            return None;
+        }
+        let message = format!(
+            "this macro generated unusually large output: {}. This can slow down compilation significantly.",
+            problems.join(" and ")
+        );
+        Some(Warning::new(span, message))
+    }
+}
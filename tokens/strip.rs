@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+
+use super::TokenRope;
+use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
+
+/// An attribute found by `strip_attrs`. Can be serialized with the `serde` feature, without the
+/// span.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AttrRef {
+    /// The path of the attribute as written, without spaces: `doc`, `serde`, `my_crate::attr`
+    pub path: String,
+    /// Whether this is an inner attribute (`#![...]`)
+    pub inner: bool,
+    /// The contents of the brackets, including the path
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_tokens"))]
+    pub tokens: TokenStream,
+    /// The span of the `#`
+    #[cfg_attr(feature = "serde", serde(skip, default = "Span::call_site"))]
+    pub span: Span,
+}
+
+impl AttrRef {
+    /// Check if the attribute has the given path: `attr.is("doc")`
+    pub fn is(&self, path: &str) -> bool {
+        self.path == path
+    }
+}
+
+/// Remove all attributes (outer `#[...]` and inner `#![...]`) for which `filter` returns `true`,
+/// at any nesting level. Doc comments are `#[doc = "..."]` attributes in token streams, so they can
+/// be removed as well:
+/// ```ignore
+/// // remove doc comments and cfgs before hashing the input for caching
+/// let stripped = tokens::strip_attrs(input, |attr| attr.is("doc") || attr.is("cfg"));
+/// ```
+pub(crate) fn strip_attrs(
+    stream: TokenStream,
+    mut filter: impl FnMut(&AttrRef) -> bool,
+) -> TokenStream {
+    strip_level(stream, &mut filter)
+}
+
+/// Remove all doc comments and `#[doc = ...]` attributes
+pub(crate) fn strip_doc_comments(stream: TokenStream) -> TokenStream {
+    strip_attrs(stream, |attr| attr.is("doc"))
+}
+
+fn strip_level(stream: TokenStream, filter: &mut impl FnMut(&AttrRef) -> bool) -> TokenStream {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut out = TokenRope::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some((attr, len)) = attribute_at(&tokens[i..]) {
+            if !filter(&attr) {
+                out.extend(tokens[i..i + len].iter().cloned());
+            }
+            i += len;
+            continue;
+        }
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                let mut new_group =
+                    Group::new(group.delimiter(), strip_level(group.stream(), filter));
+                new_group.set_span(group.span());
+                out.push_tree(TokenTree::Group(new_group));
+            }
+            token => {
+                out.push_tree(token.clone());
+            }
+        }
+        i += 1;
+    }
+    out.finish()
+}
+
+/// Check if `tokens` start with an attribute, returning it and the number of tokens it spans
+fn attribute_at(tokens: &[TokenTree]) -> Option<(AttrRef, usize)> {
+    let TokenTree::Punct(hash) = tokens.first()? else {
+        return None;
+    };
+    if hash.as_char() != '#' {
+        return None;
+    }
+    let (inner, group_index) = match tokens.get(1)? {
+        TokenTree::Punct(bang) if bang.as_char() == '!' => (true, 2),
+        _ => (false, 1),
+    };
+    let TokenTree::Group(group) = tokens.get(group_index)? else {
+        return None;
+    };
+    if group.delimiter() != Delimiter::Bracket {
+        return None;
+    }
+    let path = group
+        .stream()
+        .into_iter()
+        .take_while(|token| match token {
+            TokenTree::Ident(_) => true,
+            TokenTree::Punct(punct) => punct.as_char() == ':',
+            _ => false,
+        })
+        .map(|token| token.to_string())
+        .collect();
+    let attr = AttrRef {
+        path,
+        inner,
+        tokens: group.stream(),
+        span: hash.span(),
+    };
+    Some((attr, group_index + 1))
+}
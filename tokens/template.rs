@@ -0,0 +1,107 @@
+#![allow(dead_code)]
+
+use super::super::{Error, Result};
+use super::{walk, walk_mut, TokenVisitor, TokenVisitorMut};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+
+/// A piece of code with `__name__` placeholders that are filled in at runtime.
+///
+/// Unlike `quote!`, the template doesn't need to be known at compile time of the macro, so it
+/// can come from an attribute value or a file provided by the user of the macro:
+/// ```ignore
+/// // #[my_derive(getter = "pub fn __name__(&self) -> &__ty__ { &self.__name__ }")]
+/// let template = Template::parse_str(&getter.value(), getter.span())?;
+/// for field in fields {
+///     output.extend(template.fill(&[
+///         ("name", field.ident.to_token_stream()),
+///         ("ty", field.ty.to_token_stream()),
+///     ])?);
+/// }
+/// ```
+#[derive(Clone)]
+pub(crate) struct Template {
+    tokens: TokenStream,
+    placeholders: Vec<Ident>,
+}
+
+impl Template {
+    /// Create a template from tokens
+    pub fn new(tokens: TokenStream) -> Self {
+        let mut collector = CollectPlaceholders(vec![]);
+        walk(&tokens, &mut collector);
+        Self {
+            tokens,
+            placeholders: collector.0,
+        }
+    }
+    /// Create a template from source code, e.g. the value of an attribute. All tokens get the
+    /// given span, and lexing errors are reported at it.
+    pub fn parse_str(source: &str, span: Span) -> Result<Self> {
+        Ok(Self::new(super::lex_at(source, span)?))
+    }
+
+    /// The names of all placeholders in the template, in order of their first occurrence
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec![];
+        for placeholder in &self.placeholders {
+            let name = placeholder_name(placeholder).unwrap_or_default();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Replace all placeholders with the corresponding tokens. Returns an error at every
+    /// placeholder that has no value. Values that are not used by the template are ignored.
+    pub fn fill(&self, values: &[(&str, TokenStream)]) -> Result<TokenStream> {
+        let mut error = Error::builder();
+        for placeholder in &self.placeholders {
+            let name = placeholder_name(placeholder).unwrap_or_default();
+            if !values.iter().any(|(n, _)| *n == name) {
+                error.with(
+                    placeholder.span(),
+                    format!("no value for placeholder `{}` in template", placeholder),
+                );
+            }
+        }
+        error.ok_or_build()?;
+        Ok(walk_mut(self.tokens.clone(), &mut Fill(values)))
+    }
+}
+
+/// The name of a `__name__` placeholder
+fn placeholder_name(ident: &Ident) -> Option<String> {
+    let ident = ident.to_string();
+    let name = ident.strip_prefix("__")?.strip_suffix("__")?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+struct CollectPlaceholders(Vec<Ident>);
+
+impl TokenVisitor for CollectPlaceholders {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if placeholder_name(ident).is_some() {
+            self.0.push(ident.clone());
+        }
+    }
+}
+
+struct Fill<'a>(&'a [(&'a str, TokenStream)]);
+
+impl TokenVisitorMut for Fill<'_> {
+    fn replace_token(&mut self, token: &TokenTree) -> Option<TokenStream> {
+        let TokenTree::Ident(ident) = token else {
+            return None;
+        };
+        let name = placeholder_name(ident)?;
+        self.0
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.clone())
+    }
+}
@@ -0,0 +1,134 @@
+#![allow(dead_code)]
+
+use super::TokenRope;
+use proc_macro2::{Group, Ident, Literal, Punct, Span, TokenStream, TokenTree};
+
+/// A visitor over all tokens of a `TokenStream`, including the contents of groups.
+/// All methods have empty default implementations, so only the relevant ones need to be
+/// implemented. Use `walk` to run the visitor.
+/// ```ignore
+/// struct CountIdents(usize);
+/// impl TokenVisitor for CountIdents {
+///     fn visit_ident(&mut self, _: &Ident) {
+///         self.0 += 1;
+///     }
+/// }
+/// ```
+pub(crate) trait TokenVisitor {
+    /// Called for every identifier
+    fn visit_ident(&mut self, _ident: &Ident) {}
+    /// Called for every punctuation character
+    fn visit_punct(&mut self, _punct: &Punct) {}
+    /// Called for every literal
+    fn visit_literal(&mut self, _literal: &Literal) {}
+    /// Called for every group before its contents are visited. Return `false` to skip the
+    /// contents.
+    fn enter_group(&mut self, _group: &Group) -> bool {
+        true
+    }
+    /// Called for every group after its contents were visited (or skipped)
+    fn leave_group(&mut self, _group: &Group) {}
+}
+
+/// A visitor that can modify, replace or remove the tokens of a `TokenStream`, including the
+/// contents of groups. All methods have default implementations that leave the tokens
+/// unchanged. Use `walk_mut` to run the visitor, and see `respan` for an example.
+pub(crate) trait TokenVisitorMut {
+    /// Called for every token before anything else. Return `Some` to replace the token with the
+    /// returned tokens (which can be empty to remove it). Replacement tokens are not visited.
+    fn replace_token(&mut self, _token: &TokenTree) -> Option<TokenStream> {
+        None
+    }
+    /// Called for every identifier
+    fn visit_ident_mut(&mut self, _ident: &mut Ident) {}
+    /// Called for every punctuation character
+    fn visit_punct_mut(&mut self, _punct: &mut Punct) {}
+    /// Called for every literal
+    fn visit_literal_mut(&mut self, _literal: &mut Literal) {}
+    /// Called for every group before its contents are visited. Return `false` to leave the
+    /// contents unchanged.
+    fn enter_group_mut(&mut self, _group: &Group) -> bool {
+        true
+    }
+    /// Called for every group after its contents were visited, with the new contents
+    fn visit_group_mut(&mut self, _group: &mut Group) {}
+}
+
+/// Run a visitor over all tokens in the stream, in order
+pub(crate) fn walk(stream: &TokenStream, visitor: &mut impl TokenVisitor) {
+    for token in stream.clone() {
+        match &token {
+            TokenTree::Group(group) => {
+                if visitor.enter_group(group) {
+                    walk(&group.stream(), visitor);
+                }
+                visitor.leave_group(group);
+            }
+            TokenTree::Ident(ident) => visitor.visit_ident(ident),
+            TokenTree::Punct(punct) => visitor.visit_punct(punct),
+            TokenTree::Literal(literal) => visitor.visit_literal(literal),
+        }
+    }
+}
+
+/// Run a mutating visitor over all tokens in the stream, in order, returning the modified stream.
+/// Groups keep their span and delimiter unless the visitor changes them.
+pub(crate) fn walk_mut(stream: TokenStream, visitor: &mut impl TokenVisitorMut) -> TokenStream {
+    let mut out = TokenRope::new();
+    for token in stream {
+        if let Some(replacement) = visitor.replace_token(&token) {
+            out.push(replacement);
+            continue;
+        }
+        let token = match token {
+            TokenTree::Group(group) => {
+                let mut group = if visitor.enter_group_mut(&group) {
+                    let mut new_group =
+                        Group::new(group.delimiter(), walk_mut(group.stream(), visitor));
+                    new_group.set_span(group.span());
+                    new_group
+                } else {
+                    group
+                };
+                visitor.visit_group_mut(&mut group);
+                TokenTree::Group(group)
+            }
+            TokenTree::Ident(mut ident) => {
+                visitor.visit_ident_mut(&mut ident);
+                TokenTree::Ident(ident)
+            }
+            TokenTree::Punct(mut punct) => {
+                visitor.visit_punct_mut(&mut punct);
+                TokenTree::Punct(punct)
+            }
+            TokenTree::Literal(mut literal) => {
+                visitor.visit_literal_mut(&mut literal);
+                TokenTree::Literal(literal)
+            }
+        };
+        out.push_tree(token);
+    }
+    out.finish()
+}
+
+/// Set the span of every token in the stream, including groups and their contents
+pub(crate) fn respan(stream: TokenStream, span: Span) -> TokenStream {
+    walk_mut(stream, &mut Respan(span))
+}
+
+struct Respan(Span);
+
+impl TokenVisitorMut for Respan {
+    fn visit_ident_mut(&mut self, ident: &mut Ident) {
+        ident.set_span(self.0);
+    }
+    fn visit_punct_mut(&mut self, punct: &mut Punct) {
+        punct.set_span(self.0);
+    }
+    fn visit_literal_mut(&mut self, literal: &mut Literal) {
+        literal.set_span(self.0);
+    }
+    fn visit_group_mut(&mut self, group: &mut Group) {
+        group.set_span(self.0);
+    }
+}
@@ -0,0 +1,117 @@
+#![allow(dead_code)]
+
+use super::super::Result;
+use proc_macro2::{Span, TokenStream};
+use std::fmt::Display;
+
+/// Build code as indented text and lex it into tokens at the end. For code where `quote!` is
+/// awkward, like long tables or code that is also shown in generated docs.
+/// ```ignore
+/// let mut w = CodeWriter::new();
+/// w.block("match value", |w| {
+///     for (i, name) in names.iter().enumerate() {
+///         w.line(format_args!("{} => \"{}\",", i, name));
+///     }
+///     w.line("_ => \"unknown\",");
+/// });
+/// let tokens = w.finish(input.span())?;
+/// ```
+#[derive(Clone)]
+pub(crate) struct CodeWriter {
+    buf: String,
+    indent: usize,
+    at_line_start: bool,
+}
+
+/// Characters per indentation level
+const INDENT: &str = "    ";
+
+impl CodeWriter {
+    /// Create an empty writer
+    pub fn new() -> Self {
+        Self {
+            buf: String::new(),
+            indent: 0,
+            at_line_start: true,
+        }
+    }
+
+    /// Write text without a line break. Indented if it starts a new line.
+    pub fn write(&mut self, text: impl Display) -> &mut Self {
+        for (i, line) in text.to_string().split('\n').enumerate() {
+            if i > 0 {
+                self.buf.push('\n');
+                self.at_line_start = true;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if self.at_line_start {
+                for _ in 0..self.indent {
+                    self.buf.push_str(INDENT);
+                }
+                self.at_line_start = false;
+            }
+            self.buf.push_str(line);
+        }
+        self
+    }
+    /// Write text followed by a line break
+    pub fn line(&mut self, text: impl Display) -> &mut Self {
+        self.write(text).write("\n")
+    }
+    /// Write an empty line
+    pub fn blank_line(&mut self) -> &mut Self {
+        self.write("\n")
+    }
+
+    /// Increase the indentation of the following lines
+    pub fn indent(&mut self) -> &mut Self {
+        self.indent += 1;
+        self
+    }
+    /// Decrease the indentation of the following lines
+    pub fn dedent(&mut self) -> &mut Self {
+        self.indent = self.indent.saturating_sub(1);
+        self
+    }
+    /// Write `header {`, the indented body and `}`
+    pub fn block(&mut self, header: impl Display, body: impl FnOnce(&mut Self)) -> &mut Self {
+        let header = header.to_string();
+        if header.is_empty() {
+            self.line("{");
+        } else {
+            self.line(format_args!("{} {{", header));
+        }
+        self.indent();
+        body(self);
+        self.dedent().line("}")
+    }
+
+    /// The code written so far
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+    /// The code written so far
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+
+    /// Lex the written code into tokens, all with the given span. Errors are reported at `span`
+    /// with the line and column inside of the written code.
+    pub fn finish(&self, span: Span) -> Result<TokenStream> {
+        super::lex_at(&self.buf, span)
+    }
+}
+
+impl Default for CodeWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for CodeWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.buf)
+    }
+}
@@ -0,0 +1,71 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Literal, Span};
+#[cfg(any(test, feature = "test-util"))]
+use std::cell::RefCell;
+use std::ops::Range;
+
+#[cfg(any(test, feature = "test-util"))]
+thread_local! {
+    static FORCED: RefCell<Option<Toolchain>> = const { RefCell::new(None) };
+}
+
+/// Which of the span APIs that only work on some compilers are used. `Span::join` always works
+/// on nightly but only sometimes on stable, and `Literal::subspan` is nightly-only, so code using
+/// them has a fallback path.
+///
+/// Normally, the APIs are tried and the fallback is used if they return `None`. Outside of rustc
+/// (in unit tests), proc-macro2 implements both if its `span-locations` feature is enabled, so a
+/// test only sees the nightly path. Forcing `Stable` with `scoped` makes `join_spans` and
+/// `lit_subspan` return `None`, so the fallback can be tested on the same toolchain:
+/// ```ignore
+/// Toolchain::Stable.scoped(|| {
+///     let error = validate_format(&lit).err().unwrap();
+///     // points at the whole literal instead of the placeholder
+/// });
+/// ```
+/// Only available in tests and with the `test-util` feature.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Toolchain {
+    /// Act as if the APIs are not available
+    Stable,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Toolchain {
+    /// The toolchain forced with `scoped`, if any
+    pub fn forced() -> Option<Toolchain> {
+        FORCED.with_borrow(|current| *current)
+    }
+    /// Force this toolchain while running `f`
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        super::scope::scoped(&FORCED, Some(self), f)
+    }
+}
+
+/// `first.join(last)`, or `None` if that isn't supported or `Toolchain::Stable` is forced
+pub(crate) fn join_spans(first: Span, last: Span) -> Option<Span> {
+    if forced_stable() {
+        return None;
+    }
+    first.join(last)
+}
+
+/// `lit.subspan(range)`, or `None` if that isn't supported or `Toolchain::Stable` is forced
+pub(crate) fn lit_subspan(lit: &Literal, range: Range<usize>) -> Option<Span> {
+    if forced_stable() {
+        return None;
+    }
+    lit.subspan(range)
+}
+
+#[cfg(any(test, feature = "test-util"))]
+fn forced_stable() -> bool {
+    Toolchain::forced() == Some(Toolchain::Stable)
+}
+
+#[cfg(not(any(test, feature = "test-util")))]
+fn forced_stable() -> bool {
+    false
+}
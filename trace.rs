@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+
+/// Run `f` inside of a `tracing` span for one phase of a macro, e.g. parsing the input or
+/// resolving attributes, so a subscriber can show where the expansion time goes. The builders of
+/// `codegen`, `AttrSchema` and `MacroEntry` add their own spans. Does nothing without the
+/// `tracing` feature.
+/// ```ignore
+/// let input: syn::DeriveInput = trace::phase("parse", || syn::parse2(input))?;
+/// let fields = trace::phase("fields", || collect_fields(&input))?;
+/// ```
+pub(crate) fn phase<R>(name: &str, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("phase", name).entered();
+    #[cfg(not(feature = "tracing"))]
+    let _ = name;
+    f()
+}
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use super::Warning;
+use proc_macro2::Span;
+use std::cell::RefCell;
+
+thread_local! {
+    static UNTRACKED: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+/// Reading environment variables with `env::tracked_var`, which tells the compiler about the
+/// dependency when possible, so a change to the variable recompiles the crates that use the
+/// macro.
+pub(crate) mod env {
+    /// `std::env::var`, but tracked by the compiler with the `nightly` feature. The macro crate
+    /// has to enable `#![feature(proc_macro_tracked_env)]` for that. Without the feature (or
+    /// outside of a macro), the read is recorded for `untracked_warnings`.
+    /// ```ignore
+    /// let level = env::tracked_var("MY_MACRO_LOG").unwrap_or_default();
+    /// ```
+    pub(crate) fn tracked_var(name: &str) -> Result<String, std::env::VarError> {
+        #[cfg(all(feature = "nightly", not(feature = "no-proc-macro")))]
+        if proc_macro::is_available() {
+            return proc_macro::tracked::env_var(name);
+        }
+        super::record(format!("the environment variable `{}`", name));
+        std::env::var(name)
+    }
+}
+
+/// Reading files with `path::tracked_read`, see `env`
+pub(crate) mod path {
+    use std::{io, path::Path};
+
+    /// `std::fs::read_to_string`, but tracked by the compiler with the `nightly` feature. The
+    /// macro crate has to enable `#![feature(proc_macro_tracked_path)]` for that. Without the
+    /// feature (or outside of a macro), the read is recorded for `untracked_warnings`.
+    ///
+    /// On stable, the macro can get the same effect for a file by emitting
+    /// `const _: &[u8] = include_bytes!("/absolute/path");`.
+    /// ```ignore
+    /// let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    /// let config = path::tracked_read(Path::new(&dir).join("my_macro.toml"))?;
+    /// ```
+    pub(crate) fn tracked_read(path: impl AsRef<Path>) -> io::Result<String> {
+        let path = path.as_ref();
+        track(path);
+        std::fs::read_to_string(path)
+    }
+    /// `std::fs::read`, see `tracked_read`
+    pub(crate) fn tracked_read_bytes(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let path = path.as_ref();
+        track(path);
+        std::fs::read(path)
+    }
+
+    fn track(path: &Path) {
+        #[cfg(all(feature = "nightly", not(feature = "no-proc-macro")))]
+        if proc_macro::is_available() {
+            proc_macro::tracked::path(path);
+            return;
+        }
+        super::record(format!("the file `{}`", path.display()));
+    }
+}
+
+fn record(what: String) {
+    UNTRACKED.with(|untracked| {
+        let mut untracked = untracked.borrow_mut();
+        if !untracked.contains(&what) {
+            untracked.push(what);
+        }
+    });
+}
+
+/// Warnings at `span` for the environment variables and files that were read with
+/// `env::tracked_var` and `path::tracked_read` but couldn't be tracked, each only once. Clears
+/// the recorded reads.
+pub(crate) fn untracked_warnings(span: Span) -> Vec<Warning> {
+    let untracked = UNTRACKED.with(|untracked| untracked.take());
+    untracked
+        .into_iter()
+        .map(|what| {
+            let message = format!(
+                "changes to {} are not tracked, so they don't rebuild this crate (tracking needs nightly Rust)",
+                what
+            );
+            Warning::new(span, message)
+        })
+        .collect()
+}
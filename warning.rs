@@ -0,0 +1,58 @@
+#![allow(dead_code)]
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote_spanned, ToTokens};
+use std::fmt::Display;
+
+/// A warning that can be emitted from a proc-macro on stable Rust.
+///
+/// Stable Rust has no API for proc macro warnings, so this uses the common workaround of emitting
+/// a deprecated constant and immediately using it, which makes rustc print the message as a
+/// deprecation warning at the given span. The generated tokens are an item, so they have to be
+/// emitted in item position.
+pub(crate) struct Warning {
+    span: Span,
+    message: String,
+}
+
+impl Warning {
+    /// Create a new warning with a message and a span
+    pub fn new(span: Span, message: impl Display) -> Self {
+        Self {
+            span,
+            message: message.to_string(),
+        }
+    }
+    /// Create a new warning with a message and the span of the first token in `tokens`
+    pub fn new_spanned(tokens: impl ToTokens, message: impl Display) -> Self {
+        let span = tokens
+            .into_token_stream()
+            .into_iter()
+            .next()
+            .map_or_else(Span::call_site, |token| token.span());
+        Self::new(span, message)
+    }
+
+    /// The span the warning points at
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    /// The message of the warning
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl ToTokens for Warning {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let message = &self.message;
+        tokens.extend(quote_spanned! {self.span=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                #[allow(non_upper_case_globals)]
+                const macro_warning: () = ();
+                macro_warning
+            };
+        });
+    }
+}